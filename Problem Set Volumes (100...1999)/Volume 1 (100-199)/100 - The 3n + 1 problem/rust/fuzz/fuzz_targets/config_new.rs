@@ -0,0 +1,46 @@
+//! Feeds arbitrary byte strings to `Config::new` and
+//! `Config::new_lenient`.
+//!
+//! Run with `cargo fuzz run config_new` from the crate directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust::Config;
+
+fuzz_target!(|data: &[u8]| {
+    // `Config::new` takes a `&str`, so only valid UTF-8 can reach it.
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    let valid = tokens.len() == 2 && tokens.iter().all(|t| t.parse::<u32>().is_ok());
+    
+    match Config::new(input) {
+        Ok(config) => {
+            // Anything accepted must be exactly two unsigned integers,
+            // and those must be what ended up in the `Config`.
+            assert!(valid, "accepted invalid input {:?}", input);
+            assert_eq!(config.inputs, tokens);
+            assert_eq!(config.i, tokens[0].parse::<u32>().unwrap());
+            assert_eq!(config.j, tokens[1].parse::<u32>().unwrap());
+        },
+        Err(error) => {
+            // Anything rejected must actually be invalid, and the reason
+            // must be reported rather than swallowed.
+            assert!(!valid, "rejected valid input {:?}", input);
+            assert!(!error.is_empty(), "empty error for input {:?}", input);
+        },
+    }
+    
+    // The lenient parser only differs on blank and `#` comment lines.
+    let trimmed = input.trim();
+    match Config::new_lenient(input) {
+        Ok(None) => assert!(trimmed.is_empty() || trimmed.starts_with('#')),
+        Ok(Some(config)) => assert_eq!(Config::new(input), Ok(config)),
+        Err(error) => assert_eq!(Config::new(input), Err(error)),
+    }
+});
@@ -0,0 +1,22 @@
+//! Feeds arbitrary `u32`s to the cycle length functions, checking that
+//! none of them panic and that they all agree with one another.
+//!
+//! Run with `cargo fuzz run cycles` from the crate directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use rust::{cycle_length, cycles, peak_and_length, reference, trajectory, CycleCache};
+
+fuzz_target!(|n: u32| {
+    let length = cycle_length(n);
+    
+    assert_eq!(CycleCache::new().cycle_length(n), length);
+    assert_eq!(reference::cycle_length(n), length);
+    
+    let (_, trajectory_length) = peak_and_length(trajectory(n));
+    assert_eq!(trajectory_length, length);
+    
+    assert_eq!(cycles(n), trajectory(n).collect::<Vec<u64>>());
+});
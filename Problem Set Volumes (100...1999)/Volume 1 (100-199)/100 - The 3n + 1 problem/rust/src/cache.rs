@@ -0,0 +1,236 @@
+//! `cache` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-05-30
+//!
+//! A memoizing cache of cycle lengths. Neighbouring inputs share most of
+//! their sequences, so remembering the cycle length of every value seen
+//! so far turns a range query into a handful of steps per input.
+
+use std::collections::BTreeMap;
+
+use crate::{ordered_range, RangeError, INPUT_LIMIT};
+
+/// Counters describing how effective a `CycleCache` has been.
+///
+/// A lookup is a call to `CycleCache::cycle_length()` (including those
+/// made by the range queries). It is a hit when the cycle length of the
+/// requested value was already cached, and a miss when the sequence had
+/// to be walked.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    /// The number of lookups answered straight from the cache.
+    pub hits: u64,
+    
+    /// The number of lookups that had to walk the sequence.
+    pub misses: u64,
+    
+    /// The number of values whose cycle length is currently cached.
+    pub entries: usize,
+}
+
+/// A `CycleCache` that remembers the cycle length of every value below
+/// `INPUT_LIMIT` that it has computed.
+///
+/// The cache table grows on demand to cover the largest input queried,
+/// so a cache used only for small ranges stays small.
+///
+/// ```
+/// use rust::CycleCache;
+///
+/// let mut cache = CycleCache::new();
+/// assert_eq!(cache.cycle_length(22), 16);
+/// assert_eq!(cache.max_cycles_in_range(1, 10), Ok(20));
+/// ```
+#[derive(Debug, Default)]
+pub struct CycleCache {
+    /// Cycle lengths indexed by `n`. A value of `0` means the cycle
+    /// length of `n` has not been computed yet.
+    lengths: Vec<u32>,
+    
+    /// Scratch space holding the sequence values visited while
+    /// computing a cycle length, reused between calls.
+    path: Vec<u64>,
+    
+    /// Hit, miss, and entry counters. See `CycleCache::stats()`.
+    stats: CacheStats,
+}
+
+impl CycleCache {
+    /// Return a new, empty `CycleCache`.
+    pub fn new() -> CycleCache {
+        CycleCache::default()
+    }
+    
+    /// Returns the cycle length of `n`, consulting and filling the cache
+    /// along the way. The cycle length of `0` is undefined, so `0` is
+    /// returned for it.
+    pub fn cycle_length(&mut self, n: u32) -> u32 {
+        if n == 0 {
+            return 0;
+        }
+        
+        // Grow the table so that `n` itself can be cached.
+        let wanted = (n as usize + 1).min(INPUT_LIMIT as usize);
+        if self.lengths.len() < wanted {
+            let seeded = !self.lengths.is_empty();
+            self.lengths.resize(wanted, 0);
+            if !seeded {
+                self.lengths[1] = 1;
+                self.stats.entries += 1;
+            }
+        }
+        
+        // Answer straight from the cache if we can.
+        let known = self.lengths.get(n as usize).cloned().unwrap_or(0);
+        if known != 0 {
+            self.stats.hits += 1;
+            return known;
+        }
+        self.stats.misses += 1;
+        
+        // Walk the sequence until we reach a value whose cycle length
+        // is already known, remembering every value along the way.
+        self.path.clear();
+        let mut value = n as u64;
+        let mut length = loop {
+            if let Some(&known) = self.lengths.get(value as usize) {
+                if known != 0 {
+                    break known;
+                }
+            }
+            self.path.push(value);
+            if value.is_multiple_of(2) { // value is even
+                value /= 2;
+            }
+            else { // value is odd
+                value = (3 * value) + 1;
+            }
+        };
+        
+        // Unwind the path, recording the length of each value that
+        // fits in the table.
+        for &value in self.path.iter().rev() {
+            length += 1;
+            if let Some(slot) = self.lengths.get_mut(value as usize) {
+                if *slot == 0 {
+                    self.stats.entries += 1;
+                }
+                *slot = length;
+            }
+        }
+        length
+    }
+    
+    /// Returns the hit, miss, and entry counters of this cache.
+    ///
+    /// ```
+    /// use rust::CycleCache;
+    ///
+    /// let mut cache = CycleCache::new();
+    /// cache.cycle_length(22); // miss, caches 22, 11, 17, 13, 20, 10, ...
+    /// cache.cycle_length(22); // hit
+    /// cache.cycle_length(11); // hit
+    ///
+    /// let stats = cache.stats();
+    /// assert_eq!((stats.hits, stats.misses), (2, 1));
+    /// assert_eq!(stats.entries, 12);
+    /// ```
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+    
+    /// Returns the maximum cycle length for all integers between `min`
+    /// and `max` (inclusive). See `max_cycles_in_range()`.
+    pub fn max_cycles_in_range(&mut self, min: u32, max: u32) -> Result<u32, RangeError> {
+        let (min, max) = ordered_range(min, max)?;
+        Ok((min..=max).map(|n| self.cycle_length(n)).max().unwrap_or(0))
+    }
+    
+    /// Returns a histogram mapping each cycle length to the number of
+    /// integers between `min` and `max` (inclusive) having that cycle
+    /// length. See `cycle_histogram()`.
+    pub fn cycle_histogram(
+        &mut self,
+        min: u32,
+        max: u32
+    ) -> Result<BTreeMap<u32, u32>, RangeError> {
+        let (min, max) = ordered_range(min, max)?;
+        let mut histogram = BTreeMap::new();
+        
+        for n in min..=max {
+            *histogram.entry(self.cycle_length(n)).or_insert(0) += 1;
+        }
+        Ok(histogram)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cycle_length;
+    
+    #[test]
+    fn cache_agrees_with_cycle_length() {
+        let mut cache = CycleCache::new();
+        
+        // Query in descending order so that later lookups hit values
+        // cached by earlier ones.
+        for n in (1..5000).rev() {
+            assert_eq!(cache.cycle_length(n), cycle_length(n));
+        }
+        assert_eq!(cache.cycle_length(0), 0);
+        assert_eq!(cache.cycle_length(704_511), 243);
+        assert_eq!(cache.cycle_length(u32::MAX), cycle_length(u32::MAX));
+    }
+    
+    #[test]
+    fn cache_stats() {
+        let mut cache = CycleCache::new();
+        assert_eq!(cache.stats(), CacheStats::default());
+        
+        cache.cycle_length(0);
+        assert_eq!(cache.stats(), CacheStats::default());
+        
+        // 1 is seeded into the table, so it is a hit.
+        cache.cycle_length(1);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 0, entries: 1 });
+        
+        // 10, 5, 8, 4, 2 are new and fit in the table, 16 does not.
+        cache.cycle_length(10);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1, entries: 6 });
+        
+        // 3 is new, but its successor 10 is already cached.
+        cache.cycle_length(3);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2, entries: 7 });
+        
+        cache.max_cycles_in_range(1, 10).unwrap();
+        let stats = cache.stats();
+        assert_eq!(stats.hits + stats.misses, 3 + 10);
+        assert_eq!(stats.hits, 1 + 7);
+        assert_eq!(stats.entries, 10);
+    }
+    
+    #[test]
+    fn cache_histogram() {
+        let mut cache = CycleCache::new();
+        let histogram = cache.cycle_histogram(1, 10).unwrap();
+        
+        // 1:1, 2:2, 3:8, 4:3, 5:6, 6:9, 7:17, 8:4, 9:20, 10:7
+        let expected: BTreeMap<u32, u32> = [
+            (1, 1), (2, 1), (3, 1), (4, 1), (6, 1),
+            (7, 1), (8, 1), (9, 1), (17, 1), (20, 1),
+        ].iter().cloned().collect();
+        assert_eq!(histogram, expected);
+        
+        let histogram = cache.cycle_histogram(1000, 1).unwrap();
+        assert_eq!(histogram.values().sum::<u32>(), 1000);
+        assert_eq!(histogram.keys().max(), Some(&179));
+        
+        assert_eq!(
+            cache.cycle_histogram(0, 10),
+            Err(RangeError::OutOfDomain(0))
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! `ffi` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-05-30
+//!
+//! C-compatible exports of the 3n + 1 computation, enabled by the `ffi`
+//! feature. Build the shared library with:
+//!
+//! ```text
+//! cargo build --release --features ffi
+//! ```
+//!
+//! and declare the functions on the C side as:
+//!
+//! ```c
+//! #include <stdint.h>
+//!
+//! uint32_t uva100_max_cycles(uint32_t i, uint32_t j);
+//! uint32_t uva100_cycle_length(uint32_t n);
+//! ```
+
+use crate::{cycle_length, max_cycles_in_range};
+
+/// Returns the maximum cycle length for all integers between `i` and `j`
+/// (inclusive), in either order.
+///
+/// C has no `Result`, so `0` is returned when `i` or `j` is outside of the
+/// problem domain `0 < n < 1,000,000`. No valid range has a maximum cycle
+/// length of `0`.
+#[no_mangle]
+pub extern "C" fn uva100_max_cycles(i: u32, j: u32) -> u32 {
+    max_cycles_in_range(i, j).unwrap_or(0)
+}
+
+/// Returns the cycle length of `n`, or `0` when `n` is `0`.
+#[no_mangle]
+pub extern "C" fn uva100_cycle_length(n: u32) -> u32 {
+    cycle_length(n)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn ffi_max_cycles() {
+        assert_eq!(uva100_max_cycles(1, 10), 20);
+        assert_eq!(uva100_max_cycles(210, 201), 89);
+        assert_eq!(uva100_max_cycles(0, 10), 0);
+        assert_eq!(uva100_max_cycles(1, 1_000_000), 0);
+    }
+    
+    #[test]
+    fn ffi_cycle_length() {
+        assert_eq!(uva100_cycle_length(22), 16);
+        assert_eq!(uva100_cycle_length(0), 0);
+    }
+}
@@ -4,6 +4,26 @@
 //!
 //! Date: 2020-05-30
 
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub mod cache;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod reference;
+pub mod trajectory;
+pub mod verify;
+
+pub use cache::{CacheStats, CycleCache};
+pub use trajectory::{peak_and_length, trajectory, Trajectory};
+pub use verify::{verify, Mismatch};
+
+/// The exclusive upper bound of the problem's input domain. The problem
+/// defines every input `n` as `0 < n < 1,000,000`.
+pub const INPUT_LIMIT: u32 = 1_000_000;
+
 /// A `Config` that holds the configuration of the input parameters.
 ///
 /// Given an input of `Config::new("1 10")`, it will return
@@ -43,6 +63,8 @@ impl<'a> Config<'a> {
     /// An invalid config yields:
     ///
     /// ```
+    /// use rust::Config;
+    ///
     /// // Invalid config.
     /// let config = match Config::new("asdf asdf\r\n") {
     ///     Ok(config) => config,
@@ -88,9 +110,46 @@ impl<'a> Config<'a> {
         
         Ok(Config { inputs, i, j })
     }
+    
+    /// Parse `input` like `Config::new()`, but skip lines that carry no
+    /// query: blank lines and lines starting with `#` yield `Ok(None)`.
+    /// As with `Config::new()`, any mix of spaces and tabs is tolerated
+    /// around and between the two integers.
+    ///
+    /// ```
+    /// use rust::Config;
+    ///
+    /// assert_eq!(Config::new_lenient("  # 1 10 is the first sample\n"), Ok(None));
+    /// assert_eq!(Config::new_lenient("\t\r\n"), Ok(None));
+    ///
+    /// let config = Config::new_lenient("\t1 \t 10  \n").unwrap().unwrap();
+    /// assert_eq!((config.i, config.j), (1, 10));
+    /// ```
+    pub fn new_lenient(input: &'a str) -> Result<Option<Config<'a>>, String> {
+        let trimmed = input.trim();
+        
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            return Ok(None);
+        }
+        
+        Config::new(input).map(Some)
+    }
 }
 
-/// Returns a `Vec<u32>` containing the elements of the cycle length
+/// How strictly `run_with_mode()` treats its input lines.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ParseMode {
+    /// Every line must be a query (or `q`/`quit`). Anything else is
+    /// reported as an error. This is the judge's input format.
+    #[default]
+    Strict,
+    
+    /// Blank lines and lines starting with `#` are silently skipped, so
+    /// annotated, hand-written test files can be fed in directly.
+    Lenient,
+}
+
+/// Returns a `Vec<u64>` containing the elements of the cycle length
 /// of `n` with respect to the "3n + 1 problem".
 ///
 /// Consider the following algorithm:
@@ -111,27 +170,121 @@ impl<'a> Config<'a> {
 ///     vec![22, 11, 34, 17, 52, 26, 13, 40, 20, 10, 5, 16, 8, 4, 2, 1],
 /// );
 /// ```
-pub fn cycles(mut n: u32) -> Vec<u32> {
-    // This is where we'll store the cycle length values.
-    let mut cycle_vec = Vec::<u32>::new();
+///
+/// Elements are `u64`, as `trajectory()`'s are, because the sequences
+/// of some inputs below 1,000,000 climb above `u32::MAX` (e.g.,
+/// `704511`), and the sequence of any `u32` fits in a `u64`. The
+/// sequence of `0` is undefined (it never reaches `1`), so an empty
+/// `Vec` is returned for it.
+pub fn cycles(n: u32) -> Vec<u64> {
+    trajectory(n).collect()
+}
+
+/// Returns the cycle length of `n` with respect to the "3n + 1 problem"
+/// without storing the intermediate values.
+///
+/// This is equivalent to `cycles(n).len()`, but the sequence is computed
+/// using `u64` arithmetic so that inputs whose sequences climb above
+/// `u32::MAX` (e.g., `704511`) do not overflow. The cycle length of `0`
+/// is undefined, so `0` is returned for it.
+///
+/// ```
+/// use rust::cycle_length;
+/// assert_eq!(cycle_length(22), 16);
+/// assert_eq!(cycle_length(1), 1);
+/// ```
+pub fn cycle_length(n: u32) -> u32 {
+    if n == 0 {
+        return 0;
+    }
     
-    // Loop until `n == 1`.
-    loop {
-        if n == 1 {
-            cycle_vec.push(1);
-            break;
+    let mut n = n as u64;
+    let mut length = 1;
+    
+    while n != 1 {
+        if n.is_multiple_of(2) { // n is even
+            n /= 2;
         }
-        else {
-            cycle_vec.push(n);
-            if n % 2 == 0 { // n is even
-                n /= 2;
-            }
-            else { // n is odd
-                n = (3 * n) + 1
-            }
+        else { // n is odd
+            n = (3 * n) + 1;
         }
+        length += 1;
     }
-    cycle_vec
+    length
+}
+
+/// An error indicating that a range of inputs (or the `Config` holding
+/// them) could not be evaluated.
+#[derive(Debug, PartialEq)]
+pub enum RangeError {
+    /// The `Config` holds no inputs.
+    EmptyConfig,
+    
+    /// A range endpoint is outside of the problem domain
+    /// `0 < n < 1,000,000`. Holds the offending value.
+    OutOfDomain(u32),
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RangeError::EmptyConfig => write!(f, "no input parameters were given"),
+            RangeError::OutOfDomain(n) => write!(
+                f, "{} is outside of the domain 0 < n < {}", n, INPUT_LIMIT
+            ),
+        }
+    }
+}
+
+impl Error for RangeError {}
+
+/// Validates that `min` and `max` are both within the problem domain
+/// `0 < n < 1,000,000` and returns them arranged into their
+/// `(min, max)` values.
+pub(crate) fn ordered_range(min: u32, max: u32) -> Result<(u32, u32), RangeError> {
+    for &n in &[min, max] {
+        if n == 0 || n >= INPUT_LIMIT {
+            return Err(RangeError::OutOfDomain(n));
+        }
+    }
+    
+    if min <= max { Ok((min, max)) } else { Ok((max, min)) }
+}
+
+/// Returns the maximum cycle length for all integers between `min` and
+/// `max` (inclusive).
+///
+/// Unlike `max_cycles()`, no `Config` is needed. The endpoints may be
+/// given in either order, and each must be within the problem domain
+/// `0 < n < 1,000,000`, else a `RangeError` is returned.
+///
+/// ```
+/// use rust::{max_cycles_in_range, RangeError};
+/// assert_eq!(max_cycles_in_range(1, 10), Ok(20));
+/// assert_eq!(max_cycles_in_range(210, 100), Ok(125));
+/// assert_eq!(max_cycles_in_range(0, 10), Err(RangeError::OutOfDomain(0)));
+/// ```
+pub fn max_cycles_in_range(min: u32, max: u32) -> Result<u32, RangeError> {
+    CycleCache::new().max_cycles_in_range(min, max)
+}
+
+/// Returns a histogram mapping each cycle length to the number of
+/// integers between `min` and `max` (inclusive) having that cycle
+/// length.
+///
+/// The endpoints follow the same rules as `max_cycles_in_range()`. Use
+/// `CycleCache::cycle_histogram()` to share one cache across several
+/// queries.
+///
+/// ```
+/// use rust::cycle_histogram;
+///
+/// let histogram = cycle_histogram(1, 10).unwrap();
+/// assert_eq!(histogram.get(&20), Some(&1));
+/// assert_eq!(histogram.values().sum::<u32>(), 10);
+/// ```
+pub fn cycle_histogram(min: u32, max: u32) -> Result<BTreeMap<u32, u32>, RangeError> {
+    CycleCache::new().cycle_histogram(min, max)
 }
 
 /// Returns a 3-tuple of the initial `Config` parameter `i`, and `j`,
@@ -143,42 +296,164 @@ pub fn cycles(mut n: u32) -> Vec<u32> {
 /// (inclusive) cycles. This `Config` returns `(1, 10, 20)`:
 ///
 /// ```
-/// use rust::max_cycles;
-/// let config = match Config::new("1 10\r\n") {
-///     Ok(config) => config,
-///     Err(error) => Config { inputs: vec![], i: 0, j: 0 },
-/// };
-/// let (i, j, result) = max_cycles(config);
+/// use rust::{Config, max_cycles};
+/// let config = Config::new("1 10\r\n").unwrap();
+/// let (i, j, result) = max_cycles(config).unwrap();
 /// assert_eq!(i, 1);
 /// assert_eq!(j, 10);
 /// assert_eq!(result, 20);
 /// ```
-pub fn max_cycles(config: Config) -> (u32, u32, u32) {
-    // We store the maximum size of each cycle length here.
-    let mut result: usize = 0;
-    
-    // If we have invalid inputs, return (0, 0, 0) 3-tuple.
-    if config.inputs.len() == 0 || (config.i == 0 && config.j == 0) {
-        return (0, 0, 0);
+///
+/// A `Config` without any inputs, or whose `i` or `j` is outside of the
+/// problem domain `0 < n < 1,000,000`, yields a `RangeError`:
+///
+/// ```
+/// use rust::{Config, max_cycles, RangeError};
+/// let config = Config { inputs: vec![], i: 0, j: 0 };
+/// assert_eq!(max_cycles(config), Err(RangeError::EmptyConfig));
+///
+/// let config = Config::new("0 10").unwrap();
+/// assert_eq!(max_cycles(config), Err(RangeError::OutOfDomain(0)));
+/// ```
+pub fn max_cycles(config: Config) -> Result<(u32, u32, u32), RangeError> {
+    // A `Config` without inputs was never parsed from anything.
+    if config.inputs.is_empty() {
+        return Err(RangeError::EmptyConfig);
     }
     
-    // Arrange the input parameters `i` and `j` into their
-    // `(min, max)` values.
-    let (min, max) = match config.i < config.j {
-        true => (config.i, config.j),
-        false => (config.j, config.i),
-    };
+    // Compute the maximum cycle length between `i` and `j`
+    // (inclusive), validating both against the problem domain.
+    let result = max_cycles_in_range(config.i, config.j)?;
+    
+    Ok((config.i, config.j, result))
+}
+
+/// The result of a `max_cycles()` computation, which formats itself
+/// exactly as the judge expects to see it: `"i j result"`.
+///
+/// ```
+/// use rust::{Config, max_cycles, MaxCyclesResult};
+///
+/// let config = Config::new("1 10").unwrap();
+/// let result = MaxCyclesResult::from(max_cycles(config).unwrap());
+/// assert_eq!(result.to_string(), "1 10 20");
+///
+/// let mut output = Vec::new();
+/// result.write_to(&mut output).unwrap();
+/// assert_eq!(output, b"1 10 20\n");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxCyclesResult {
+    /// The first input parameter, as given.
+    pub i: u32,
+    
+    /// The second input parameter, as given.
+    pub j: u32,
+    
+    /// The maximum cycle length for all integers between `i` and `j`
+    /// (inclusive).
+    pub result: u32,
+}
+
+impl MaxCyclesResult {
+    /// Write the result line, terminated by a newline, to `out`.
+    pub fn write_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        writeln!(out, "{}", self)
+    }
+}
+
+impl From<(u32, u32, u32)> for MaxCyclesResult {
+    fn from((i, j, result): (u32, u32, u32)) -> MaxCyclesResult {
+        MaxCyclesResult { i, j, result }
+    }
+}
+
+impl fmt::Display for MaxCyclesResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.i, self.j, self.result)
+    }
+}
+
+/// Read `"i j"` query lines from `input` until EOF or a `q`/`quit` line,
+/// writing one `"i j result"` line per query to `output`.
+///
+/// A single line buffer and a single `CycleCache` are reused for every
+/// query, so multi-megabyte query files are processed without
+/// allocating per line. `output` is only flushed once all queries have
+/// been answered; wrap it in an `io::BufWriter` to avoid a write per
+/// line. Lines that cannot be evaluated are reported on `io::stderr`
+/// and otherwise skipped.
+///
+/// ```
+/// let mut input = "1 10\n100 200\n".as_bytes();
+/// let mut output = Vec::new();
+///
+/// rust::run(&mut input, &mut output).unwrap();
+/// assert_eq!(output, b"1 10 20\n100 200 125\n");
+/// ```
+pub fn run(input: &mut impl io::BufRead, output: &mut impl io::Write) -> io::Result<()> {
+    run_with_mode(input, output, ParseMode::Strict)
+}
+
+/// Like `run()`, but with the given `ParseMode`.
+///
+/// ```
+/// use rust::ParseMode;
+///
+/// let mut input = "# Samples\n\n1 10\n\t100\t200 \n".as_bytes();
+/// let mut output = Vec::new();
+///
+/// rust::run_with_mode(&mut input, &mut output, ParseMode::Lenient).unwrap();
+/// assert_eq!(output, b"1 10 20\n100 200 125\n");
+/// ```
+pub fn run_with_mode(
+    input: &mut impl io::BufRead,
+    output: &mut impl io::Write,
+    mode: ParseMode
+) -> io::Result<()> {
+    let mut cache = CycleCache::new();
+    let mut line = String::new();
     
-    // Loop from the `min` value to the `max` value (inclusive)
-    // and computing the resulting length of each `cycle_vec` and
-    // storing the maximum size into `result`.
-    for n in min..=max {
-        let cycle_vec = cycles(n);
-        result = cycle_vec.len().max(result);
-        //println!("cycle_vec = {:?}, cycle length = {}", cycle_vec, cycle_vec.len());
+    loop {
+        // Empty the buffer without touching its capacity.
+        line.clear();
+        
+        // Stop at EOF.
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        
+        // Check to see if the user wants to quit the application.
+        if line.trim() == "q" || line.trim() == "quit" {
+            break;
+        }
+        
+        // Parse the `i` and `j` parameters out of the line.
+        let parsed = match mode {
+            ParseMode::Strict => Config::new(&line).map(Some),
+            ParseMode::Lenient => Config::new_lenient(&line),
+        };
+        let config = match parsed {
+            Ok(Some(config)) => config,
+            Ok(None) => continue,
+            Err(error) => {
+                eprintln!(
+                    ">> Error: could not parse input into a pair of unsigned integers. Reason: {}",
+                    error
+                );
+                continue;
+            },
+        };
+        
+        // Get the result of the maximum cycle length between
+        // integers `i` and `j`.
+        match cache.max_cycles_in_range(config.i, config.j) {
+            Ok(result) => MaxCyclesResult { i: config.i, j: config.j, result }.write_to(output)?,
+            Err(error) => eprintln!(">> Error: {}", error),
+        }
     }
     
-    (config.i, config.j, result as u32)
+    output.flush()
 }
 
 #[cfg(test)]
@@ -197,6 +472,19 @@ mod test {
         let cycle_vec = cycles(10);
         assert_eq!(vec![10, 5, 16, 8, 4, 2, 1], cycle_vec);
         assert_eq!(7, cycle_vec.len());
+        
+        assert_eq!(cycles(0), Vec::<u64>::new());
+    }
+    
+    #[test]
+    fn cycles_above_u32_max() {
+        let cycle_vec = cycles(704_511);
+        assert_eq!(cycle_vec.len(), 243);
+        assert_eq!(cycle_vec.iter().max(), Some(&56_991_483_520));
+        
+        // The largest element of any `u32`'s sequence, that of the
+        // input below 2^32 whose sequence climbs the highest.
+        assert_eq!(cycles(1_410_123_943).iter().max(), Some(&7_125_885_122_794_452_160));
     }
     
     #[test]
@@ -207,7 +495,7 @@ mod test {
             j: 10,
         };
         
-        let (i, j, result) = max_cycles(config);
+        let (i, j, result) = max_cycles(config).unwrap();
         
         assert_eq!(i, 1);
         assert_eq!(j, 10);
@@ -219,10 +507,122 @@ mod test {
             j: 1,
         };
         
-        let (i, j, result) = max_cycles(config);
+        let (i, j, result) = max_cycles(config).unwrap();
         
         assert_eq!(i, 10);
         assert_eq!(j, 1);
         assert_eq!(result, 20);
     }
+    
+    #[test]
+    fn max_cycles_invalid_config() {
+        let config = Config {
+            inputs: vec![],
+            i: 0,
+            j: 0,
+        };
+        assert_eq!(max_cycles(config), Err(RangeError::EmptyConfig));
+        
+        let config = Config {
+            inputs: vec!["0", "0"],
+            i: 0,
+            j: 0,
+        };
+        assert_eq!(max_cycles(config), Err(RangeError::OutOfDomain(0)));
+        
+        let config = Config {
+            inputs: vec!["1", "1000000"],
+            i: 1,
+            j: 1_000_000,
+        };
+        assert_eq!(max_cycles(config), Err(RangeError::OutOfDomain(1_000_000)));
+    }
+    
+    #[test]
+    fn max_cycles_result_output() {
+        let mut output = Vec::new();
+        
+        for input in &["1 10", "100 200", "201 210", "900 1000"] {
+            let config = Config::new(input).unwrap();
+            MaxCyclesResult::from(max_cycles(config).unwrap())
+                .write_to(&mut output)
+                .unwrap();
+        }
+        
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "1 10 20\n100 200 125\n201 210 89\n900 1000 174\n"
+        );
+    }
+    
+    #[test]
+    fn run_reads_until_eof_or_quit() {
+        let mut input = "1 10\n\nasdf\n0 10\n210 201\n".as_bytes();
+        let mut output = Vec::new();
+        run(&mut input, &mut output).unwrap();
+        assert_eq!(output, b"1 10 20\n210 201 89\n");
+        
+        let mut input = "1 10\nquit\n100 200\n".as_bytes();
+        let mut output = Vec::new();
+        run(&mut input, &mut output).unwrap();
+        assert_eq!(output, b"1 10 20\n");
+    }
+    
+    #[test]
+    fn config_new_lenient() {
+        assert_eq!(Config::new_lenient(""), Ok(None));
+        assert_eq!(Config::new_lenient("   \t\r\n"), Ok(None));
+        assert_eq!(Config::new_lenient("#"), Ok(None));
+        assert_eq!(Config::new_lenient("\t# 1 10\n"), Ok(None));
+        assert_eq!(
+            Config::new_lenient(" 1\t\t10 \r\n"),
+            Ok(Some(Config { inputs: vec!["1", "10"], i: 1, j: 10 }))
+        );
+        assert!(Config::new_lenient("1 10 # trailing").is_err());
+        assert!(Config::new_lenient("asdf").is_err());
+    }
+    
+    #[test]
+    fn run_with_lenient_mode() {
+        let text = "# The sample input.\n\n1 10\n  # indented comment\n100\t200\n\t201  210\n";
+        
+        let mut output = Vec::new();
+        run_with_mode(&mut text.as_bytes(), &mut output, ParseMode::Lenient).unwrap();
+        assert_eq!(output, b"1 10 20\n100 200 125\n201 210 89\n");
+        
+        // Strict mode reports the comment lines as errors but still
+        // answers the queries.
+        let mut output = Vec::new();
+        run_with_mode(&mut text.as_bytes(), &mut output, ParseMode::Strict).unwrap();
+        assert_eq!(output, b"1 10 20\n100 200 125\n201 210 89\n");
+    }
+    
+    #[test]
+    fn correct_cycle_length() {
+        for n in 1..1000 {
+            assert_eq!(cycle_length(n) as usize, cycles(n).len());
+        }
+        assert_eq!(cycle_length(0), 0);
+        assert_eq!(cycle_length(704_511), 243);
+    }
+    
+    #[test]
+    fn correct_max_cycles_in_range_result() {
+        assert_eq!(max_cycles_in_range(1, 10), Ok(20));
+        assert_eq!(max_cycles_in_range(10, 1), Ok(20));
+        assert_eq!(max_cycles_in_range(100, 200), Ok(125));
+        assert_eq!(max_cycles_in_range(201, 210), Ok(89));
+        assert_eq!(max_cycles_in_range(900, 1000), Ok(174));
+        assert_eq!(max_cycles_in_range(7, 7), Ok(17));
+    }
+    
+    #[test]
+    fn max_cycles_in_range_out_of_domain() {
+        assert_eq!(max_cycles_in_range(0, 10), Err(RangeError::OutOfDomain(0)));
+        assert_eq!(max_cycles_in_range(10, 0), Err(RangeError::OutOfDomain(0)));
+        assert_eq!(
+            max_cycles_in_range(1, INPUT_LIMIT),
+            Err(RangeError::OutOfDomain(INPUT_LIMIT))
+        );
+    }
 }
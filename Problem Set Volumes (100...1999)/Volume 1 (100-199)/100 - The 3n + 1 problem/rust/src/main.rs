@@ -1,52 +1,30 @@
+use std::env;
 use std::io;
 use std::process;
 
-use rust::{Config, max_cycles};
+use rust::ParseMode;
 
-fn main() -> io::Result<()> {
-    loop {
-        // User input string.
-        let mut input = String::new();
-        
-        // Read user input from io::stdin and set it to `input`.
-        match io::stdin().read_line(&mut input) {
-            Ok(_n) => {
-                //print!("{} bytes read, input = {}", _n, input);
-            }
-            Err(error) => {
-                eprintln!(">> Error: error reading from io::stdin `{}`", error);
-                process::exit(1);
-            },
-        }
-        
-        // Check to see if the user wants to quit the application.
-        if input.trim() == "q" || input.trim() == "quit" {
-            break;
-        }
-        
-        // Parse a new Config {} struct instance given `input` string.
-        // If `input` cannot be parsed into two unsigned integers, a
-        // default Config {} instance will be returned.
-        let config = match Config::new(&input) {
-            Ok(config) => config,
-            Err(error) => {
-                eprintln!(
-                    ">> Error: could not parse input into a pair of unsigned integers. Reason: {}",
-                    error
-                );
-                Config {
-                    inputs: Vec::<&str>::new(),
-                    i: 0,
-                    j: 0,
-                }
-            },
-        };
-        
-        // Get the result of the maximum cycle length between
-        // integers `i` and `j`, the `result` contains the result.
-        let (i, j, result) = max_cycles(config);
-        println!("{} {} {}", i, j, result);
-    }
+fn main() {
+    // Pass `--lenient` to skip blank lines and `#` comment lines in the
+    // input instead of reporting them as errors.
+    let mode = match env::args().nth(1).as_deref() {
+        None => ParseMode::Strict,
+        Some("--lenient") => ParseMode::Lenient,
+        Some(arg) => {
+            eprintln!(">> Error: unknown argument `{}`, expected `--lenient`", arg);
+            process::exit(2);
+        },
+    };
+    
+    // Lock both standard streams once, and buffer the output so that
+    // each result line doesn't cost a write to `io::stdout`.
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut reader = stdin.lock();
+    let mut writer = io::BufWriter::new(stdout.lock());
     
-    Ok(())
+    if let Err(error) = rust::run_with_mode(&mut reader, &mut writer, mode) {
+        eprintln!(">> Error: error reading from io::stdin or writing to io::stdout `{}`", error);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,176 @@
+//! `reference` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-05-30
+//!
+//! A deliberately simple, slow implementation of the "3n + 1 problem"
+//! that serves as an oracle. Nothing here is cached or clever: every
+//! cycle length is computed from scratch by following the algorithm as
+//! stated in the problem. Faster implementations can be checked against
+//! it with `compare()`.
+
+/// Returns the cycle length of `n`, following the algorithm step by step
+/// using `u64` arithmetic. The cycle length of `0` is undefined, so `0`
+/// is returned for it.
+///
+/// ```
+/// use rust::reference;
+/// assert_eq!(reference::cycle_length(22), 16);
+/// ```
+pub fn cycle_length(n: u32) -> u32 {
+    if n == 0 {
+        return 0;
+    }
+    
+    let mut n = n as u64;
+    let mut length = 1;
+    
+    while n != 1 {
+        if n.is_multiple_of(2) {
+            n /= 2;
+        }
+        else {
+            n = 3 * n + 1;
+        }
+        length += 1;
+    }
+    length
+}
+
+/// Returns the maximum cycle length for all integers between `i` and `j`
+/// (inclusive), in either order. No domain checking is done.
+///
+/// ```
+/// use rust::reference;
+/// assert_eq!(reference::max_cycles(1, 10), 20);
+/// assert_eq!(reference::max_cycles(10, 1), 20);
+/// ```
+pub fn max_cycles(i: u32, j: u32) -> u32 {
+    let mut result = 0;
+    
+    for n in i.min(j)..=i.max(j) {
+        let length = cycle_length(n);
+        if length > result {
+            result = length;
+        }
+    }
+    result
+}
+
+/// A query on which a candidate implementation disagrees with the
+/// reference implementation.
+#[derive(Debug, PartialEq)]
+pub struct Disagreement {
+    /// The first input parameter.
+    pub i: u32,
+    
+    /// The second input parameter.
+    pub j: u32,
+    
+    /// The result computed by `max_cycles()` in this module.
+    pub expected: u32,
+    
+    /// The result computed by the candidate implementation.
+    pub actual: u32,
+}
+
+/// Runs every `(i, j)` query through both the reference `max_cycles()`
+/// and `candidate`, returning every query on which they disagree.
+///
+/// ```
+/// use rust::{max_cycles_in_range, reference};
+///
+/// let queries = vec![(1, 10), (100, 200), (201, 210), (900, 1000)];
+/// let disagreements = reference::compare(queries, |i, j| {
+///     max_cycles_in_range(i, j).unwrap()
+/// });
+/// assert!(disagreements.is_empty());
+/// ```
+pub fn compare<I, F>(queries: I, mut candidate: F) -> Vec<Disagreement>
+where
+    I: IntoIterator<Item = (u32, u32)>,
+    F: FnMut(u32, u32) -> u32,
+{
+    queries
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let expected = max_cycles(i, j);
+            let actual = candidate(i, j);
+            if expected == actual {
+                None
+            }
+            else {
+                Some(Disagreement { i, j, expected, actual })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cycles, max_cycles_in_range, CycleCache};
+    
+    /// Deterministic pseudo-random `(i, j)` queries within the problem
+    /// domain.
+    fn queries(count: usize) -> Vec<(u32, u32)> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        
+        (0..count)
+            .map(|_| {
+                let i = (next() % 999_999) as u32 + 1;
+                let span = (next() % 2000) as u32;
+                let j = (i + span).min(999_999);
+                if next().is_multiple_of(2) { (i, j) } else { (j, i) }
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn reference_matches_cycles() {
+        for n in 1..1000 {
+            assert_eq!(cycle_length(n) as usize, cycles(n).len());
+        }
+        assert_eq!(cycle_length(0), 0);
+    }
+    
+    #[test]
+    fn reference_sample_output() {
+        assert_eq!(max_cycles(1, 10), 20);
+        assert_eq!(max_cycles(100, 200), 125);
+        assert_eq!(max_cycles(201, 210), 89);
+        assert_eq!(max_cycles(900, 1000), 174);
+    }
+    
+    #[test]
+    fn library_agrees_with_reference() {
+        let queries = queries(200);
+        
+        let disagreements = compare(queries.iter().cloned(), |i, j| {
+            max_cycles_in_range(i, j).unwrap()
+        });
+        assert_eq!(disagreements, vec![]);
+        
+        let mut cache = CycleCache::new();
+        let disagreements = compare(queries, |i, j| {
+            cache.max_cycles_in_range(i, j).unwrap()
+        });
+        assert_eq!(disagreements, vec![]);
+    }
+    
+    #[test]
+    fn compare_reports_disagreements() {
+        let disagreements = compare(vec![(1, 10), (7, 7)], |_, _| 17);
+        assert_eq!(
+            disagreements,
+            vec![Disagreement { i: 1, j: 10, expected: 20, actual: 17 }]
+        );
+    }
+}
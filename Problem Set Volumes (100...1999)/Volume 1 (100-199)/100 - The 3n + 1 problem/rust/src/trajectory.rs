@@ -0,0 +1,96 @@
+//! `trajectory` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-05-30
+//!
+//! Lazy access to the sequence of numbers produced by the "3n + 1
+//! problem", for analysis code that wants to inspect every element
+//! without storing them in a `Vec` the way `cycles()` does.
+
+/// An iterator over the sequence of numbers starting at `n` and ending
+/// at `1`. See `trajectory()`.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    /// The next value to yield, or `None` once `1` has been yielded.
+    next: Option<u64>,
+}
+
+impl Iterator for Trajectory {
+    type Item = u64;
+    
+    fn next(&mut self) -> Option<u64> {
+        let n = self.next?;
+        
+        self.next = if n == 1 {
+            None
+        }
+        else if n.is_multiple_of(2) { // n is even
+            Some(n / 2)
+        }
+        else { // n is odd
+            Some((3 * n) + 1)
+        };
+        Some(n)
+    }
+}
+
+/// Returns a lazy iterator over the sequence of numbers starting at `n`
+/// and ending at `1`, yielding the same elements as `cycles(n)`.
+///
+/// Elements are `u64` because the sequences of some inputs below
+/// 1,000,000 climb above `u32::MAX` (e.g., `704511` peaks at
+/// `56991483520`). The sequence of `0` is undefined, so it is empty.
+///
+/// ```
+/// use rust::{cycles, trajectory};
+///
+/// let lazy: Vec<u64> = trajectory(22).collect();
+/// assert_eq!(lazy, cycles(22));
+/// ```
+pub fn trajectory(n: u32) -> Trajectory {
+    Trajectory {
+        next: if n == 0 { None } else { Some(n as u64) },
+    }
+}
+
+/// Consumes a trajectory and returns its `(peak, length)` in one pass,
+/// where `peak` is the largest element seen and `length` is the number
+/// of elements. An empty trajectory yields `(0, 0)`.
+///
+/// ```
+/// use rust::{peak_and_length, trajectory};
+///
+/// assert_eq!(peak_and_length(trajectory(22)), (52, 16));
+/// assert_eq!(peak_and_length(trajectory(704_511)), (56_991_483_520, 243));
+/// ```
+pub fn peak_and_length(trajectory: impl Iterator<Item = u64>) -> (u64, u32) {
+    trajectory.fold((0, 0), |(peak, length), n| (peak.max(n), length + 1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{cycle_length, cycles};
+    
+    #[test]
+    fn trajectory_matches_cycles() {
+        for n in 1..1000 {
+            let lazy: Vec<u64> = trajectory(n).collect();
+            assert_eq!(lazy, cycles(n));
+        }
+        assert_eq!(trajectory(0).count(), 0);
+        assert_eq!(trajectory(1).collect::<Vec<u64>>(), vec![1]);
+    }
+    
+    #[test]
+    fn correct_peak_and_length() {
+        for n in 1..1000 {
+            let (_, length) = peak_and_length(trajectory(n));
+            assert_eq!(length, cycle_length(n));
+        }
+        assert_eq!(peak_and_length(trajectory(0)), (0, 0));
+        assert_eq!(peak_and_length(trajectory(1)), (1, 1));
+        assert_eq!(peak_and_length(trajectory(27)), (9232, 112));
+    }
+}
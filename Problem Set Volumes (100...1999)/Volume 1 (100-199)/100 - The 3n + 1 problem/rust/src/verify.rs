@@ -0,0 +1,86 @@
+//! `verify` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-05-30
+//!
+//! Differential verification of the memoized `CycleCache` against the
+//! naive `cycle_length()` function. Any future optimization of the cache
+//! can be checked here against the straightforward algorithm.
+
+use std::fmt;
+
+use crate::{cycle_length, ordered_range, CycleCache, RangeError};
+
+/// A single input whose memoized cycle length disagrees with the naive
+/// cycle length.
+#[derive(Debug, PartialEq)]
+pub struct Mismatch {
+    /// The input value.
+    pub n: u32,
+    
+    /// The cycle length computed by `CycleCache::cycle_length()`.
+    pub memoized: u32,
+    
+    /// The cycle length computed by `cycle_length()`.
+    pub naive: u32,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "n = {}: memoized = {}, naive = {}", self.n, self.memoized, self.naive)
+    }
+}
+
+/// Computes the cycle length of every integer between `min` and `max`
+/// (inclusive) with both the memoized and the naive implementations, and
+/// returns every input on which they disagree.
+///
+/// An empty `Vec` means the implementations agree over the whole range.
+/// The endpoints follow the same rules as `max_cycles_in_range()`.
+///
+/// ```
+/// use rust::verify;
+///
+/// let mismatches = verify(1, 10000).unwrap();
+/// assert!(mismatches.is_empty());
+/// ```
+pub fn verify(min: u32, max: u32) -> Result<Vec<Mismatch>, RangeError> {
+    let (min, max) = ordered_range(min, max)?;
+    let mut cache = CycleCache::new();
+    
+    Ok((min..=max)
+        .filter_map(|n| {
+            let memoized = cache.cycle_length(n);
+            let naive = cycle_length(n);
+            if memoized == naive {
+                None
+            }
+            else {
+                Some(Mismatch { n, memoized, naive })
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn verify_finds_no_mismatches() {
+        assert_eq!(verify(1, 100_000), Ok(vec![]));
+        assert_eq!(verify(999_000, 999_999), Ok(vec![]));
+    }
+    
+    #[test]
+    fn verify_out_of_domain() {
+        assert_eq!(verify(0, 10), Err(RangeError::OutOfDomain(0)));
+    }
+    
+    #[test]
+    fn mismatch_display() {
+        let mismatch = Mismatch { n: 22, memoized: 15, naive: 16 };
+        assert_eq!(mismatch.to_string(), "n = 22: memoized = 15, naive = 16");
+    }
+}
@@ -0,0 +1,142 @@
+//! `pile_chains` benchmark
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! Measure `Blocks` operation throughput on the worst cases: tall
+//! stacks that are piled back and forth, and chains that are built up
+//! with `over` and knocked down with `onto`. Run with `cargo bench`,
+//! optionally followed by a scenario name to run only that scenario.
+
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust::blocks::Blocks;
+
+/// The number of blocks in each scenario's world.
+const BLOCKS: u32 = 10_000;
+
+/// A scenario run on a fresh world, returning the number of
+/// operations it executed.
+type Scenario = fn(&mut Blocks) -> usize;
+
+/// Pile a stack of `BLOCKS - 1` blocks back and forth between two
+/// stacks, moving nearly every block in the world on every operation.
+/// Returns the number of operations.
+fn tall_pile_back_and_forth(blocks: &mut Blocks) -> usize {
+    let last = BLOCKS - 1;
+    for i in 1..last {
+        blocks.pile_a_over_b(i, 0).unwrap();
+    }
+    
+    let rounds = 1_000;
+    for _ in 0..rounds {
+        blocks.pile_a_over_b(black_box(1), last).unwrap();
+        blocks.pile_a_over_b(black_box(1), 0).unwrap();
+    }
+    
+    last as usize - 1 + 2 * rounds
+}
+
+/// Like `tall_pile_back_and_forth()`, but alternating `onto` and
+/// `over`, which also has to clear the blocks above the target.
+/// Returns the number of operations.
+fn tall_pile_onto_and_over(blocks: &mut Blocks) -> usize {
+    let last = BLOCKS - 1;
+    for i in 1..last {
+        blocks.pile_a_over_b(i, 0).unwrap();
+    }
+    
+    let rounds = 1_000;
+    for _ in 0..rounds {
+        blocks.pile_a_onto_b(black_box(1), last).unwrap();
+        blocks.pile_a_over_b(black_box(1), 0).unwrap();
+    }
+    
+    last as usize - 1 + 2 * rounds
+}
+
+/// Build a chain of every block on top of block `0` with `move over`,
+/// then knock it down with a single `move onto`, which returns every
+/// block above the moved one to its original position. Returns the
+/// number of operations.
+fn chain_build_and_knock_down(blocks: &mut Blocks) -> usize {
+    let last = BLOCKS - 1;
+    let rounds = 10;
+    for _ in 0..rounds {
+        for i in 1..last {
+            blocks.move_a_over_b(black_box(i), 0).unwrap();
+        }
+        blocks.move_a_onto_b(black_box(1), last).unwrap();
+    }
+    
+    rounds * last as usize
+}
+
+/// Pile a stack of half the blocks onto a different block each time,
+/// alternating `onto` and `over`, so the tall pile wanders across
+/// the other half of the world. Returns the number of operations.
+fn wandering_pile(blocks: &mut Blocks) -> usize {
+    let half = BLOCKS / 2;
+    for i in 2..half {
+        blocks.pile_a_over_b(i, 1).unwrap();
+    }
+    
+    // Every block in the other half is alone in its own stack when the
+    // pile lands on it, so every operation is legal.
+    let rounds = 2_000;
+    for round in 0..rounds {
+        let b = half + round % half;
+        if round % 2 == 0 {
+            blocks.pile_a_onto_b(black_box(1), b).unwrap();
+        }
+        else {
+            blocks.pile_a_over_b(black_box(1), b).unwrap();
+        }
+    }
+    
+    half as usize - 2 + rounds as usize
+}
+
+/// Run `scenario` on a fresh world, timing it, and print its
+/// throughput.
+fn bench(name: &str, scenario: Scenario) {
+    let mut best = Duration::from_secs(u64::MAX);
+    let mut operations = 0;
+    
+    for _ in 0..5 {
+        let mut blocks = Blocks::new(BLOCKS).unwrap();
+        let start = Instant::now();
+        operations = scenario(&mut blocks);
+        best = best.min(start.elapsed());
+        black_box(&blocks);
+    }
+    
+    println!(
+        "{:<28} {:>8} ops {:>10.3} ms {:>10.1} ns/op",
+        name,
+        operations,
+        best.as_secs_f64() * 1e3,
+        best.as_nanos() as f64 / operations as f64
+    );
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; any other argument selects the
+    // scenarios to run.
+    let filters: Vec<String> = env::args().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+    let scenarios: [(&str, Scenario); 4] = [
+        ("tall_pile_back_and_forth", tall_pile_back_and_forth),
+        ("tall_pile_onto_and_over", tall_pile_onto_and_over),
+        ("chain_build_and_knock_down", chain_build_and_knock_down),
+        ("wandering_pile", wandering_pile),
+    ];
+    
+    for (name, scenario) in scenarios.iter() {
+        if filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str())) {
+            bench(name, *scenario);
+        }
+    }
+}
@@ -4,12 +4,36 @@
 //!
 //! Date: 2020-06-04
 
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::io::BufRead;
+use std::path::Path;
+
+use crate::command::{Command, Placement};
+use crate::event::OpEvent;
+use crate::render::{self, RenderStyle};
+use crate::rules::{BlockRules, StandardRules};
+use crate::snapshot::WorldSnapshot;
+
+/// The `(stack, height)` coordinates of a block in the world, where
+/// `stack` indexes into `Blocks::world()` and `height` indexes into
+/// that stack.
+type Position = (usize, usize);
+
+/// Worlds with more blocks than this are not checked with
+/// `Blocks::validate()` after every operation in debug builds, since
+/// doing so costs time proportional to the number of blocks.
+const DEBUG_VALIDATE_LIMIT: usize = 10_000;
+
 /// The state of the `Blocks` struct during its processing.
 /// The state changes depending on the initial command of
 /// `move_a()` or `pile_a()`. If there is an invalid order
 /// of commands, the blocks state gets set back to
 /// `BlockState::Init`.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BlockState {
     /// Initial block state.
     Init,
@@ -21,13 +45,127 @@ pub enum BlockState {
     Pile,
 }
 
+/// An error returned when a `Blocks` operation cannot be performed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockError {
+    /// The command is not a block operation. This is the case for
+    /// `print` and `quit` commands.
+    NotAnOperation,
+    
+    /// `a` and `b` are the same block. Holds the block number.
+    SameBlock(u32),
+    
+    /// The block is not in the world, i.e., it is not less than the
+    /// number of blocks. Holds the block number.
+    OutOfRange(u32),
+    
+    /// `a` and `b` are in the same stack of blocks. Holds `a` and `b`.
+    SameStack(u32, u32),
+    
+    /// The block has another block on top of it. Holds the block
+    /// number.
+    NotOnTop(u32),
+}
+
+impl fmt::Display for BlockError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlockError::NotAnOperation => write!(f, "command is not a block operation"),
+            BlockError::SameBlock(a) => write!(f, "`a` and `b` are the same block ({})", a),
+            BlockError::OutOfRange(block) => write!(f, "block {} is not in the world", block),
+            BlockError::SameStack(a, b) => write!(f, "blocks {} and {} are in the same stack", a, b),
+            BlockError::NotOnTop(block) => write!(f, "block {} is not on top of its stack", block),
+        }
+    }
+}
+
+impl Error for BlockError {}
+
+/// An error returned by `Blocks::validate()` when the world is
+/// corrupt, i.e., it breaks the rule that every block `0..n` is in
+/// exactly one stack, exactly where its recorded position says it
+/// is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldError {
+    /// The world has no blocks at all.
+    Empty,
+    
+    /// The world doesn't have one stack per block. Holds the number
+    /// of stacks and the number of blocks.
+    StackCount(usize, usize),
+    
+    /// A block number that is not less than the number of blocks is
+    /// in the world. Holds the block number.
+    UnknownBlock(u32),
+    
+    /// The block is in the world more than once. Holds the block
+    /// number.
+    DuplicateBlock(u32),
+    
+    /// The block is not in the world. Holds the block number.
+    MissingBlock(u32),
+    
+    /// The block's recorded position doesn't match where it actually
+    /// is.
+    WrongPosition {
+        block: u32,
+        recorded: (usize, usize),
+        actual: (usize, usize),
+    },
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorldError::Empty => write!(f, "the world has no blocks"),
+            WorldError::StackCount(stacks, blocks) => {
+                write!(f, "the world has {} stacks for {} blocks", stacks, blocks)
+            },
+            WorldError::UnknownBlock(block) => write!(f, "block {} is not a known block", block),
+            WorldError::DuplicateBlock(block) => write!(f, "block {} is in the world more than once", block),
+            WorldError::MissingBlock(block) => write!(f, "block {} is missing from the world", block),
+            WorldError::WrongPosition { block, recorded, actual } => write!(
+                f,
+                "block {} is recorded at {:?} but is at {:?}",
+                block, recorded, actual
+            ),
+        }
+    }
+}
+
+impl Error for WorldError {}
+
 /// The `Blocks` instance containing the block state (`Move` or
-/// `Pile`), the main blocks structure (vec of vecs), and the `a`
-/// and `b` block targets for the operation.
-#[derive(Debug, PartialEq)]
+/// `Pile`), the main blocks structure (vec of vecs), an index of
+/// every block's position in that structure, and the `a` and `b`
+/// block targets for the operation.
+///
+/// Thanks to the index, finding a block takes constant time, and an
+/// operation costs time proportional to the number of blocks it
+/// moves, independent of the size of the world. Every block that is
+/// returned to its original position was put on top of another
+/// block by an earlier operation, so over a whole command stream
+/// only `pile` operations on tall piles cost more than a few steps.
+/// Worlds of a million blocks handle a million random commands in
+/// well under a second in release builds.
+///
+/// Two `Blocks` instances are equal, and hash the same, when their
+/// worlds are the same, regardless of any chained operation in
+/// progress. See `canonical_key()`.
+#[derive(Debug, Clone)]
 pub struct Blocks {
     pub state: BlockState,
-    pub world: Vec<Vec<u32>>,
+    
+    /// The stacks of blocks. Private, so that it can't get out of
+    /// step with `positions`; see `world()` and `stacks()`.
+    world: Vec<Vec<u32>>,
+    
+    /// `positions[block]` is the `(stack, height)` of `block` in
+    /// `world`. It is kept in step with `world` on every push and
+    /// pop so that finding a block doesn't require scanning the
+    /// whole world. See `stack_of()` and `height_of()`.
+    positions: Vec<(usize, usize)>,
+    
     pub a: Option<u32>,
     pub b: Option<u32>,
 }
@@ -52,14 +190,72 @@ impl Blocks {
         // E.g., `vec![vec![0], vec![1], ..., vec![n]]`
         let vec_elements: Vec<Vec<u32>> = (0..elements).map(|x| vec![x]).collect();
         
+        // Every block starts at the bottom of its own stack.
+        let positions: Vec<(usize, usize)> = (0..elements as usize).map(|x| (x, 0)).collect();
+        
         Ok(Blocks {
             world: vec_elements,
+            positions,
             state: BlockState::Init,
             a: None,
             b: None,
         })
     }
     
+    /// Return a new `Blocks` instance whose world is `world`, for
+    /// starting from an arbitrary arrangement rather than the initial
+    /// one. `world` must have one stack per block, and hold every
+    /// block `0..n` exactly once. Otherwise, the problem is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::{Blocks, WorldError};
+    ///
+    /// let mut blocks = Blocks::from_world(vec![vec![0, 2], vec![], vec![1]]).unwrap();
+    /// blocks.move_a_onto_b(1, 0).unwrap();
+    /// assert_eq!(blocks.world(), vec![vec![0, 1], vec![], vec![2]]);
+    ///
+    /// assert_eq!(
+    ///     Blocks::from_world(vec![vec![0, 1], vec![1]]),
+    ///     Err(WorldError::DuplicateBlock(1))
+    /// );
+    /// ```
+    pub fn from_world(world: Vec<Vec<u32>>) -> Result<Blocks, WorldError> {
+        if world.is_empty() {
+            return Err(WorldError::Empty);
+        }
+        
+        // Find every block, making sure each one is found only once.
+        let mut positions: Vec<Option<(usize, usize)>> = vec![None; world.len()];
+        for (i, stack) in world.iter().enumerate() {
+            for (j, &block) in stack.iter().enumerate() {
+                match positions.get_mut(block as usize) {
+                    None => return Err(WorldError::UnknownBlock(block)),
+                    Some(Some(_)) => return Err(WorldError::DuplicateBlock(block)),
+                    Some(position) => *position = Some((i, j)),
+                }
+            }
+        }
+        
+        let positions = positions
+            .into_iter()
+            .enumerate()
+            .map(|(block, position)| position.ok_or(WorldError::MissingBlock(block as u32)))
+            .collect::<Result<Vec<(usize, usize)>, WorldError>>()?;
+        
+        let blocks = Blocks {
+            state: BlockState::Init,
+            world,
+            positions,
+            a: None,
+            b: None,
+        };
+        blocks.debug_validate();
+        
+        Ok(blocks)
+    }
+    
     /// Given an intial state of `BlockState::Init`, sets the
     /// internal state to `Move` and sets the `a` parameter
     /// appropriately. If the internal state is anything other
@@ -112,8 +308,10 @@ impl Blocks {
                 // "move a onto b"
                 self.b = Some(b);
                 
-                // Perform operation.
-                self.move_a_onto_b();
+                // Perform operation. Illegal operations are ignored.
+                if let Some(a) = self.a {
+                    let _ = self.move_a_onto_b(a, b);
+                }
                 
                 // Reset to Init state.
                 self.reset_state();
@@ -122,8 +320,10 @@ impl Blocks {
                 // "pile a onto b"
                 self.b = Some(b);
                 
-                // Perform operation.
-                self.pile_a_onto_b();
+                // Perform operation. Illegal operations are ignored.
+                if let Some(a) = self.a {
+                    let _ = self.pile_a_onto_b(a, b);
+                }
                 
                 // Reset to Init state.
                 self.reset_state();
@@ -146,8 +346,10 @@ impl Blocks {
                 // "move a over b"
                 self.b = Some(b);
                 
-                // Perform operation.
-                self.move_a_over_b();
+                // Perform operation. Illegal operations are ignored.
+                if let Some(a) = self.a {
+                    let _ = self.move_a_over_b(a, b);
+                }
                 
                 // Reset to Init state.
                 self.reset_state();
@@ -156,8 +358,10 @@ impl Blocks {
                 // "pile a over b"
                 self.b = Some(b);
                 
-                // Perform operation.
-                self.pile_a_over_b();
+                // Perform operation. Illegal operations are ignored.
+                if let Some(a) = self.a {
+                    let _ = self.pile_a_over_b(a, b);
+                }
                 
                 // Reset to Init state.
                 self.reset_state();
@@ -167,32 +371,133 @@ impl Blocks {
         self
     }
     
-    /// Print onto `std::io::stdout` the internal state of the
-    /// `world` attribute for a given `Blocks` instance.
+    /// Perform the whole operation described by a parsed `command`,
+    /// e.g., `move 9 onto 1`, in one step.
     ///
-    /// Should print to something akin to:
+    /// Unlike chaining `move_a()`/`pile_a()` with `onto_b()`/`over_b()`,
+    /// this does not depend on (or leave behind) any intermediate
+    /// state. If the operation is illegal, the world is left untouched
+    /// and an `Err()` is returned.
+    ///
+    /// # Example
     ///
-    /// ```ignore
-    /// 0: 0 1
-    /// 1:
-    /// 2: 2
-    /// 3: 3 4 5
-    /// 4:
-    /// 5:
     /// ```
-    pub fn print(&self) {
-        for (index, vec) in (&self.world).iter().enumerate() {
-            print!("{}:", index);
-            for &item in vec.iter() {
-                print!(" {}", item);
-            }
-            print!("\n");
+    /// use rust::blocks::{Blocks, BlockError};
+    /// use rust::command::Command;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    ///
+    /// let command = Command::parse("move 2 onto 0").unwrap();
+    /// assert_eq!(blocks.execute(&command), Ok(()));
+    /// assert_eq!(blocks.world(), vec![vec![0, 2], vec![1], vec![]]);
+    ///
+    /// let command = Command::parse("pile 0 over 2").unwrap();
+    /// assert_eq!(blocks.execute(&command), Err(BlockError::SameStack(0, 2)));
+    /// assert_eq!(blocks.execute(&Command::Print), Err(BlockError::NotAnOperation));
+    /// ```
+    pub fn execute(&mut self, command: &Command) -> Result<(), BlockError> {
+        self.execute_with(command, &StandardRules)
+    }
+    
+    /// Like `execute()`, but `move` and `pile` operations follow
+    /// `rules` instead of the problem's rules. See `BlockRules`.
+    pub fn execute_with(&mut self, command: &Command, rules: &dyn BlockRules) -> Result<(), BlockError> {
+        // Start from a clean state, regardless of any previous
+        // (possibly incomplete) chained calls.
+        self.reset_state();
+        
+        match *command {
+            Command::Move { a, b, placement: Placement::Onto } => rules.move_onto(self, a, b),
+            Command::Move { a, b, placement: Placement::Over } => rules.move_over(self, a, b),
+            Command::Pile { a, b, placement: Placement::Onto } => rules.pile_onto(self, a, b),
+            Command::Pile { a, b, placement: Placement::Over } => rules.pile_over(self, a, b),
+            Command::Return { a } => self.return_a(a),
+            Command::Swap { a, b } => self.swap_a_b(a, b),
+            Command::Reset => { self.reset(); Ok(()) },
+            Command::Print | Command::Quit => Err(BlockError::NotAnOperation),
         }
     }
     
-    //
-    // Private methods.
-    //
+    /// Perform every operation in `commands`, in order, like
+    /// `execute()`, returning the result of each one. Illegal
+    /// operations are ignored, so the rest of the commands are still
+    /// performed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::{Blocks, BlockError};
+    /// use rust::command::Command;
+    ///
+    /// let commands: Vec<Command> = ["move 1 onto 0", "move 0 onto 1", "pile 2 over 1"]
+    ///     .iter()
+    ///     .map(|line| Command::parse(line).unwrap())
+    ///     .collect();
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// assert_eq!(
+    ///     blocks.execute_all(&commands),
+    ///     vec![Ok(()), Err(BlockError::SameStack(0, 1)), Ok(())]
+    /// );
+    /// assert_eq!(blocks.world(), vec![vec![0, 1, 2], vec![], vec![]]);
+    /// ```
+    pub fn execute_all(&mut self, commands: &[Command]) -> Vec<Result<(), BlockError>> {
+        commands.iter().map(|command| self.execute(command)).collect()
+    }
+    
+    /// Return the commands that undo the operation described by
+    /// `event`, given that this is the world right after it. Executing
+    /// them, e.g., with `execute_all()`, restores the world as it was
+    /// before the operation.
+    ///
+    /// Usually, every moved block is returned to its original position
+    /// with `return`, from the extended grammar, then put back where it
+    /// was with `move ... over ...`, from the bottom of each stack up.
+    /// Worlds rearranged by `swap` can have blocks at the bottom of
+    /// another block's stack, which that can't put back, so those are
+    /// rebuilt from scratch with `reset`, `swap` and `move ... over
+    /// ...` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    /// use rust::command::Command;
+    /// use rust::event::OpEvent;
+    ///
+    /// let mut blocks = Blocks::from_world(vec![vec![0, 1], vec![], vec![2]]).unwrap();
+    ///
+    /// let command = Command::parse("move 2 onto 0").unwrap();
+    /// let snapshot = blocks.snapshot();
+    /// blocks.execute(&command).unwrap();
+    /// let event = OpEvent { command, moves: snapshot.diff(&blocks.snapshot()) };
+    ///
+    /// let undo = blocks.inverse_of(&event);
+    /// let undo: Vec<String> = undo.iter().map(|command| command.to_string()).collect();
+    /// assert_eq!(undo, vec!["return 2", "move 1 over 0"]);
+    /// ```
+    pub fn inverse_of(&self, event: &OpEvent) -> Vec<Command> {
+        // Where every block was before the operation.
+        let mut positions = self.positions.clone();
+        for m in event.moves.iter() {
+            positions[m.block as usize] = m.from;
+        }
+        let mut order: Vec<u32> = (0..positions.len() as u32).collect();
+        order.sort_by_key(|&block| positions[block as usize]);
+        let mut before = vec![Vec::new(); positions.len()];
+        for block in order {
+            before[positions[block as usize].0].push(block);
+        }
+        
+        let mut blocks = self.clone();
+        let commands = blocks.put_back(event);
+        if blocks.world == before {
+            return commands;
+        }
+        
+        let mut blocks = self.clone();
+        blocks.rebuild(&before)
+    }
     
     /// `move a onto b`
     ///
@@ -200,48 +505,30 @@ impl Blocks {
     /// `a` onto block `b` after returning any blocks
     /// that are stacked on top of blocks `a` and `b`
     /// to their initial positions.
-    fn move_a_onto_b(&mut self) -> &mut Self {
-        let (a, b) = self.get_a_and_b();
-        
-        if self.parameters_invalid() {
-            return self.reset_state();
-        }
-        
+    ///
+    /// Returns an `Err()`, leaving the world untouched, if the
+    /// operation is illegal.
+    pub fn move_a_onto_b(&mut self, a: u32, b: u32) -> Result<(), BlockError> {
         // Get the coordinates of blocks `a` and `b`. This
         // corresponds to the `self.world` vec of vecs.
-        let (i, j) = self.where_is(a);
-        let (k, l) = self.where_is(b);
-        
-        // Split the block `a` vector into two slices.
-        let (_, right_a) = self.world[i as usize].split_at(j as usize);
-        
-        let mut block_a: u32;
-        
-        // Pop off every block on top of block `a` and put those
-        // blocks in their original positions.
-        for _ in 0..right_a.len() - 1 {
-            block_a = self.world[i as usize].pop().unwrap();
-            self.world[block_a as usize].push(block_a);
-        }
+        let ((i, j), (k, l)) = self.check_parameters(a, b)?;
         
-        // Finally, pop off block `a` for later use.
-        block_a = self.world[i as usize].pop().unwrap();
+        // Take every block off of blocks `a` and `b`.
+        let above_a = self.take_blocks_above(i, j);
+        let block_a = self.pop_block(i);
+        let above_b = self.take_blocks_above(k, l);
         
-        // Split the block `b` vector into two slices.
-        let (_, right_b) = self.world[k as usize].split_at(l as usize);
+        // Move block `a` onto block `b`.
+        self.push_block(k, block_a);
         
-        // Pop off every block on top of block `b` and put those
-        // blocks in their original positions.
-        for _ in 0..right_b.len() - 1 {
-            let block_b = self.world[k as usize].pop().unwrap();
-            self.world[block_b as usize].push(block_b);
-        }
+        // Finally, put the blocks that were on top of blocks `a`
+        // and `b` in their original positions.
+        self.return_blocks(above_a);
+        self.return_blocks(above_b);
         
-        // Don't pop off block `b` because now we're going to move
-        // `a` onto `b`.
-        self.world[k as usize].push(block_a);
+        self.debug_validate();
         
-        self
+        Ok(())
     }
     
     /// `move a over b`
@@ -250,37 +537,28 @@ impl Blocks {
     /// `a` onto the top of the stack containing block `b`,
     /// after returning any blocks that are stacked on top
     /// of block `a` to their initial positions.
-    fn move_a_over_b(&mut self) -> &mut Self {
-        let (a, b) = self.get_a_and_b();
-        
-        if self.parameters_invalid() {
-            return self.reset_state();
-        }
-        
-        // Get the coordinates of blocks `a` and `b`. This corresponds
-        // to the `self.world` vec of vecs.
-        let (i, j) = self.where_is(a);
-        let (k, _) = self.where_is(b);
-        
-        // Split the block `a` vector into two slices.
-        let (_, right_a) = self.world[i as usize].split_at(j as usize);
-        
-        let mut block_a: u32;
+    ///
+    /// Returns an `Err()`, leaving the world untouched, if the
+    /// operation is illegal.
+    pub fn move_a_over_b(&mut self, a: u32, b: u32) -> Result<(), BlockError> {
+        // Get the coordinates of blocks `a` and `b`. This
+        // corresponds to the `self.world` vec of vecs.
+        let ((i, j), (k, _)) = self.check_parameters(a, b)?;
         
-        // Pop off every block on top of block `a` and put those
-        // blocks in their original positions.
-        for _ in 0..right_a.len() - 1 {
-            block_a = self.world[i as usize].pop().unwrap();
-            self.world[block_a as usize].push(block_a);
-        }
+        // Take every block off of block `a`.
+        let above_a = self.take_blocks_above(i, j);
+        let block_a = self.pop_block(i);
         
-        // Finally, pop off block `a`.
-        block_a = self.world[i as usize].pop().unwrap();
+        // Put every block that was on top of block `a` in their
+        // original positions.
+        self.return_blocks(above_a);
         
         // Put block `a` on top of the stack containing block `b`.
-        self.world[k as usize].push(block_a);
+        self.push_block(k, block_a);
         
-        self
+        self.debug_validate();
+        
+        Ok(())
     }
     
     /// `pile a onto b`
@@ -292,48 +570,28 @@ impl Blocks {
     /// moved to their initial positions prior to the
     /// pile taking place. The blocks stacked above
     /// block `a` retain their order when moved.
-    fn pile_a_onto_b(&mut self) -> &mut Self {
-        let (a, b) = self.get_a_and_b();
-        
-        if self.parameters_invalid() {
-            return self.reset_state();
-        }
-        
-        // Get the coordinates of blocks `a` and `b`. This corresponds
-        // to the `self.world` vec of vecs.
-        let (i, j) = self.where_is(a);
-        let (k, l) = self.where_is(b);
-        
-        // Split the block `b` vector into two slices.
-        let (_, right_b) = self.world[k as usize].split_at(l as usize);
-        
-        // Pop off every block on top of block `b` and put those
-        // blocks in their original positions.
-        for _ in 0..right_b.len() - 1 {
-            let block_b = self.world[k as usize].pop().unwrap();
-            self.world[block_b as usize].push(block_b);
-        }
-        
-        // Split the block `a` vector into two slices.
-        let (_, right_a) = self.world[i as usize].split_at(j as usize);
+    ///
+    /// Returns an `Err()`, leaving the world untouched, if the
+    /// operation is illegal.
+    pub fn pile_a_onto_b(&mut self, a: u32, b: u32) -> Result<(), BlockError> {
+        // Get the coordinates of blocks `a` and `b`. This
+        // corresponds to the `self.world` vec of vecs.
+        let ((i, j), (k, l)) = self.check_parameters(a, b)?;
         
-        let mut right_a_vec = Vec::<u32>::new();
+        // Take every block off of block `b`.
+        let above_b = self.take_blocks_above(k, l);
         
-        // Copy the `right_a` slice.
-        for idx in 0..right_a.len() {
-            right_a_vec.push(right_a[idx]);
-        }
+        // Move block `a`, and every block on top of it, onto
+        // block `b`.
+        self.move_pile(i, j, k);
         
-        // Pop off every block on top of block `a`, including `a`.
-        for _ in 0..right_a.len() {
-            let _item = self.world[i as usize].pop().unwrap();
-        }
+        // Put every block that was on top of block `b` in their
+        // original positions.
+        self.return_blocks(above_b);
         
-        // Push the blocks on top of block `a`, including `a`, onto
-        // block `b`.
-        self.world[k as usize].extend(right_a_vec);
+        self.debug_validate();
         
-        self
+        Ok(())
     }
     
     /// `pile a over b`
@@ -344,48 +602,560 @@ impl Blocks {
     /// top of the stack containing block `b`. The blocks
     /// stacked above block `a` retain their original
     /// order when moved.
-    fn pile_a_over_b(&mut self) -> &mut Self {
-        let (a, b) = self.get_a_and_b();
+    ///
+    /// Returns an `Err()`, leaving the world untouched, if the
+    /// operation is illegal.
+    pub fn pile_a_over_b(&mut self, a: u32, b: u32) -> Result<(), BlockError> {
+        // Get the coordinates of blocks `a` and `b`. This
+        // corresponds to the `self.world` vec of vecs.
+        let ((i, j), (k, _)) = self.check_parameters(a, b)?;
+        
+        // Move block `a`, and every block on top of it, onto the
+        // top of the stack containing block `b`.
+        self.move_pile(i, j, k);
+        
+        self.debug_validate();
+        
+        Ok(())
+    }
+    
+    /// `return a`
+    ///
+    /// Where `a` is a block number; puts block `a`, and every
+    /// block stacked on top of it, back in their original
+    /// positions. Part of the extended grammar.
+    ///
+    /// Returns an `Err()` if block `a` is not in the world.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.pile_a_over_b(1, 0).unwrap();
+    /// blocks.move_a_over_b(2, 0).unwrap();
+    ///
+    /// blocks.return_a(1).unwrap();
+    /// assert_eq!(blocks.world(), vec![vec![0], vec![1], vec![2]]);
+    /// ```
+    pub fn return_a(&mut self, a: u32) -> Result<(), BlockError> {
+        let (i, j) = self.where_is(a).ok_or(BlockError::OutOfRange(a))?;
+        
+        // Take block `a`, and every block on top of it, off of its
+        // stack, then put them all in their original positions.
+        let pile = self.world[i].split_off(j);
+        self.return_blocks(pile);
+        
+        self.debug_validate();
+        
+        Ok(())
+    }
+    
+    /// `swap a b`
+    ///
+    /// Where `a` and `b` are block numbers; exchanges the places
+    /// of blocks `a` and `b`, which must both be on top of their
+    /// stacks. Part of the extended grammar.
+    ///
+    /// Returns an `Err()` if `a` and `b` are the same block, if
+    /// either is not in the world, or if either has a block on top
+    /// of it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::{Blocks, BlockError};
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(1, 0).unwrap();
+    ///
+    /// blocks.swap_a_b(1, 2).unwrap();
+    /// assert_eq!(blocks.world(), vec![vec![0, 2], vec![], vec![1]]);
+    ///
+    /// assert_eq!(blocks.swap_a_b(0, 1), Err(BlockError::NotOnTop(0)));
+    /// ```
+    pub fn swap_a_b(&mut self, a: u32, b: u32) -> Result<(), BlockError> {
+        let ((i, j), (k, l)) = self.check_parameters(a, b)?;
         
-        if self.parameters_invalid() {
-            return self.reset_state();
+        if j + 1 != self.world[i].len() {
+            return Err(BlockError::NotOnTop(a));
+        }
+        if l + 1 != self.world[k].len() {
+            return Err(BlockError::NotOnTop(b));
         }
         
-        // Get the coordinates of blocks `a` and `b`. This corresponds
-        // to the `self.world` vec of vecs.
-        let (i, j) = self.where_is(a);
-        let (k, _) = self.where_is(b);
+        self.world[i][j] = b;
+        self.world[k][l] = a;
+        self.positions[a as usize] = (k, l);
+        self.positions[b as usize] = (i, j);
         
-        // Split the block `a` vector into two slices.
-        let (_, right_a) = self.world[i as usize].split_at(j as usize);
+        self.debug_validate();
         
-        let mut right_a_vec = Vec::<u32>::new();
+        Ok(())
+    }
+    
+    /// `reset`
+    ///
+    /// Puts every block back in its original position, as if the
+    /// world had just been created with `Blocks::new()`. Part of the
+    /// extended grammar.
+    pub fn reset(&mut self) {
+        let elements = self.positions.len();
+        
+        self.world = (0..elements as u32).map(|x| vec![x]).collect();
+        self.positions = (0..elements).map(|x| (x, 0)).collect();
+        self.reset_state();
+        self.debug_validate();
+    }
+    
+    /// Return the stacks of blocks, bottom block first, indexed by
+    /// their original positions, for reading the world without
+    /// printing it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// assert_eq!(blocks.world(), &[vec![0, 2], vec![1], vec![]]);
+    /// ```
+    pub fn world(&self) -> &[Vec<u32>] {
+        &self.world
+    }
+    
+    /// Consume the `Blocks` instance, returning its stacks of blocks.
+    /// See `world()`.
+    pub fn into_world(self) -> Vec<Vec<u32>> {
+        self.world
+    }
+    
+    /// Return an iterator over the stacks of the world, in order,
+    /// each as a slice of block numbers from the bottom up.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// let heights: Vec<usize> = blocks.stacks().map(|stack| stack.len()).collect();
+    /// assert_eq!(heights, vec![2, 1, 0]);
+    /// ```
+    pub fn stacks(&self) -> impl Iterator<Item = &[u32]> + '_ {
+        self.world.iter().map(Vec::as_slice)
+    }
+    
+    /// Return an iterator over every block in the world, ordered by
+    /// block number, as `(block, stack, height)` where `stack` is the
+    /// index of the stack the block is in and `height` is the number
+    /// of blocks below it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// let blocks: Vec<(u32, usize, usize)> = blocks.blocks().collect();
+    /// assert_eq!(blocks, vec![(0, 0, 0), (1, 1, 0), (2, 0, 1)]);
+    /// ```
+    pub fn blocks(&self) -> impl Iterator<Item = (u32, usize, usize)> + '_ {
+        self.positions
+            .iter()
+            .enumerate()
+            .map(|(block, &(stack, height))| (block as u32, stack, height))
+    }
+    
+    /// Return the index of the stack that `block` is in, or `None` if
+    /// there is no such block.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// assert_eq!(blocks.stack_of(2), Some(0));
+    /// assert_eq!(blocks.stack_of(3), None);
+    /// ```
+    pub fn stack_of(&self, block: u32) -> Option<usize> {
+        self.where_is(block).map(|(stack, _)| stack)
+    }
+    
+    /// Return the height of `block` in its stack, i.e., the number of
+    /// blocks below it, or `None` if there is no such block.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.pile_a_over_b(1, 0).unwrap();
+    /// blocks.move_a_over_b(2, 0).unwrap();
+    ///
+    /// assert_eq!(blocks.height_of(0), Some(0));
+    /// assert_eq!(blocks.height_of(2), Some(2));
+    /// assert_eq!(blocks.height_of(3), None);
+    /// ```
+    pub fn height_of(&self, block: u32) -> Option<usize> {
+        self.where_is(block).map(|(_, height)| height)
+    }
+    
+    /// Return the number of blocks on top of `block`, or `None` if
+    /// there is no such block. A `move` of `block` returns that many
+    /// blocks to their original positions first.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.pile_a_over_b(1, 0).unwrap();
+    /// blocks.move_a_over_b(2, 0).unwrap();
+    ///
+    /// assert_eq!(blocks.blocks_above(0), Some(2));
+    /// assert_eq!(blocks.blocks_above(2), Some(0));
+    /// ```
+    pub fn blocks_above(&self, block: u32) -> Option<usize> {
+        self.where_is(block)
+            .map(|(stack, height)| self.world[stack].len() - height - 1)
+    }
+    
+    /// Return the block on top of stack `stack`, or `None` if the
+    /// stack is empty or there is no such stack.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// assert_eq!(blocks.top_of(0), Some(2));
+    /// assert_eq!(blocks.top_of(2), None);
+    /// assert_eq!(blocks.top_of(3), None);
+    /// ```
+    pub fn top_of(&self, stack: usize) -> Option<u32> {
+        self.world.get(stack).and_then(|stack| stack.last().copied())
+    }
+    
+    /// Check that the world is consistent: there is one stack per
+    /// block, every block `0..n` is in exactly one stack, and
+    /// `positions` records exactly where each block is. Returns the
+    /// first problem found.
+    ///
+    /// The world can only be changed through the operations, so this
+    /// only fails on a bug in them. Every operation checks this in
+    /// debug builds, so a corrupting bug fails loudly in tests and
+    /// fuzzing.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// assert_eq!(blocks.validate(), Ok(()));
+    ///
+    /// blocks.pile_a_over_b(2, 1).unwrap();
+    /// assert_eq!(blocks.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), WorldError> {
+        let blocks = self.positions.len();
+        if self.world.len() != blocks {
+            return Err(WorldError::StackCount(self.world.len(), blocks));
+        }
         
-        // Copy the `right_a` slice.
-        for idx in 0..right_a.len() {
-            right_a_vec.push(right_a[idx]);
+        let mut seen = vec![false; blocks];
+        for (i, stack) in self.world.iter().enumerate() {
+            for (j, &block) in stack.iter().enumerate() {
+                let index = block as usize;
+                if index >= blocks {
+                    return Err(WorldError::UnknownBlock(block));
+                }
+                if seen[index] {
+                    return Err(WorldError::DuplicateBlock(block));
+                }
+                seen[index] = true;
+                
+                if self.positions[index] != (i, j) {
+                    return Err(WorldError::WrongPosition {
+                        block,
+                        recorded: self.positions[index],
+                        actual: (i, j),
+                    });
+                }
+            }
         }
         
-        // Pop off every block on top of block `a`, including `a`.
-        for _ in 0..right_a.len() {
-            self.world[i as usize].pop().unwrap();
+        match seen.iter().position(|&seen| !seen) {
+            Some(block) => Err(WorldError::MissingBlock(block as u32)),
+            None => Ok(()),
+        }
+    }
+    
+    /// Save the world into a new file at `path`, replacing any
+    /// existing file, so that it can be restored with `load()`. The
+    /// file holds the world as `print()` prints it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    /// blocks.save("world.txt").unwrap();
+    ///
+    /// assert_eq!(Blocks::load("world.txt").unwrap(), blocks);
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), io::Error> {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        self.print_to(&mut file)?;
+        io::Write::flush(&mut file)
+    }
+    
+    /// Return the world saved in the file at `path` by `save()`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Blocks, io::Error> {
+        Blocks::load_from(&mut io::BufReader::new(File::open(path)?))
+    }
+    
+    /// Return the world read from `buf`, in the format `print()`
+    /// prints it in: one line per stack, in order, holding the stack
+    /// number, a colon, and the blocks from the bottom up. The world
+    /// must be valid, see `from_world()`. Otherwise, an
+    /// `io::ErrorKind::InvalidData` error describing the problem is
+    /// returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io;
+    /// use rust::blocks::Blocks;
+    ///
+    /// let blocks = Blocks::load_from(&mut "0: 0 2\n1: 1\n2:\n".as_bytes()).unwrap();
+    /// assert_eq!(blocks.world(), vec![vec![0, 2], vec![1], vec![]]);
+    ///
+    /// let error = Blocks::load_from(&mut "0: 0 2\n1: 1 2\n2:\n".as_bytes()).unwrap_err();
+    /// assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    /// assert_eq!(error.to_string(), "block 2 is in the world more than once");
+    /// ```
+    pub fn load_from(buf: &mut impl BufRead) -> Result<Blocks, io::Error> {
+        let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut world = Vec::new();
+        
+        for line in buf.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            
+            let number = world.len();
+            let blocks = match line.split_once(':') {
+                Some((stack, blocks)) if stack.trim() == number.to_string() => blocks,
+                _ => return Err(invalid(format!("expected stack {}, got `{}`", number, line))),
+            };
+            
+            let stack = blocks
+                .split_whitespace()
+                .map(|block| block.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .map_err(|_| invalid(format!("invalid block number in `{}`", line)))?;
+            world.push(stack);
         }
         
-        // Push the blocks on top of block `a`, including `a`, onto
-        // block `b`.
-        self.world[k as usize].extend(right_a_vec);
+        Blocks::from_world(world).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+    
+    /// Return a compact key that identifies the world: for each stack,
+    /// in order, its height followed by its blocks from the bottom up.
+    /// Two worlds have the same key if and only if they are equal, so
+    /// keys can stand in for whole worlds, e.g., when deduplicating the
+    /// configurations reachable in a search.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashSet;
+    /// use rust::blocks::Blocks;
+    ///
+    /// let mut blocks = Blocks::new(3).unwrap();
+    /// blocks.move_a_onto_b(2, 0).unwrap();
+    /// assert_eq!(blocks.canonical_key(), vec![2, 0, 2, 1, 1, 0]);
+    ///
+    /// // Different routes to the same world are the same world.
+    /// let mut other = Blocks::new(3).unwrap();
+    /// other.pile_a_over_b(2, 1).unwrap();
+    /// other.move_a_onto_b(2, 0).unwrap();
+    ///
+    /// let seen: HashSet<Blocks> = vec![blocks, other].into_iter().collect();
+    /// assert_eq!(seen.len(), 1);
+    /// ```
+    pub fn canonical_key(&self) -> Vec<u32> {
+        let mut key = Vec::with_capacity(self.world.len() * 2);
+        for stack in self.world.iter() {
+            key.push(stack.len() as u32);
+            key.extend_from_slice(stack);
+        }
         
-        self
+        key
+    }
+    
+    /// Return a frozen copy of the world, which can later be compared
+    /// with other snapshots. See `WorldSnapshot::diff()`.
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot::new(self)
+    }
+    
+    /// Print onto `std::io::stdout` the internal state of the
+    /// `world` attribute for a given `Blocks` instance. See the
+    /// `Display` implementation for the format.
+    pub fn print(&self) {
+        print!("{}", self);
+    }
+    
+    /// Write the internal state of the `world` attribute onto `out`,
+    /// exactly as `print()` would print it onto `std::io::stdout`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    ///
+    /// let blocks = Blocks::new(2).unwrap();
+    /// let mut out = Vec::new();
+    /// blocks.print_to(&mut out).unwrap();
+    ///
+    /// assert_eq!(out, b"0: 0\n1: 1\n");
+    /// ```
+    pub fn print_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
+    
+    /// Write the world onto `out`, drawn in the given `style`.
+    /// `RenderStyle::Lines` is the same as `print_to()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    /// use rust::render::RenderStyle;
+    ///
+    /// let mut blocks = Blocks::new(2).unwrap();
+    /// blocks.move_a_onto_b(0, 1).unwrap();
+    /// let mut out = Vec::new();
+    /// blocks.render_to(&mut out, RenderStyle::Vertical).unwrap();
+    ///
+    /// assert_eq!(out, b"  0\n  1\n---\n0 1\n");
+    /// ```
+    pub fn render_to(&self, out: &mut impl io::Write, style: RenderStyle) -> io::Result<()> {
+        match style {
+            RenderStyle::Lines => self.print_to(out),
+            RenderStyle::Vertical => render::vertical_to(self, out),
+            RenderStyle::Json => render::json_to(self, out),
+        }
+    }
+    
+    //
+    // Private methods.
+    //
+    
+    /// Put the blocks moved by `event` back where they were, as
+    /// described by `inverse_of()`, returning the commands executed.
+    /// Commands that turn out to be illegal are skipped.
+    fn put_back(&mut self, event: &OpEvent) -> Vec<Command> {
+        let mut commands = Vec::new();
+        
+        // Return the moved blocks, topmost first, so that each
+        // `return` only returns one block.
+        let mut moves: Vec<_> = event.moves.iter().collect();
+        moves.sort_by_key(|m| std::cmp::Reverse(m.to.1));
+        for m in moves.iter() {
+            if self.where_is(m.block) != Some((m.block as usize, 0)) {
+                commands.push(Command::Return { a: m.block });
+            }
+            let _ = self.return_a(m.block);
+        }
+        
+        // Put each moved block back on the block that was below it.
+        // Going from the bottom of each stack up, that block is
+        // already in place.
+        moves.sort_by_key(|m| m.from);
+        for m in moves.iter() {
+            let (stack, height) = m.from;
+            let below = match height.checked_sub(1).and_then(|h| self.world[stack].get(h)) {
+                Some(&below) => below,
+                None => continue,
+            };
+            
+            let command = Command::Move { a: m.block, b: below, placement: Placement::Over };
+            if self.execute(&command).is_ok() {
+                commands.push(command);
+            }
+        }
+        
+        commands
+    }
+    
+    /// Rearrange the blocks into `world` from scratch, returning the
+    /// commands executed: `reset`, then `swap` the block meant to be
+    /// at the bottom of each stack into it, then `move` every other
+    /// block over the one meant to be below it.
+    fn rebuild(&mut self, world: &[Vec<u32>]) -> Vec<Command> {
+        let mut commands = vec![Command::Reset];
+        self.reset();
+        
+        // Every block is alone in a stack from here on until it is
+        // moved, so every `swap` is legal.
+        for (stack, blocks) in world.iter().enumerate() {
+            if let (Some(&bottom), Some(&current)) = (blocks.first(), self.world[stack].first()) {
+                if bottom != current {
+                    let command = Command::Swap { a: current, b: bottom };
+                    self.execute(&command).expect("lone blocks can be swapped");
+                    commands.push(command);
+                }
+            }
+        }
+        
+        for blocks in world.iter() {
+            for pair in blocks.windows(2) {
+                let command = Command::Move { a: pair[1], b: pair[0], placement: Placement::Over };
+                self.execute(&command).expect("lone blocks can be moved over blocks in place");
+                commands.push(command);
+            }
+        }
+        
+        commands
+    }
+    
+    /// Panic if the world is corrupt, in debug builds only, and only
+    /// for worlds of up to `DEBUG_VALIDATE_LIMIT` blocks. See
+    /// `validate()`.
+    fn debug_validate(&self) {
+        if self.positions.len() <= DEBUG_VALIDATE_LIMIT {
+            debug_assert_eq!(self.validate(), Ok(()));
+        }
     }
     
     /// Resets the state of the Blocks struct to the
     /// BlockState::Init state and `a` and `b` = None.
     ///
-    /// This can happen when:
+    /// This happens after every `over_b()` or `onto_b()` call,
+    /// and when:
     ///
-    ///  * `a` and `b` are equal
-    ///  * `a` and `b` are in the same stack
-    ///  * `a` or `b` are < 0 (-1 for None)
     ///  * `over_b()` or `onto_b()` are called without
     ///    the appropriate state
     ///  * `move_a()` or `pile_a()` are called without
@@ -398,67 +1168,83 @@ impl Blocks {
         self
     }
     
-    /// Return a 2-tuple of the values in the attributes
-    /// `a` and `b`.
-    fn get_a_and_b(&self) -> (i32, i32) {
-        let a = match self.a {
-            Some(a) => a as i32,
-            None => -1 as i32,
-        };
+    /// Return the coordinates of blocks `a` and `b` if an operation
+    /// on them is legal, else return the appropriate `Err()`. Here
+    /// are the rules:
+    ///
+    /// 1. `a` must not equal `b`
+    /// 2. `a` and `b` must be less than the length of the `world`
+    ///    attribute (a vector)
+    /// 3. blocks `a` and `b` must not be in the same stack of
+    ///    blocks
+    fn check_parameters(
+        &self,
+        a: u32,
+        b: u32
+    ) -> Result<(Position, Position), BlockError> {
+        if a == b {
+            return Err(BlockError::SameBlock(a));
+        }
         
-        let b = match self.b {
-            Some(b) => b as i32,
-            None => -1 as i32,
-        };
+        let position_a = self.where_is(a).ok_or(BlockError::OutOfRange(a))?;
+        let position_b = self.where_is(b).ok_or(BlockError::OutOfRange(b))?;
+        
+        if position_a.0 == position_b.0 {
+            return Err(BlockError::SameStack(a, b));
+        }
         
-        (a, b)
+        Ok((position_a, position_b))
+    }
+    
+    /// Take off every block on top of the block at `height` in stack
+    /// `stack`, returning them from the bottom up. Their entries in
+    /// `positions` are left stale until they are pushed somewhere
+    /// else.
+    fn take_blocks_above(&mut self, stack: usize, height: usize) -> Vec<u32> {
+        self.world[stack].split_off(height + 1)
     }
     
-    /// Return `true` if the parameters for the operations
-    /// to be performed are incorrect. Here are the rules:
+    /// Put every block in `blocks`, which were taken off of a stack
+    /// from the bottom up, in their original positions. The topmost
+    /// block is returned first.
     ///
-    /// 1. `a` must not equal `b`
-    /// 2. `a` and `b` must not be negative
-    /// 3. `a` and `b` must not be greater than the length of
-    ///    the `world` attribute (a vector)
-    /// 4. blocks `a` and `b` must not be in the same stack of
-    ///    blocks
-    fn parameters_invalid(&self) -> bool {
-        let (a, b) = self.get_a_and_b();
-        
-        if a == b ||
-           a < 0 ||
-           b < 0 ||
-           a as usize >= self.world.len() ||
-           b as usize >= self.world.len()
-        {
-            true
-        }
-        else {
-            self.same_stack()
+    /// Blocks are always taken off before any are returned, so that
+    /// the operations stay well defined when a block sits in its own
+    /// stack above other blocks (e.g., after a `swap`).
+    fn return_blocks(&mut self, blocks: Vec<u32>) {
+        for block in blocks.into_iter().rev() {
+            self.push_block(block as usize, block);
         }
     }
     
-    /// Return `true` if blocks `a` and `b` are in the same stack
-    /// of blocks.
-    fn same_stack(&self) -> bool {
-        let (a, b) = self.get_a_and_b();
-        
-        for i in (&self.world).iter() {
-            if let Some(_) = i.iter().find(|&&x| x == a as u32) {
-                if let Some(_) = i.iter().find(|&&x| x == b as u32) {
-                    return true;
-                }
-            }
+    /// Pop the top block off of stack `stack`. The stack must not be
+    /// empty. The block's entry in `positions` is left stale until
+    /// it is pushed somewhere else.
+    fn pop_block(&mut self, stack: usize) -> u32 {
+        self.world[stack].pop().unwrap()
+    }
+    
+    /// Push `block` on top of stack `stack`, recording its new
+    /// position.
+    fn push_block(&mut self, stack: usize, block: u32) {
+        self.positions[block as usize] = (stack, self.world[stack].len());
+        self.world[stack].push(block);
+    }
+    
+    /// Move the block at `height` in stack `from`, and every block
+    /// on top of it, onto the top of stack `to`, keeping their
+    /// order.
+    fn move_pile(&mut self, from: usize, height: usize, to: usize) {
+        let pile = self.world[from].split_off(height);
+        for block in pile {
+            self.push_block(to, block);
         }
-        
-        false
     }
     
     /// Given a `block` number, return a 2-tuple of the coordinates
-    /// for the block.
+    /// for the block, or `None` if the block is not in the world.
     ///
-    /// Given `blocks.world` vector, find the coordinate for
+    /// Given the `world` vector, find the coordinate for
     ///
     /// ```ignore
     /// vec![
@@ -468,21 +1254,65 @@ impl Blocks {
     /// ]
     /// ```
     ///
-    /// such that `blocks.where_is(1)` will return `(0, 1)`.
-    fn where_is(&self, block: i32) -> (i32, i32) {
-        if block < 0 {
-            return (-1, -1);
-        }
-        
-        for (i, stack) in (&self.world).iter().enumerate() {
-            for (j, item) in stack.iter().enumerate() {
-                if *item == block as u32 {
-                    return (i as i32, j as i32);
-                }
+    /// such that `blocks.where_is(1)` will return `Some((0, 1))`.
+    fn where_is(&self, block: u32) -> Option<Position> {
+        self.positions.get(block as usize).copied()
+    }
+}
+
+/// Formats the internal state of the `world` attribute, one line per
+/// stack, each terminated by a newline.
+///
+/// Formats to something akin to:
+///
+/// ```ignore
+/// 0: 0 1
+/// 1:
+/// 2: 2
+/// 3: 3 4 5
+/// 4:
+/// 5:
+/// ```
+///
+/// # Example
+///
+/// ```
+/// use rust::blocks::Blocks;
+///
+/// let mut blocks = Blocks::new(3).unwrap();
+/// blocks.move_a_onto_b(1, 0).unwrap();
+///
+/// assert_eq!(blocks.to_string(), "0: 0 1\n1:\n2: 2\n");
+/// ```
+impl fmt::Display for Blocks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, vec) in self.world.iter().enumerate() {
+            write!(f, "{}:", index)?;
+            for &item in vec.iter() {
+                write!(f, " {}", item)?;
             }
+            writeln!(f)?;
         }
         
-        return (-1, -1);
+        Ok(())
+    }
+}
+
+/// Worlds are equal when their stacks are, since the positions of the
+/// blocks follow from the stacks, and a chained operation in progress
+/// is not part of the world.
+impl PartialEq for Blocks {
+    fn eq(&self, other: &Blocks) -> bool {
+        self.world == other.world
+    }
+}
+
+impl Eq for Blocks {}
+
+/// Hash the world consistently with `PartialEq`.
+impl Hash for Blocks {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.world.hash(state);
     }
 }
 
@@ -493,6 +1323,7 @@ impl Blocks {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::command::ParseMode;
     
     #[test]
     fn blocks_init_new() {
@@ -501,6 +1332,7 @@ mod test {
             Err(_error) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             },
@@ -509,6 +1341,7 @@ mod test {
             Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0], vec![1], vec![2]],
+                positions: vec![(0, 0), (1, 0), (2, 0)],
                 a: None,
                 b: None,
             },
@@ -523,6 +1356,7 @@ mod test {
             Err(_error) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             },
@@ -531,6 +1365,7 @@ mod test {
             Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             },
@@ -545,6 +1380,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -579,6 +1415,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -613,6 +1450,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -647,6 +1485,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -681,6 +1520,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -725,6 +1565,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -769,6 +1610,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -919,7 +1761,7 @@ mod test {
         assert_eq!(BlockState::Init, blocks.state);
         assert_eq!(blocks.a, None);
         assert_eq!(blocks.b, None);
-        
+    
     }
     
     #[test]
@@ -929,6 +1771,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -1079,7 +1922,7 @@ mod test {
         assert_eq!(BlockState::Init, blocks.state);
         assert_eq!(blocks.a, None);
         assert_eq!(blocks.b, None);
-        
+    
     }
     
     #[test]
@@ -1089,6 +1932,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -1239,7 +2083,7 @@ mod test {
         assert_eq!(BlockState::Init, blocks.state);
         assert_eq!(blocks.a, None);
         assert_eq!(blocks.b, None);
-        
+    
     }
     
     #[test]
@@ -1249,6 +2093,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -1401,6 +2246,467 @@ mod test {
         assert_eq!(blocks.b, None);
     }
     
+    #[test]
+    fn blocks_execute() {
+        let mut blocks = Blocks::new(10).unwrap();
+        
+        // Test the sample input as given by the problem instructions.
+        // `pile 8 over 6` is illegal since both blocks are in the same
+        // stack at that point.
+        let results: Vec<Result<(), BlockError>> = [
+            "move 9 onto 1",
+            "move 8 over 1",
+            "move 7 over 1",
+            "move 6 over 1",
+            "pile 8 over 6",
+            "pile 8 over 5",
+            "move 2 over 1",
+            "move 4 over 9",
+        ].iter().map(|input| blocks.execute(&Command::parse(input).unwrap())).collect();
+        assert_eq!(
+            vec![
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+                Err(BlockError::SameStack(8, 6)),
+                Ok(()),
+                Ok(()),
+                Ok(()),
+            ],
+            results
+        );
+        assert_eq!(
+            vec![
+                vec![0],
+                vec![1, 9, 2, 4],
+                vec![],
+                vec![3],
+                vec![],
+                vec![5, 8, 7, 6],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+            ],
+            blocks.world
+        );
+        assert_eq!(BlockState::Init, blocks.state);
+        assert_eq!(blocks.a, None);
+        assert_eq!(blocks.b, None);
+    }
+    
+    #[test]
+    fn blocks_execute_errors() {
+        let mut blocks = Blocks::new(10).unwrap();
+        blocks.execute(&Command::parse("pile 1 onto 2").unwrap()).unwrap();
+        let world = blocks.world.clone();
+        
+        for (input, error) in &[
+            ("move 3 onto 3", BlockError::SameBlock(3)),
+            ("pile 2 over 1", BlockError::SameStack(2, 1)),
+            ("move 10 over 1", BlockError::OutOfRange(10)),
+            ("pile 1 onto 12", BlockError::OutOfRange(12)),
+        ] {
+            assert_eq!(blocks.execute(&Command::parse(input).unwrap()), Err(error.clone()));
+            assert_eq!(world, blocks.world);
+        }
+        
+        for command in &[Command::Print, Command::Quit] {
+            assert_eq!(blocks.execute(command), Err(BlockError::NotAnOperation));
+            assert_eq!(world, blocks.world);
+        }
+        
+        // A dangling chained call does not affect `execute()`.
+        blocks.move_a(5);
+        assert_eq!(blocks.execute(&Command::parse("move 4 onto 5").unwrap()), Ok(()));
+        assert_eq!(blocks.world[5], vec![5, 4]);
+        assert_eq!(BlockState::Init, blocks.state);
+    }
+    
+    #[test]
+    fn blocks_operations_return_errors() {
+        let mut blocks = Blocks::new(5).unwrap();
+        
+        assert_eq!(blocks.move_a_onto_b(1, 1), Err(BlockError::SameBlock(1)));
+        assert_eq!(blocks.move_a_over_b(5, 1), Err(BlockError::OutOfRange(5)));
+        assert_eq!(blocks.pile_a_onto_b(1, 7), Err(BlockError::OutOfRange(7)));
+        assert_eq!(blocks.pile_a_over_b(3, 4), Ok(()));
+        assert_eq!(blocks.move_a_onto_b(4, 3), Err(BlockError::SameStack(4, 3)));
+        assert_eq!(
+            vec![vec![0], vec![1], vec![2], vec![], vec![4, 3]],
+            blocks.world
+        );
+        assert_eq!(
+            BlockError::SameStack(4, 3).to_string(),
+            "blocks 4 and 3 are in the same stack"
+        );
+    }
+    
+    #[test]
+    fn blocks_display() {
+        let mut blocks = Blocks::new(10).unwrap();
+        blocks.move_a(9).onto_b(1)
+              .move_a(8).over_b(1)
+              .move_a(7).over_b(1)
+              .move_a(6).over_b(1)
+              .pile_a(8).over_b(6)
+              .pile_a(8).over_b(5)
+              .move_a(2).over_b(1)
+              .move_a(4).over_b(9);
+        
+        assert_eq!(
+            blocks.to_string(),
+            "0: 0\n1: 1 9 2 4\n2:\n3: 3\n4:\n5: 5 8 7 6\n6:\n7:\n8:\n9:\n"
+        );
+    }
+    
+    #[test]
+    fn blocks_extended_operations() {
+        let mut blocks = Blocks::new(5).unwrap();
+        let extended = |input| Command::parse_with_mode(input, ParseMode::Extended).unwrap();
+        
+        blocks.execute(&extended("pile 1 over 0")).unwrap();
+        blocks.execute(&extended("move 2 over 0")).unwrap();
+        blocks.execute(&extended("move 4 onto 3")).unwrap();
+        assert_eq!(blocks.world, vec![vec![0, 1, 2], vec![], vec![], vec![3, 4], vec![]]);
+        
+        assert_eq!(blocks.execute(&extended("swap 1 4")), Err(BlockError::NotOnTop(1)));
+        blocks.execute(&extended("swap 2 4")).unwrap();
+        assert_eq!(blocks.world, vec![vec![0, 1, 4], vec![], vec![], vec![3, 2], vec![]]);
+        
+        blocks.execute(&extended("return 1")).unwrap();
+        assert_eq!(blocks.world, vec![vec![0], vec![1], vec![], vec![3, 2], vec![4]]);
+        
+        // Block 3 is already home; only block 2 moves.
+        blocks.execute(&extended("return 3")).unwrap();
+        assert_eq!(blocks.world, vec![vec![0], vec![1], vec![2], vec![3], vec![4]]);
+        
+        assert_eq!(blocks.execute(&extended("return 5")), Err(BlockError::OutOfRange(5)));
+        
+        blocks.execute(&extended("pile 0 onto 4")).unwrap();
+        blocks.execute(&extended("reset")).unwrap();
+        assert_eq!(blocks, Blocks::new(5).unwrap());
+    }
+    
+    #[test]
+    fn blocks_validate() {
+        let mut blocks = Blocks::new(4).unwrap();
+        blocks.pile_a_over_b(1, 0).unwrap();
+        assert_eq!(blocks.validate(), Ok(()));
+        
+        let mut broken = Blocks::new(4).unwrap();
+        broken.world.pop();
+        assert_eq!(broken.validate(), Err(WorldError::StackCount(3, 4)));
+        
+        let mut broken = Blocks::new(4).unwrap();
+        broken.world[3].push(7);
+        assert_eq!(broken.validate(), Err(WorldError::UnknownBlock(7)));
+        
+        let mut broken = Blocks::new(4).unwrap();
+        broken.world[2].clear();
+        assert_eq!(broken.validate(), Err(WorldError::MissingBlock(2)));
+        
+        let mut broken = Blocks::new(4).unwrap();
+        broken.world.swap(1, 2);
+        assert_eq!(
+            broken.validate(),
+            Err(WorldError::WrongPosition { block: 2, recorded: (2, 0), actual: (1, 0) })
+        );
+        assert_eq!(
+            broken.validate().unwrap_err().to_string(),
+            "block 2 is recorded at (2, 0) but is at (1, 0)"
+        );
+    }
+    
+    #[test]
+    fn blocks_from_world() {
+        let world = vec![vec![0], vec![1, 9, 2, 4], vec![], vec![3], vec![],
+                         vec![5, 8, 7, 6], vec![], vec![], vec![], vec![]];
+        let mut blocks = Blocks::from_world(world.clone()).unwrap();
+        assert_eq!(blocks.world, world);
+        assert_eq!(blocks.positions[6], (5, 3));
+        assert_eq!(blocks.validate(), Ok(()));
+        
+        // Continue from the sample's final arrangement.
+        blocks.pile_a_onto_b(8, 9).unwrap();
+        assert_eq!(blocks.world[1], vec![1, 9, 8, 7, 6]);
+        assert_eq!(blocks.world[2], vec![2]);
+        assert_eq!(blocks.world[4], vec![4]);
+        
+        assert_eq!(Blocks::from_world(vec![]), Err(WorldError::Empty));
+        assert_eq!(Blocks::from_world(vec![vec![0, 2], vec![1]]), Err(WorldError::UnknownBlock(2)));
+        assert_eq!(Blocks::from_world(vec![vec![0], vec![]]), Err(WorldError::MissingBlock(1)));
+    }
+    
+    #[test]
+    fn blocks_iterators() {
+        let blocks = Blocks::from_world(vec![vec![0, 3], vec![1], vec![], vec![2]]).unwrap();
+        
+        let stacks: Vec<&[u32]> = blocks.stacks().collect();
+        assert_eq!(stacks, vec![&[0, 3][..], &[1], &[], &[2]]);
+        
+        let all: Vec<(u32, usize, usize)> = blocks.blocks().collect();
+        assert_eq!(all, vec![(0, 0, 0), (1, 1, 0), (2, 3, 0), (3, 0, 1)]);
+        
+        // Both views agree on where every block is.
+        for (block, stack, height) in blocks.blocks() {
+            assert_eq!(stacks[stack][height], block);
+        }
+    }
+    
+    #[test]
+    fn blocks_save_and_load() {
+        let path = std::env::temp_dir().join(format!("blocks_save_{}.txt", std::process::id()));
+        let world = vec![vec![0], vec![1, 9, 2, 4], vec![], vec![3], vec![],
+                         vec![5, 8, 7, 6], vec![], vec![], vec![], vec![]];
+        let blocks = Blocks::from_world(world).unwrap();
+        
+        blocks.save(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), blocks.to_string());
+        assert_eq!(Blocks::load(&path).unwrap(), blocks);
+        std::fs::remove_file(&path).unwrap();
+        
+        assert_eq!(Blocks::load(&path).unwrap_err().kind(), io::ErrorKind::NotFound);
+        
+        let load = |text: &str| Blocks::load_from(&mut text.as_bytes()).map_err(|e| e.to_string());
+        assert_eq!(load("0: 0\n\n1: 1\n\n").map(|b| b.world), Ok(vec![vec![0], vec![1]]));
+        assert_eq!(load("0: 0\n2: 1\n"), Err("expected stack 1, got `2: 1`".to_string()));
+        assert_eq!(load("0: 0 x\n1:\n"), Err("invalid block number in `0: 0 x`".to_string()));
+        assert_eq!(load(""), Err("the world has no blocks".to_string()));
+    }
+    
+    #[test]
+    fn blocks_inverse_of() {
+        use crate::command::ParseMode;
+        
+        let n = 8;
+        let mut blocks = Blocks::new(n).unwrap();
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut undone = 0;
+        
+        for _ in 0..3000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let a = (state % u64::from(n)) as u32;
+            let b = ((state >> 32) % u64::from(n)) as u32;
+            let line = match (state >> 20) % 7 {
+                0 => format!("move {} onto {}", a, b),
+                1 => format!("move {} over {}", a, b),
+                2 => format!("pile {} onto {}", a, b),
+                3 => format!("pile {} over {}", a, b),
+                4 => format!("return {}", a),
+                5 => format!("swap {} {}", a, b),
+                _ => String::from("reset"),
+            };
+            let command = Command::parse_with_mode(&line, ParseMode::Extended).unwrap();
+            
+            let before = blocks.clone();
+            let snapshot = blocks.snapshot();
+            if blocks.execute(&command).is_err() {
+                continue;
+            }
+            let event = OpEvent { command, moves: snapshot.diff(&blocks.snapshot()) };
+            
+            // Undo the operation on a copy, then carry on from the
+            // world after it.
+            let mut undo = blocks.clone();
+            let results = undo.execute_all(&blocks.inverse_of(&event));
+            assert!(results.iter().all(Result::is_ok), "{}", line);
+            assert_eq!(undo, before, "{}", line);
+            undone += 1;
+        }
+        
+        assert!(undone > 1000);
+    }
+    
+    #[test]
+    fn blocks_canonical_equality() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::collections::HashSet;
+        
+        let hash = |blocks: &Blocks| {
+            let mut hasher = DefaultHasher::new();
+            blocks.hash(&mut hasher);
+            hasher.finish()
+        };
+        
+        let mut a = Blocks::new(4).unwrap();
+        a.pile_a_over_b(1, 0).unwrap();
+        let mut b = Blocks::new(4).unwrap();
+        b.move_a_onto_b(1, 0).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert_eq!(a.canonical_key(), b.canonical_key());
+        
+        // A chained operation in progress doesn't change the world.
+        b.move_a(3);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        
+        // Stacks are told apart by where they start, not only by
+        // their blocks.
+        let c = Blocks::from_world(vec![vec![0], vec![], vec![1, 2]]).unwrap();
+        let d = Blocks::from_world(vec![vec![0], vec![1, 2], vec![]]).unwrap();
+        assert_ne!(c, d);
+        assert_ne!(c.canonical_key(), d.canonical_key());
+        
+        // Explore every world reachable from 3 blocks with `move`, as
+        // a search would.
+        let mut seen = HashSet::new();
+        let mut frontier = vec![Blocks::new(3).unwrap()];
+        while let Some(blocks) = frontier.pop() {
+            if !seen.insert(blocks.canonical_key()) {
+                continue;
+            }
+            for a in 0..3 {
+                for b in 0..3 {
+                    let mut next = blocks.clone();
+                    if next.move_a_over_b(a, b).is_ok() {
+                        frontier.push(next);
+                    }
+                }
+            }
+        }
+        assert!(seen.len() > 1);
+        assert!(seen.contains(&vec![3, 0, 1, 2, 0, 0]));
+    }
+    
+    #[test]
+    fn blocks_world_accessors() {
+        let world = vec![vec![0, 3], vec![1], vec![], vec![2]];
+        let blocks = Blocks::from_world(world.clone()).unwrap();
+        
+        assert_eq!(blocks.world(), &world[..]);
+        assert_eq!(blocks.into_world(), world);
+    }
+    
+    /// Run `commands` pseudo-random operations on a world of `n`
+    /// blocks, returning how many of them were legal.
+    fn random_operations(n: u32, commands: usize) -> usize {
+        let mut blocks = Blocks::new(n).unwrap();
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut legal = 0;
+        
+        for _ in 0..commands {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            let a = (state % u64::from(n)) as u32;
+            let b = ((state >> 32) % u64::from(n)) as u32;
+            let result = match (state >> 20) % 4 {
+                0 => blocks.move_a_onto_b(a, b),
+                1 => blocks.move_a_over_b(a, b),
+                2 => blocks.pile_a_onto_b(a, b),
+                _ => blocks.pile_a_over_b(a, b),
+            };
+            if result.is_ok() {
+                legal += 1;
+            }
+        }
+        
+        assert_eq!(blocks.validate(), Ok(()));
+        legal
+    }
+    
+    #[test]
+    fn blocks_execute_all() {
+        let commands = vec![
+            Command::Move { a: 9, b: 1, placement: Placement::Onto },
+            Command::Move { a: 9, b: 9, placement: Placement::Over },
+            Command::Pile { a: 8, b: 10, placement: Placement::Over },
+            Command::Print,
+            Command::Pile { a: 1, b: 2, placement: Placement::Onto },
+        ];
+        
+        let mut blocks = Blocks::new(10).unwrap();
+        let results = blocks.execute_all(&commands);
+        assert_eq!(
+            results,
+            vec![
+                Ok(()),
+                Err(BlockError::SameBlock(9)),
+                Err(BlockError::OutOfRange(10)),
+                Err(BlockError::NotAnOperation),
+                Ok(()),
+            ]
+        );
+        assert_eq!(blocks.world[2], vec![2, 1, 9]);
+        assert_eq!(blocks.execute_all(&[]), vec![]);
+    }
+    
+    #[test]
+    fn blocks_queries() {
+        let world = vec![vec![0], vec![1, 9, 2, 4], vec![], vec![3], vec![],
+                         vec![5, 8, 7, 6], vec![], vec![], vec![], vec![]];
+        let blocks = Blocks::from_world(world).unwrap();
+        
+        assert_eq!(blocks.stack_of(7), Some(5));
+        assert_eq!(blocks.height_of(7), Some(2));
+        assert_eq!(blocks.blocks_above(7), Some(1));
+        assert_eq!(blocks.blocks_above(1), Some(3));
+        assert_eq!(blocks.top_of(1), Some(4));
+        assert_eq!(blocks.top_of(3), Some(3));
+        assert_eq!(blocks.top_of(4), None);
+        
+        assert_eq!(blocks.stack_of(10), None);
+        assert_eq!(blocks.height_of(10), None);
+        assert_eq!(blocks.blocks_above(10), None);
+        assert_eq!(blocks.top_of(10), None);
+        
+        // Every query agrees with the world itself.
+        for (block, stack, height) in blocks.blocks() {
+            assert_eq!(blocks.world[stack][height], block);
+            assert_eq!(blocks.stack_of(block), Some(stack));
+            assert_eq!(blocks.height_of(block), Some(height));
+        }
+    }
+    
+    #[test]
+    fn blocks_large_world() {
+        assert!(random_operations(100_000, 100_000) > 99_000);
+    }
+    
+    /// Takes a few seconds in debug builds; run with
+    /// `cargo test --release -- --ignored`.
+    #[test]
+    #[ignore]
+    fn blocks_million_block_world() {
+        assert!(random_operations(1_000_000, 1_000_000) > 999_000);
+    }
+    
+    #[test]
+    fn blocks_positions_follow_world() {
+        let mut blocks = Blocks::new(25).unwrap();
+        
+        // A fixed pseudo-random command stream touching every
+        // operation, including illegal ones.
+        let mut state: u32 = 0x9e37_79b9;
+        for _ in 0..2000 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            let (a, b) = ((state >> 8) % 25, (state >> 16) % 25);
+            let _ = match state % 6 {
+                0 => blocks.move_a_onto_b(a, b),
+                1 => blocks.move_a_over_b(a, b),
+                2 => blocks.pile_a_onto_b(a, b),
+                3 => blocks.pile_a_over_b(a, b),
+                4 => blocks.return_a(a),
+                _ => blocks.swap_a_b(a, b),
+            };
+            
+            for (i, stack) in blocks.world.iter().enumerate() {
+                for (j, &block) in stack.iter().enumerate() {
+                    assert_eq!(blocks.positions[block as usize], (i, j));
+                }
+            }
+        }
+        
+        assert_eq!(blocks.world.iter().map(Vec::len).sum::<usize>(), 25);
+    }
+    
     #[test]
     fn blocks_problem_input_output() {
         let mut blocks = match Blocks::new(10) {
@@ -1408,6 +2714,7 @@ mod test {
             Err(_) => Blocks {
                 state: BlockState::Init,
                 world: vec![vec![0]],
+                positions: vec![(0, 0)],
                 a: None,
                 b: None,
             }
@@ -4,75 +4,72 @@
 //!
 //! Date: 2020-06-04
 
-/// The command state of the attempted command.
-#[derive(Debug, PartialEq)]
-pub enum CommandState {
-    /// Initial state.
-    Init,
-    
-    /// Move state. Indicates that the desired operation is a `move`
-    /// from `a` to `b`.
-    Move,
-    
-    /// Pile state. Indicates that the desired operation is a `pile`
-    /// from `a` to `b`.
-    Pile,
-    
-    /// Onto state. Indicates that the desired operation is a `move`
-    /// or `pile` from `a` `onto` `b`.
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+
+/// Where a `move` or `pile` puts block `a` relative to block `b`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    /// `onto`: directly on top of block `b`, after returning every
+    /// block on top of `b` to its original position.
     Onto,
     
-    /// Over state. Indicates that the desired operation is a `move`
-    /// or `pile` from `a` `over` `b`.
+    /// `over`: on top of the stack containing block `b`.
     Over,
+}
+
+impl fmt::Display for Placement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Placement::Onto => write!(f, "onto"),
+            Placement::Over => write!(f, "over"),
+        }
+    }
+}
+
+/// The grammar accepted by `Command::parse_with_mode()`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+    /// Only the commands defined by the problem are accepted:
+    /// `move`/`pile` ... `onto`/`over` ..., `print` and `quit`.
+    #[default]
+    Strict,
     
-    /// Quit state. Indicates that the desired operation is to print
-    /// the blocks world and then exit the program.
-    Quit,
+    /// The problem's commands plus `return a`, `swap a b` and
+    /// `reset`. These are not part of the problem, so the judge
+    /// never sends them.
+    Extended,
+}
+
+/// A parsed command, such as `move a over b`, where `a` and `b` are
+/// block numbers. Whether the block numbers are valid for a given
+/// world is only known once the command is executed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// `move a onto b` or `move a over b`.
+    Move { a: u32, b: u32, placement: Placement },
     
-    /// Print state. Indicates that the desired operation is to print
-    /// the blocks world.
-    Print,
+    /// `pile a onto b` or `pile a over b`.
+    Pile { a: u32, b: u32, placement: Placement },
     
-    /// Error state. Indicates that there was an error in parsing the
-    /// input command.
-    Error,
+    /// `return a`: return block `a`, and every block on top of it, to
+    /// their original positions. Extended grammar only.
+    Return { a: u32 },
     
-    /// Do state. Indicates that command parsing succeeded within the
-    /// appropriate bounds. "Do" the operations indicated provided by
-    /// the command.
-    Do,
-}
-
-/// `Command` struct that, when initialized, holds the state and
-/// parameters of commands such as `move a over b`, where `a` and `b`
-/// are valid block numbers.
-#[derive(Debug, PartialEq)]
-pub struct Command {
-    /// When an error occurs during parsing, a `String` error message
-    /// will be populated here. If this is set to a non-empty string,
-    /// `Command.state` should be `CommandState::Error`.
-    pub error_msg: String,
-    
-    /// `state` attribute that holds the `CommandState` enum instance.
-    /// See docs for `CommandState` enum.
-    pub state: CommandState,
-    
-    /// One of `CommandState::Init`, `CommandState::Move` or
-    /// `CommandState::Pile`.
-    pub from: CommandState,
-    
-    /// One of `CommandState::Init`, `CommandState::Onto` or
-    /// `CommandState::Over`.
-    pub to: CommandState,
-    
-    /// The `a` parameter value for commands such as `move a over b`
-    /// where `a` is a valid block number.
-    pub a: i32,
-    
-    /// The `b` parameter value for commands such as `move a over b`
-    /// where `b` is a valid block number.
-    pub b: i32,
+    /// `swap a b`: exchange the top blocks `a` and `b`. Extended
+    /// grammar only.
+    Swap { a: u32, b: u32 },
+    
+    /// `reset`: put every block back in its original position.
+    /// Extended grammar only.
+    Reset,
+    
+    /// `print`: print the blocks world.
+    Print,
+    
+    /// `quit`: print the blocks world and then exit the program.
+    Quit,
 }
 
 impl Command {
@@ -94,148 +91,297 @@ impl Command {
     ///   * an unsigned integer (including 0) [may be valid or
     ///     invalid]
     ///
+    /// or are one of `print` (`p`) or `quit` (`q`). If the input is
+    /// not a valid command, a `ParseError` describing the problem,
+    /// and where it is in the input, is returned.
+    ///
     /// # Example
     ///
     /// ```
-    /// use rust::command::{Command, CommandState};
-    /// 
+    /// use rust::command::{Command, Placement};
+    ///
     /// let input = String::from("move 1 onto 3");
     /// let command = Command::parse(&input);
     ///
-    /// assert_eq!(command.state, CommandState::Do);
-    /// assert_eq!(command.from, CommandState::Move);
-    /// assert_eq!(command.to, CommandState::Onto);
-    /// assert_eq!(command.a, 1);
-    /// assert_eq!(command.b, 3);
+    /// assert_eq!(command, Ok(Command::Move { a: 1, b: 3, placement: Placement::Onto }));
     /// ```
-    pub fn parse(input: &String) -> Command {
-        let input = input.trim().to_lowercase();
-        
-        // Default states.
-        let error_msg = String::new();
-        let state = CommandState::Do;
-        let mut from = CommandState::Init;
-        let mut to = CommandState::Init;
-        let mut a = -1;
-        let mut b = -1;
+    pub fn parse(input: &str) -> Result<Command, ParseError> {
+        Command::parse_with_mode(input, ParseMode::Strict)
+    }
+    
+    /// Parse the `input` command like `Command::parse()`, using the
+    /// grammar selected by `mode`.
+    ///
+    /// `ParseMode::Extended` additionally accepts:
+    ///
+    ///   * `return a`: return block `a`, and every block on top of
+    ///     it, to their original positions
+    ///   * `swap a b`: exchange blocks `a` and `b`, which must both
+    ///     be on top of their stacks
+    ///   * `reset`: put every block back in its original position
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::{Command, ParseMode};
+    ///
+    /// let command = Command::parse_with_mode("swap 1 3", ParseMode::Extended);
+    /// assert_eq!(command, Ok(Command::Swap { a: 1, b: 3 }));
+    ///
+    /// let command = Command::parse_with_mode("swap 1 3", ParseMode::Strict);
+    /// assert!(command.is_err());
+    /// ```
+    pub fn parse_with_mode(input: &str, mode: ParseMode) -> Result<Command, ParseError> {
+        // Split the input (e.g., `move 1 onto 3`) string into its
+        // constituent parts, remembering where each one is in the
+        // input so that errors can point at them.
+        let tokens = tokenize(input);
+        let parts: Vec<&str> = tokens.iter().map(|(_, part)| part.as_str()).collect();
+        let error = |kind, token: usize| ParseError {
+            kind,
+            token: Some(token),
+            span: tokens[token].0.clone(),
+        };
         
         // If the user inputs `quit`, `q`, `print`, or `p`, return the
-        // appropriate command instance with the proper states.
-        match input.as_str() {
-            "quit" | "q" => return Command {
-                error_msg,
-                state: CommandState::Quit,
-                from,
-                to,
-                a,
-                b
-            },
-            "print" | "p" => return Command {
-                error_msg,
-                state: CommandState::Print,
-                from,
-                to,
-                a,
-                b
-            },
+        // appropriate command.
+        match parts.as_slice() {
+            ["quit"] | ["q"] => return Ok(Command::Quit),
+            ["print"] | ["p"] => return Ok(Command::Print),
             _ => {},
         }
         
-        // Split the input (e.g., `move 1 onto 3`) string into its
-        // constituent parts. Results in a `Vec<&str>` instance containing
-        // the individual parts of the attempted command.
-        let parts: Vec<&str> = input.split_whitespace().collect();
+        // The extended grammar's commands have fewer than 4 parts, so
+        // they are handled before the usual checks.
+        if mode == ParseMode::Extended {
+            if let Some(command) = Command::parse_extended(&parts, &error) {
+                return command;
+            }
+        }
         
         // After checking for our 1-parameter input, we now must have an
         // input string that contains exactly 4 parts. Else return an
-        // error.
+        // error about the whole command.
         if parts.len() != 4 {
-            return Command {
-                error_msg: format!("Error! Expected 4 input parameters, got {}", parts.len()),
-                state: CommandState::Error,
-                from,
-                to,
-                a,
-                b
+            let span = match (tokens.first(), tokens.last()) {
+                (Some((first, _)), Some((last, _))) => first.start..last.end,
+                _ => 0..0,
             };
+            return Err(ParseError { kind: ParseErrorKind::PartCount(parts.len()), token: None, span });
         }
         
         // Check the first part of the command. It must equal `move` or
         // `pile`. Else return an error.
         if parts[0] != "move" && parts[0] != "pile" {
-            return Command {
-                error_msg: format!("Error! `{}` is not a valid command.", parts[0]),
-                state: CommandState::Error,
-                from,
-                to,
-                a,
-                b
-            };
+            return Err(error(ParseErrorKind::InvalidCommand(parts[0].to_string()), 0));
         }
         
         // Check the third part of the command. It must equal `over` or
         // `onto`. Else return an error.
-        if parts[2] != "over" && parts[2] != "onto" {
-            return Command {
-                error_msg: format!("Error! `{}` is not a valid command.", parts[2]),
-                state: CommandState::Error,
-                from,
-                to,
-                a,
-                b
-            };
-        }
+        let placement = match parts[2] {
+            "onto" => Placement::Onto,
+            "over" => Placement::Over,
+            _ => return Err(error(ParseErrorKind::InvalidCommand(parts[2].to_string()), 2)),
+        };
         
-        // Parse the second part of the command into an unsigned integer,
-        // else return an error.
-        if let Ok(num) = parts[1].parse::<u32>() {
-            a = num as i32;
+        // Parse the second and fourth parts of the command into
+        // unsigned integers, else return an error.
+        let a = parse_block_number(&parts, 1, &error)?;
+        let b = parse_block_number(&parts, 3, &error)?;
+        
+        // Build the command based on the first part of the input
+        // command.
+        if parts[0] == "move" {
+            Ok(Command::Move { a, b, placement })
         }
         else {
-            return Command {
-                error_msg: format!("Error! `{}` is not a valid positive integer.", parts[1]),
-                state: CommandState::Error,
-                from,
-                to,
-                a,
-                b,
-            };
+            Ok(Command::Pile { a, b, placement })
         }
+    }
+    
+    /// Parse the `input` command like `Command::parse_with_mode()`,
+    /// but leniently, so that annotated scripts can be fed to a
+    /// `Robot` as they are: everything from a `#` to the end of the
+    /// line is a comment, and a line that is blank once its comment
+    /// is removed holds no command, so `Ok(None)` is returned.
+    ///
+    /// Repeated whitespace between the parts of a command is accepted
+    /// by every mode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::{Command, ParseMode, Placement};
+    ///
+    /// let command = Command::parse_lenient("move  9 \tonto 1  # the first move", ParseMode::Strict);
+    /// assert_eq!(command, Ok(Some(Command::Move { a: 9, b: 1, placement: Placement::Onto })));
+    ///
+    /// assert_eq!(Command::parse_lenient("# pile them up", ParseMode::Strict), Ok(None));
+    /// assert_eq!(Command::parse_lenient("\n", ParseMode::Strict), Ok(None));
+    /// assert!(Command::parse_lenient("move 9 # onto 1", ParseMode::Strict).is_err());
+    /// ```
+    pub fn parse_lenient(input: &str, mode: ParseMode) -> Result<Option<Command>, ParseError> {
+        let input = match input.find('#') {
+            Some(comment) => &input[..comment],
+            None => input,
+        };
         
-        // Parse the fourth part of the command into an unsigned integer,
-        // else return an error.
-        if let Ok(num) = parts[3].parse::<u32>() {
-            b = num as i32;
-        }
-        else {
-            return Command {
-                error_msg: format!("Error! `{}` is not a valid positive integer.", parts[3]),
-                state: CommandState::Error,
-                from,
-                to,
-                a,
-                b,
-            };
+        if input.trim().is_empty() {
+            return Ok(None);
         }
         
-        // Set the appropriate state based on the first part of the
-        // input command.
-        match parts[0] {
-            "move" => from = CommandState::Move,
-            "pile" => from = CommandState::Pile,
-            _ => {},
+        Command::parse_with_mode(input, mode).map(Some)
+    }
+    
+    /// Parse the extended grammar's `return a`, `swap a b` and
+    /// `reset` commands out of the whitespace separated `parts` of
+    /// the input, building errors with `error`. Return `None` if
+    /// `parts` is not one of them.
+    fn parse_extended(
+        parts: &[&str],
+        error: &impl Fn(ParseErrorKind, usize) -> ParseError
+    ) -> Option<Result<Command, ParseError>> {
+        let command = match parts {
+            ["return", _] => parse_block_number(parts, 1, error).map(|a| Command::Return { a }),
+            ["swap", _, _] => parse_block_number(parts, 1, error)
+                .and_then(|a| Ok(Command::Swap { a, b: parse_block_number(parts, 2, error)? })),
+            ["reset"] => Ok(Command::Reset),
+            _ => return None,
+        };
+        
+        Some(command)
+    }
+}
+
+/// Format the command the way it is written in the input, e.g.,
+/// `move 9 onto 1`, so that it parses back into the same command.
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Command::Move { a, b, placement } => write!(f, "move {} {} {}", a, placement, b),
+            Command::Pile { a, b, placement } => write!(f, "pile {} {} {}", a, placement, b),
+            Command::Return { a } => write!(f, "return {}", a),
+            Command::Swap { a, b } => write!(f, "swap {} {}", a, b),
+            Command::Reset => write!(f, "reset"),
+            Command::Print => write!(f, "print"),
+            Command::Quit => write!(f, "quit"),
         }
+    }
+}
+
+/// What is wrong with a command. See `ParseError`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// The command doesn't have the right number of parts. Holds the
+    /// number of parts it has.
+    PartCount(usize),
+    
+    /// A part that should be a word of the grammar, e.g., `move` or
+    /// `onto`, is not one. Holds the part, in lowercase.
+    InvalidCommand(String),
+    
+    /// A part that should be a block number is not one. Holds the
+    /// part, in lowercase.
+    InvalidNumber(String),
+}
+
+/// An error returned when parsing a `Command` fails, saying what is
+/// wrong and where it is in the input, so that the problem can be
+/// pointed out, e.g., with `underline()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// What is wrong.
+    pub kind: ParseErrorKind,
+    
+    /// The index of the offending whitespace separated part of the
+    /// input, or `None` if the problem is with the whole command.
+    pub token: Option<usize>,
+    
+    /// The byte range of the offending part in the input, or of the
+    /// whole command, without surrounding whitespace, if `token` is
+    /// `None`.
+    pub span: Range<usize>,
+}
+
+impl ParseError {
+    /// Return the first line of `input`, the input the error was
+    /// returned for, followed by a line marking the problem with `^`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::Command;
+    ///
+    /// let input = "move 1 qwer 3";
+    /// let error = Command::parse(input).unwrap_err();
+    ///
+    /// assert_eq!(error.token, Some(2));
+    /// assert_eq!(error.span, 7..11);
+    /// assert_eq!(error.underline(input), "move 1 qwer 3\n       ^^^^");
+    /// ```
+    pub fn underline(&self, input: &str) -> String {
+        let line = input.lines().next().unwrap_or("");
+        
+        // Keep tabs, so that the marker lines up however they are
+        // displayed.
+        let indent: String = input
+            .get(..self.span.start)
+            .unwrap_or("")
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = input.get(self.span.clone()).map_or(0, |part| part.chars().count());
         
-        // Set the appropriate state based on the third part of the
-        // input command.
-        match parts[2] {
-            "over" => to = CommandState::Over,
-            "onto" => to = CommandState::Onto,
+        format!("{}\n{}{}", line, indent, "^".repeat(width.max(1)))
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::PartCount(count) => {
+                write!(f, "Error! Expected 4 input parameters, got {}", count)
+            },
+            ParseErrorKind::InvalidCommand(part) => write!(f, "Error! `{}` is not a valid command.", part),
+            ParseErrorKind::InvalidNumber(part) => {
+                write!(f, "Error! `{}` is not a valid positive integer.", part)
+            },
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Split `input` into its whitespace separated parts, in lowercase,
+/// each with its byte range in `input`.
+fn tokenize(input: &str) -> Vec<(Range<usize>, String)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    
+    for (index, c) in input.char_indices().chain(std::iter::once((input.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(index),
+            (Some(first), true) => {
+                tokens.push((first..index, input[first..index].to_lowercase()));
+                start = None;
+            },
             _ => {},
         }
-        
-        Command { error_msg, state, from, to, a, b }
     }
+    
+    tokens
+}
+
+/// Parse the block number at `index` in the `parts` of a command into
+/// an unsigned integer, else return an error built with `error`.
+fn parse_block_number(
+    parts: &[&str],
+    index: usize,
+    error: &impl Fn(ParseErrorKind, usize) -> ParseError
+) -> Result<u32, ParseError> {
+    parts[index]
+        .parse::<u32>()
+        .map_err(|_| error(ParseErrorKind::InvalidNumber(parts[index].to_string()), index))
 }
 
 #[cfg(test)]
@@ -247,11 +393,7 @@ mod test {
         let input = String::from("move 1 onto 3\r\n");
         let command = Command::parse(&input);
         
-        assert_eq!(command.state, CommandState::Do);
-        assert_eq!(command.from, CommandState::Move);
-        assert_eq!(command.to, CommandState::Onto);
-        assert_eq!(command.a, 1);
-        assert_eq!(command.b, 3);
+        assert_eq!(command, Ok(Command::Move { a: 1, b: 3, placement: Placement::Onto }));
     }
     
     #[test]
@@ -259,11 +401,7 @@ mod test {
         let input = String::from("move 3 over 10\r\n");
         let command = Command::parse(&input);
         
-        assert_eq!(command.state, CommandState::Do);
-        assert_eq!(command.from, CommandState::Move);
-        assert_eq!(command.to, CommandState::Over);
-        assert_eq!(command.a, 3);
-        assert_eq!(command.b, 10);
+        assert_eq!(command, Ok(Command::Move { a: 3, b: 10, placement: Placement::Over }));
     }
     
     #[test]
@@ -271,11 +409,7 @@ mod test {
         let input = String::from("pile 2 onto 100\r\n");
         let command = Command::parse(&input);
         
-        assert_eq!(command.state, CommandState::Do);
-        assert_eq!(command.from, CommandState::Pile);
-        assert_eq!(command.to, CommandState::Onto);
-        assert_eq!(command.a, 2);
-        assert_eq!(command.b, 100);
+        assert_eq!(command, Ok(Command::Pile { a: 2, b: 100, placement: Placement::Onto }));
     }
     
     #[test]
@@ -283,11 +417,39 @@ mod test {
         let input = String::from("pile 200 over 0\r\n");
         let command = Command::parse(&input);
         
-        assert_eq!(command.state, CommandState::Do);
-        assert_eq!(command.from, CommandState::Pile);
-        assert_eq!(command.to, CommandState::Over);
-        assert_eq!(command.a, 200);
-        assert_eq!(command.b, 0);
+        assert_eq!(command, Ok(Command::Pile { a: 200, b: 0, placement: Placement::Over }));
+    }
+    
+    #[test]
+    fn command_parse_print_and_quit() {
+        assert_eq!(Command::parse("print\n"), Ok(Command::Print));
+        assert_eq!(Command::parse("P"), Ok(Command::Print));
+        assert_eq!(Command::parse("quit\r\n"), Ok(Command::Quit));
+        assert_eq!(Command::parse("q"), Ok(Command::Quit));
+    }
+    
+    #[test]
+    fn command_parse_lenient() {
+        assert_eq!(Command::parse_lenient("", ParseMode::Strict), Ok(None));
+        assert_eq!(Command::parse_lenient("   \r\n", ParseMode::Strict), Ok(None));
+        assert_eq!(Command::parse_lenient("# comment\n", ParseMode::Strict), Ok(None));
+        assert_eq!(Command::parse_lenient("  ## indented # comment", ParseMode::Strict), Ok(None));
+        assert_eq!(
+            Command::parse_lenient("pile   2\tover 0#no space\r\n", ParseMode::Strict),
+            Ok(Some(Command::Pile { a: 2, b: 0, placement: Placement::Over }))
+        );
+        assert_eq!(Command::parse_lenient("quit # done", ParseMode::Strict), Ok(Some(Command::Quit)));
+        assert_eq!(
+            Command::parse_lenient("swap 1 2 # extended", ParseMode::Extended),
+            Ok(Some(Command::Swap { a: 1, b: 2 }))
+        );
+        assert_eq!(
+            Command::parse_lenient("swap 1 2 # extended", ParseMode::Strict).map_err(|error| error.to_string()),
+            Err(String::from("Error! Expected 4 input parameters, got 3"))
+        );
+        
+        // Strict parsing doesn't know about comments.
+        assert!(Command::parse("# comment").is_err());
     }
     
     #[test]
@@ -296,15 +458,8 @@ mod test {
         let command = Command::parse(&input);
         
         assert_eq!(
-            command,
-            Command {
-                error_msg: format!("Error! Expected 4 input parameters, got 6"),
-                state: CommandState::Error,
-                from: CommandState::Init,
-                to: CommandState::Init,
-                a: -1,
-                b: -1,
-            }
+            command.map_err(|error| error.to_string()),
+            Err(String::from("Error! Expected 4 input parameters, got 6"))
         );
     }
     
@@ -314,15 +469,8 @@ mod test {
         let command = Command::parse(&input);
         
         assert_eq!(
-            command,
-            Command {
-                error_msg: format!("Error! `asdf` is not a valid command."),
-                state: CommandState::Error,
-                from: CommandState::Init,
-                to: CommandState::Init,
-                a: -1,
-                b: -1,
-            }
+            command.map_err(|error| error.to_string()),
+            Err(String::from("Error! `asdf` is not a valid command."))
         );
     }
     
@@ -332,15 +480,8 @@ mod test {
         let command = Command::parse(&input);
         
         assert_eq!(
-            command,
-            Command {
-                error_msg: format!("Error! `qwer` is not a valid command."),
-                state: CommandState::Error,
-                from: CommandState::Init,
-                to: CommandState::Init,
-                a: -1,
-                b: -1,
-            }
+            command.map_err(|error| error.to_string()),
+            Err(String::from("Error! `qwer` is not a valid command."))
         );
     }
     
@@ -350,15 +491,8 @@ mod test {
         let command = Command::parse(&input);
         
         assert_eq!(
-            command,
-            Command {
-                error_msg: format!("Error! `-1` is not a valid positive integer."),
-                state: CommandState::Error,
-                from: CommandState::Init,
-                to: CommandState::Init,
-                a: -1,
-                b: -1,
-            }
+            command.map_err(|error| error.to_string()),
+            Err(String::from("Error! `-1` is not a valid positive integer."))
         );
     }
     
@@ -368,15 +502,91 @@ mod test {
         let command = Command::parse(&input);
         
         assert_eq!(
-            command,
-            Command {
-                error_msg: format!("Error! `-3` is not a valid positive integer."),
-                state: CommandState::Error,
-                from: CommandState::Init,
-                to: CommandState::Init,
-                a: 2,
-                b: -1,
-            }
+            command.map_err(|error| error.to_string()),
+            Err(String::from("Error! `-3` is not a valid positive integer."))
         );
     }
+    
+    #[test]
+    fn command_parse_error_spans() {
+        let error = Command::parse("  Move 1 QWER 3\r\n").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidCommand(String::from("qwer")));
+        assert_eq!(error.token, Some(2));
+        assert_eq!(error.span, 9..13);
+        assert_eq!(error.underline("  Move 1 QWER 3\r\n"), "  Move 1 QWER 3\n         ^^^^");
+        
+        let error = Command::parse("move 2\tonto -3").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::InvalidNumber(String::from("-3")));
+        assert_eq!(error.token, Some(3));
+        assert_eq!(error.span, 12..14);
+        assert_eq!(error.underline("move 2\tonto -3"), "move 2\tonto -3\n      \t     ^^");
+        
+        let error = Command::parse(" move 1 onto 3 now ").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::PartCount(5));
+        assert_eq!(error.token, None);
+        assert_eq!(error.span, 1..18);
+        
+        let error = Command::parse_with_mode("swap 1 ü", ParseMode::Extended).unwrap_err();
+        assert_eq!(error.token, Some(2));
+        assert_eq!(error.span, 7..9);
+        assert_eq!(error.underline("swap 1 ü"), "swap 1 ü\n       ^");
+        
+        let error = Command::parse_lenient("fly 1 # over 2", ParseMode::Strict).unwrap_err();
+        assert_eq!(error.span, 0..5);
+        
+        let error = Command::parse("").unwrap_err();
+        assert_eq!(error.span, 0..0);
+        assert_eq!(error.underline(""), "\n^");
+    }
+    
+    #[test]
+    fn command_parse_extended() {
+        let command = Command::parse_with_mode("return 4\r\n", ParseMode::Extended);
+        assert_eq!(command, Ok(Command::Return { a: 4 }));
+        
+        let command = Command::parse_with_mode("SWAP 2 7", ParseMode::Extended);
+        assert_eq!(command, Ok(Command::Swap { a: 2, b: 7 }));
+        
+        let command = Command::parse_with_mode("reset\n", ParseMode::Extended);
+        assert_eq!(command, Ok(Command::Reset));
+        
+        // The problem's commands are still accepted.
+        let command = Command::parse_with_mode("pile 2 over 0", ParseMode::Extended);
+        assert_eq!(command, Ok(Command::Pile { a: 2, b: 0, placement: Placement::Over }));
+    }
+    
+    #[test]
+    fn command_display_round_trip() {
+        for input in &[
+            "move 9 onto 1",
+            "move 8 over 1",
+            "pile 0 onto 12",
+            "pile 3 over 4",
+            "return 4",
+            "swap 2 7",
+            "reset",
+            "print",
+            "quit",
+        ] {
+            let command = Command::parse_with_mode(input, ParseMode::Extended).unwrap();
+            assert_eq!(command.to_string(), *input);
+        }
+    }
+    
+    #[test]
+    fn command_parse_extended_errors() {
+        let command = Command::parse_with_mode("return x", ParseMode::Extended);
+        assert_eq!(command.map_err(|error| error.to_string()), Err(String::from("Error! `x` is not a valid positive integer.")));
+        
+        let command = Command::parse_with_mode("swap 1 y", ParseMode::Extended);
+        assert_eq!(command.map_err(|error| error.to_string()), Err(String::from("Error! `y` is not a valid positive integer.")));
+        
+        let command = Command::parse_with_mode("swap 1", ParseMode::Extended);
+        assert!(command.is_err());
+        
+        // Not available in the problem's grammar.
+        for input in &["return 4", "swap 2 7", "reset"] {
+            assert!(Command::parse(input).is_err());
+        }
+    }
 }
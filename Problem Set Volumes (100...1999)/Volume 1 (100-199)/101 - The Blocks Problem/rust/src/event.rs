@@ -0,0 +1,49 @@
+//! `event` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! Events describing the block operations a `Robot` executes, for
+//! observers registered with `Robot::on_operation()`.
+
+use crate::command::Command;
+use crate::snapshot::BlockMove;
+
+/// A callback registered with `Robot::on_operation()`. Observers must
+/// be `Send + Sync`, so that a `Robot` is too.
+pub type Observer = Box<dyn FnMut(&OpEvent) + Send + Sync>;
+
+/// An operation that was executed on a blocks world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpEvent {
+    /// The command that was executed.
+    pub command: Command,
+    
+    /// Every block the operation moved, ordered by block number, with
+    /// where it was before and where it is after the operation.
+    pub moves: Vec<BlockMove>,
+}
+
+impl OpEvent {
+    /// Return how `block` was moved by the operation, or `None` if it
+    /// didn't move.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::Command;
+    /// use rust::event::OpEvent;
+    /// use rust::snapshot::BlockMove;
+    ///
+    /// let event = OpEvent {
+    ///     command: Command::parse("move 2 onto 0").unwrap(),
+    ///     moves: vec![BlockMove { block: 2, from: (2, 0), to: (0, 1) }],
+    /// };
+    /// assert_eq!(event.moved(2).map(|m| m.to), Some((0, 1)));
+    /// assert_eq!(event.moved(1), None);
+    /// ```
+    pub fn moved(&self, block: u32) -> Option<&BlockMove> {
+        self.moves.iter().find(|m| m.block == block)
+    }
+}
@@ -0,0 +1,326 @@
+//! `fuzz` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! Test support for replaying random command streams, valid and
+//! invalid, through a `Robot`, checking that nothing panics and that
+//! every block stays in the world exactly once. The streams are
+//! pseudo-random from fixed seeds, so a failure always replays the
+//! same way.
+
+use crate::blocks::Blocks;
+
+/// A xorshift pseudo-random number generator. Good enough to shuffle
+/// commands around, and needs no dependency.
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Return a generator whose stream is fixed by `seed`.
+    pub(crate) fn new(seed: u64) -> Rng {
+        // A zero state would stay zero forever.
+        Rng { state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1 }
+    }
+    
+    /// Return the next number of the stream.
+    pub(crate) fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+    
+    /// Return a number in `0..n`. `n` must be greater than `0`.
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+    
+    /// Return `true` about once every `n` calls.
+    pub(crate) fn one_in(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+    
+    /// Return one of `items`, which must not be empty.
+    pub(crate) fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+}
+
+/// Return a random line holding a command of the extended grammar for
+/// a world of `n` blocks. The operation may still be illegal, e.g.,
+/// when both blocks are in the same stack.
+pub(crate) fn valid_line(rng: &mut Rng, n: u32) -> String {
+    let a = rng.below(u64::from(n));
+    let b = rng.below(u64::from(n));
+    
+    let words: Vec<String> = match rng.below(20) {
+        0 => vec![String::from("return"), a.to_string()],
+        1 => vec![String::from("swap"), a.to_string(), b.to_string()],
+        2 => vec![String::from(*rng.pick(&["print", "p"]))],
+        3 if rng.one_in(10) => vec![String::from("reset")],
+        _ => vec![
+            String::from(*rng.pick(&["move", "pile"])),
+            a.to_string(),
+            String::from(*rng.pick(&["onto", "over"])),
+            b.to_string(),
+        ],
+    };
+    
+    join_randomly(rng, &words)
+}
+
+/// Return a random line that is not a command, or not one that can be
+/// executed on a world of `n` blocks.
+pub(crate) fn invalid_line(rng: &mut Rng, n: u32) -> String {
+    let n = u64::from(n);
+    let words: Vec<String> = match rng.below(10) {
+        // Blank lines and comments.
+        0 => vec![String::from(*rng.pick(&["", "  ", "\t", "# move 1 onto 2"]))],
+        
+        // Too few or too many parts.
+        1 => vec![String::from("move"), rng.below(n).to_string(), String::from("onto")],
+        2 => vec![
+            String::from("pile"),
+            rng.below(n).to_string(),
+            String::from("over"),
+            rng.below(n).to_string(),
+            String::from("now"),
+        ],
+        
+        // Words that are not part of the grammar.
+        3 => vec![
+            String::from(*rng.pick(&["fly", "mov", "quite", "retur"])),
+            rng.below(n).to_string(),
+            String::from(*rng.pick(&["onto", "under", "ü"])),
+            rng.below(n).to_string(),
+        ],
+        
+        // Numbers that are not block numbers.
+        4 => vec![
+            String::from("move"),
+            String::from(*rng.pick(&["-1", "1.5", "4294967296", "one", "0x1"])),
+            String::from("onto"),
+            rng.below(n).to_string(),
+        ],
+        
+        // Blocks that are not in the world.
+        5 => vec![
+            String::from("pile"),
+            (n + rng.below(3)).to_string(),
+            String::from("over"),
+            rng.below(n).to_string(),
+        ],
+        6 => vec![String::from("swap"), rng.below(n).to_string(), u32::MAX.to_string()],
+        
+        // The same block twice.
+        7 => {
+            let a = rng.below(n).to_string();
+            vec![String::from("move"), a.clone(), String::from("over"), a]
+        },
+        
+        // A command followed by a comment, only valid in lenient mode.
+        8 => {
+            let line = valid_line(rng, n as u32);
+            return format!("{} # {}", line, rng.next());
+        },
+        
+        // Noise.
+        _ => {
+            let noise: String = (0..rng.below(12))
+                .map(|_| *rng.pick(&['a', 'Z', '9', ' ', '\t', '#', '-', 'é', '\u{1F9F1}', '\r']))
+                .collect();
+            vec![noise]
+        },
+    };
+    
+    join_randomly(rng, &words)
+}
+
+/// Return a random script of `lines` lines for a world of `n` blocks,
+/// mostly holding valid commands, without the number of blocks or a
+/// `quit`.
+pub(crate) fn script(rng: &mut Rng, n: u32, lines: usize) -> String {
+    let mut script = String::new();
+    
+    for _ in 0..lines {
+        let line = if rng.one_in(5) {
+            invalid_line(rng, n)
+        }
+        else {
+            valid_line(rng, n)
+        };
+        script.push_str(&line);
+        script.push('\n');
+    }
+    
+    script
+}
+
+/// Assert that every block `0..n` of `blocks` is in the world exactly
+/// once, and that the world is consistent.
+pub(crate) fn assert_world(blocks: &Blocks) {
+    assert_eq!(blocks.validate(), Ok(()));
+    assert_each_block_once(blocks.world());
+}
+
+/// Assert that `world` holds one stack per block, and every block
+/// `0..n` exactly once.
+pub(crate) fn assert_each_block_once(world: &[Vec<u32>]) {
+    let mut blocks: Vec<u32> = world.iter().flatten().copied().collect();
+    blocks.sort_unstable();
+    
+    assert_eq!(blocks, (0..world.len() as u32).collect::<Vec<u32>>(), "world: {:?}", world);
+}
+
+/// Join `words` with random whitespace, in random case.
+fn join_randomly(rng: &mut Rng, words: &[String]) -> String {
+    let mut line = String::from(*rng.pick(&["", "", "", " ", "\t"]));
+    
+    for (index, word) in words.iter().enumerate() {
+        if index > 0 {
+            let separator = *rng.pick(&[" ", " ", " ", "  ", "\t", " \t "]);
+            line.push_str(separator);
+        }
+        if rng.one_in(8) {
+            line.push_str(&word.to_uppercase());
+        }
+        else {
+            line.push_str(word);
+        }
+    }
+    
+    line
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::command::{Command, ParseMode};
+    use crate::robot::{Robot, RobotOptions};
+    
+    /// The number of random scripts each test replays.
+    const SEEDS: u64 = 200;
+    
+    #[test]
+    fn fuzz_valid_lines_parse() {
+        let mut rng = Rng::new(1);
+        
+        for _ in 0..5_000 {
+            let n = 1 + rng.below(24) as u32;
+            let line = valid_line(&mut rng, n);
+            let command = Command::parse_with_mode(&line, ParseMode::Extended)
+                .unwrap_or_else(|error| panic!("`{}`: {}", line, error));
+            
+            // Commands survive being printed and parsed again.
+            assert_eq!(Command::parse_with_mode(&command.to_string(), ParseMode::Extended), Ok(command));
+        }
+    }
+    
+    #[test]
+    fn fuzz_invalid_lines_are_rejected() {
+        let mut rng = Rng::new(2);
+        
+        for _ in 0..5_000 {
+            let n = 1 + rng.below(24) as u32;
+            let line = invalid_line(&mut rng, n);
+            
+            // Whatever the line holds, parsing it must not panic, and
+            // any error must point inside the line.
+            for mode in [ParseMode::Strict, ParseMode::Extended].iter() {
+                if let Err(error) = Command::parse_lenient(&line, *mode) {
+                    assert!(error.span.end <= line.len(), "`{}`: {:?}", line, error);
+                    assert!(line.is_char_boundary(error.span.start));
+                    assert!(line.is_char_boundary(error.span.end));
+                    let _ = error.underline(&line);
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn fuzz_execute_script() {
+        for seed in 0..SEEDS {
+            let mut rng = Rng::new(seed);
+            let n = 1 + rng.below(24) as u32;
+            let options = RobotOptions {
+                parse_mode: *rng.pick(&[ParseMode::Strict, ParseMode::Extended]),
+                lenient: rng.one_in(2),
+                ..RobotOptions::default()
+            };
+            let mut robot = Robot::with_options(n, options);
+            
+            // Check the world after every line, so a failure points at
+            // the line that broke it.
+            for line in script(&mut rng, n, 200).lines() {
+                let _ = robot.execute_script(line);
+                assert_world(&robot.blocks);
+            }
+            
+            let stats = robot.stats();
+            assert!(stats.executed + stats.illegal <= stats.parsed);
+        }
+    }
+    
+    #[test]
+    fn fuzz_main_loop() {
+        let mut rng = Rng::new(3);
+        
+        // Several datasets, some ended by `quit` and one by the end
+        // of the input.
+        let mut input = String::new();
+        let mut sizes = Vec::new();
+        for _ in 0..SEEDS {
+            let n = 1 + rng.below(24) as u32;
+            sizes.push(n);
+            input.push_str(&format!("{}\n", n));
+            input.push_str(&script(&mut rng, n, 100));
+            input.push_str("quit\n");
+        }
+        let n = 1 + rng.below(24) as u32;
+        sizes.push(n);
+        input.push_str(&format!("{}\n", n));
+        input.push_str(&script(&mut rng, n, 100));
+        
+        let options = RobotOptions {
+            parse_mode: ParseMode::Extended,
+            ..RobotOptions::default()
+        };
+        let mut out = Vec::new();
+        Robot::run_with_options(&mut input.as_bytes(), &mut out, options).unwrap();
+        
+        // `print` writes the world too, so only check that every world
+        // printed is whole, and that the last one of each dataset has
+        // the dataset's size.
+        let out = String::from_utf8(out).unwrap();
+        let mut worlds: Vec<Vec<Vec<u32>>> = Vec::new();
+        for line in out.lines() {
+            let (stack, blocks) = line.split_at(line.find(':').unwrap());
+            let stack: usize = stack.parse().unwrap();
+            let blocks: Vec<u32> = blocks[1..].split_whitespace().map(|b| b.parse().unwrap()).collect();
+            if stack == 0 {
+                worlds.push(Vec::new());
+            }
+            worlds.last_mut().unwrap().push(blocks);
+        }
+        for world in worlds.iter() {
+            assert_each_block_once(world);
+        }
+        
+        // Every dataset ends with its world being printed.
+        let mut sizes = sizes.iter();
+        let mut expected = sizes.next();
+        for world in worlds.iter() {
+            if Some(&(world.len() as u32)) == expected {
+                expected = sizes.next();
+            }
+        }
+        assert_eq!(expected, None);
+    }
+}
@@ -0,0 +1,94 @@
+//! `history` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! The history of the operations a `Robot` has executed, whether they
+//! were legal or not. See `Robot::history()`.
+
+use std::fmt;
+use std::io;
+
+use crate::blocks::BlockError;
+use crate::command::Command;
+
+/// An operation a `Robot` was asked to execute, and what came of it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    /// The command that was executed.
+    pub command: Command,
+    
+    /// `Ok(())` if the operation was performed, or why it was
+    /// rejected as illegal.
+    pub result: Result<(), BlockError>,
+}
+
+/// Format the entry as the command, followed by why it was rejected
+/// if it was.
+///
+/// # Example
+///
+/// ```
+/// use rust::blocks::BlockError;
+/// use rust::command::Command;
+/// use rust::history::HistoryEntry;
+///
+/// let entry = HistoryEntry {
+///     command: Command::parse("move 0 onto 1").unwrap(),
+///     result: Err(BlockError::SameStack(0, 1)),
+/// };
+/// assert_eq!(
+///     entry.to_string(),
+///     "move 0 onto 1 (rejected: blocks 0 and 1 are in the same stack)"
+/// );
+/// ```
+impl fmt::Display for HistoryEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            Ok(()) => write!(f, "{}", self.command),
+            Err(error) => write!(f, "{} (rejected: {})", self.command, error),
+        }
+    }
+}
+
+/// Write the numbered list of `entries` onto `out`, one per line,
+/// counting from `1`.
+pub fn write_history(entries: &[HistoryEntry], out: &mut impl io::Write) -> io::Result<()> {
+    for (number, entry) in entries.iter().enumerate() {
+        writeln!(out, "{}: {}", number + 1, entry)?;
+    }
+    
+    Ok(())
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::command::Placement;
+    
+    #[test]
+    fn history_write() {
+        let entries = vec![
+            HistoryEntry {
+                command: Command::Move { a: 1, b: 0, placement: Placement::Onto },
+                result: Ok(()),
+            },
+            HistoryEntry {
+                command: Command::Pile { a: 3, b: 0, placement: Placement::Over },
+                result: Err(BlockError::OutOfRange(3)),
+            },
+        ];
+        
+        let mut out = Vec::new();
+        write_history(&entries, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1: move 1 onto 0\n2: pile 3 over 0 (rejected: block 3 is not in the world)\n"
+        );
+    }
+}
@@ -13,3 +13,12 @@
 pub mod robot;
 pub mod command;
 pub mod blocks;
+pub mod snapshot;
+pub mod event;
+pub mod render;
+pub mod stats;
+pub mod history;
+pub mod rules;
+
+#[cfg(test)]
+mod fuzz;
@@ -1,22 +1,135 @@
-#![allow(unused_imports)]
-#![allow(unused_must_use)]
-
+use std::env;
 use std::io;
+use std::io::Write;
 use std::fs::File;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
 
-use rust::robot::Robot;
+use rust::command::ParseMode;
+use rust::render::RenderStyle;
+use rust::robot::{ErrorMode, Robot, RobotOptions};
 
 fn main() -> std::io::Result<()> {
+    let mut options = RobotOptions::default();
+    let mut input = None;
+    let mut args = env::args().skip(1);
+    
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Also accept the `return a`, `swap a b` and `reset`
+            // commands, which are not part of the problem.
+            "--extended" => options.parse_mode = ParseMode::Extended,
+            
+            // Prompt for input, report mistakes, and print the world
+            // after every command.
+            "--interactive" => options.interactive = true,
+            
+            // Write every executed operation, and the resulting
+            // world, onto `io::stderr`.
+            "--trace" => options.trace = true,
+            
+            // Report rejected commands onto `io::stderr`, either as
+            // they happen or as a summary once a dataset is done.
+            "--errors" => options.report_errors = ErrorMode::Stderr,
+            "--error-summary" => options.report_errors = ErrorMode::CollectIntoSummary,
+            
+            // Stop at the first rejected command, with an error.
+            "--strict" => options.strict = true,
+            
+            // Print every executed command and the world it results
+            // in, optionally pausing for a number of milliseconds
+            // after each one.
+            "--step" => options.step = true,
+            "--step-delay" => match args.next().and_then(|ms| ms.parse::<u64>().ok()) {
+                Some(ms) => {
+                    options.step = true;
+                    options.step_delay = Duration::from_millis(ms);
+                },
+                None => {
+                    eprintln!("Error! `--step-delay` expects a number of milliseconds.");
+                    process::exit(2);
+                },
+            },
+            
+            // Write how many commands were parsed, executed and
+            // ignored onto `io::stderr` once each dataset is done.
+            "--stats" => options.stats = true,
+            
+            // Allow datasets of more than `UVA_MAX_BLOCKS` blocks, up
+            // to a given number of blocks, or without any limit.
+            "--max-blocks" => match args.next().as_deref() {
+                Some("none") => options.max_blocks = None,
+                Some(max) if max.parse::<u32>().is_ok() => {
+                    options.max_blocks = max.parse().ok();
+                },
+                _ => {
+                    eprintln!("Error! `--max-blocks` expects a number of blocks or `none`.");
+                    process::exit(2);
+                },
+            },
+            
+            // Skip blank lines and `#` comments in the input.
+            "--lenient" => options.lenient = true,
+            
+            // Draw the world with the stacks standing upright rather
+            // than one line per stack.
+            "--vertical" => options.render_style = RenderStyle::Vertical,
+            
+            // Print the world as a JSON array of arrays, for other
+            // tools to read.
+            "--json" => options.render_style = RenderStyle::Json,
+            
+            // Record every accepted command into a script that
+            // `Robot::replay()` can re-execute.
+            "--record" => match args.next() {
+                Some(path) => options.record = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("Error! `--record` expects a file path.");
+                    process::exit(2);
+                },
+            },
+            
+            // Anything else that isn't a flag is the input file.
+            _ if !arg.starts_with("--") && input.is_none() => {
+                input = Some(PathBuf::from(arg));
+            },
+            
+            _ => {
+                eprintln!("Error! Unknown argument `{}`, expected `--extended`, `--interactive`, `--trace`, `--strict`, `--lenient`, `--step`, `--step-delay <ms>`, `--stats`, `--max-blocks <n|none>`, `--vertical`, `--json`, `--errors`, `--error-summary`, `--record <path>` or an input file.", arg);
+                process::exit(2);
+            },
+        }
+    }
+    
+    // Read the commands from the input file if one was given, else
+    // from `io::stdin`.
     let stdin = io::stdin();
-    let mut _reader = stdin.lock();
+    let mut reader: Box<dyn io::BufRead> = match input {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(io::BufReader::new(file)),
+            Err(error) => {
+                eprintln!("Error! Cannot open `{}`: {}", path.display(), error);
+                process::exit(1);
+            },
+        },
+        None => Box::new(stdin.lock()),
+    };
+    
+    // Buffer the output so that printing a large world doesn't cost a
+    // write to `io::stdout` per stack.
+    let stdout = io::stdout();
+    let mut writer = io::BufWriter::new(stdout.lock());
     
-    // Uncomment these two lines to read the commands
-    // from a file called "input.txt". TODO: pass in file
-    // as parameters to the execution of this program.
-    //let fh = File::open("input.txt")?;
-    //let mut _reader = io::BufReader::new(fh);
+    let result = Robot::run_with_options(&mut reader, &mut writer, options);
+    writer.flush()?;
     
-    Robot::run(&mut _reader).unwrap();
+    // Errors, such as too many blocks or a rejected command in strict
+    // mode, are reported as they are, rather than debug-formatted.
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
     
     Ok(())
 }
@@ -0,0 +1,156 @@
+//! `render` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! The ways a `Blocks` world can be drawn. The problem requires one
+//! line per stack, but drawing the stacks upright, as they would sit
+//! on the table, is easier to follow when teaching or exploring the
+//! world interactively, and JSON is easier for other tools to read.
+
+use std::io;
+
+use crate::blocks::Blocks;
+
+/// How `Blocks::render_to()` draws the world.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RenderStyle {
+    /// One line per stack, bottom block first, e.g., `0: 0 1`. This
+    /// is the format the problem requires.
+    #[default]
+    Lines,
+    
+    /// The stacks standing upright side by side, tallest row on top,
+    /// above the table and the stack numbers.
+    Vertical,
+    
+    /// A JSON array holding each stack as an array of block numbers,
+    /// bottom block first, on a single line.
+    Json,
+}
+
+/// Write the world of `blocks` onto `out` with the stacks standing
+/// upright. Every column is as wide as the largest block number, so
+/// the blocks line up with the stack numbers below the table.
+///
+/// # Example
+///
+/// ```
+/// use rust::blocks::Blocks;
+/// use rust::render;
+///
+/// let mut blocks = Blocks::new(3).unwrap();
+/// blocks.move_a_onto_b(1, 0).unwrap();
+///
+/// let mut out = Vec::new();
+/// render::vertical_to(&blocks, &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "1\n0   2\n-----\n0 1 2\n");
+/// ```
+pub fn vertical_to(blocks: &Blocks, out: &mut impl io::Write) -> io::Result<()> {
+    let stacks = blocks.world().len();
+    let width = stacks.saturating_sub(1).to_string().len();
+    let height = blocks.world().iter().map(Vec::len).max().unwrap_or(0);
+    let mut row = String::new();
+    
+    for level in (0..height).rev() {
+        row.clear();
+        for (index, stack) in blocks.world().iter().enumerate() {
+            if index > 0 {
+                row.push(' ');
+            }
+            match stack.get(level) {
+                Some(block) => row.push_str(&format!("{:>width$}", block, width = width)),
+                None => row.push_str(&" ".repeat(width)),
+            }
+        }
+        writeln!(out, "{}", row.trim_end())?;
+    }
+    
+    // The table, and the stack numbers beneath it.
+    writeln!(out, "{}", "-".repeat((width + 1) * stacks - 1))?;
+    let numbers: Vec<String> = (0..stacks)
+        .map(|index| format!("{:>width$}", index, width = width))
+        .collect();
+    writeln!(out, "{}", numbers.join(" "))
+}
+
+/// Write the world of `blocks` onto `out` as a JSON array of arrays,
+/// e.g., for web visualizers.
+///
+/// # Example
+///
+/// ```
+/// use rust::blocks::Blocks;
+/// use rust::render;
+///
+/// let mut blocks = Blocks::new(3).unwrap();
+/// blocks.move_a_onto_b(1, 0).unwrap();
+///
+/// let mut out = Vec::new();
+/// render::json_to(&blocks, &mut out).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "[[0,1],[],[2]]\n");
+/// ```
+pub fn json_to(blocks: &Blocks, out: &mut impl io::Write) -> io::Result<()> {
+    let stacks: Vec<String> = blocks
+        .stacks()
+        .map(|stack| {
+            let blocks: Vec<String> = stack.iter().map(u32::to_string).collect();
+            format!("[{}]", blocks.join(","))
+        })
+        .collect();
+    
+    writeln!(out, "[{}]", stacks.join(","))
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn render_vertical_sample() {
+        // The sample output's final world.
+        let blocks = Blocks::from_world(vec![vec![0], vec![1, 9, 2, 4], vec![], vec![3], vec![],
+                                             vec![5, 8, 7, 6], vec![], vec![], vec![], vec![]]).unwrap();
+        
+        let mut out = Vec::new();
+        vertical_to(&blocks, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  4       6\n  2       7\n  9       8\n0 1   3   5\n-------------------\n0 1 2 3 4 5 6 7 8 9\n"
+        );
+    }
+    
+    #[test]
+    fn render_json_sample() {
+        let blocks = Blocks::from_world(vec![vec![0], vec![1, 9, 2, 4], vec![], vec![3], vec![],
+                                             vec![5, 8, 7, 6], vec![], vec![], vec![], vec![]]).unwrap();
+        
+        let mut out = Vec::new();
+        blocks.render_to(&mut out, RenderStyle::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[[0],[1,9,2,4],[],[3],[],[5,8,7,6],[],[],[],[]]\n"
+        );
+    }
+    
+    #[test]
+    fn render_vertical_wide_numbers() {
+        let mut blocks = Blocks::new(11).unwrap();
+        blocks.move_a_onto_b(10, 0).unwrap();
+        
+        let mut out = Vec::new();
+        vertical_to(&blocks, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        
+        assert_eq!(lines[0], "10");
+        assert_eq!(lines[1], " 0  1  2  3  4  5  6  7  8  9");
+        assert_eq!(lines[2], "-".repeat(32));
+        assert_eq!(lines[3], " 0  1  2  3  4  5  6  7  8  9 10");
+    }
+}
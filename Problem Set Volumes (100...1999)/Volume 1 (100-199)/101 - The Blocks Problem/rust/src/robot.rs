@@ -37,32 +37,401 @@
 //! }
 //! ```
 
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
 use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
-use crate::blocks::{Blocks, BlockState};
-use crate::command::{Command, CommandState};
+use crate::blocks::{Blocks, BlockError};
+use crate::command::{Command, ParseError, ParseMode};
+use crate::event::{Observer, OpEvent};
+use crate::history::{self, HistoryEntry};
+use crate::render::RenderStyle;
+use crate::rules::{BlockRules, StandardRules};
+use crate::stats::RobotStats;
+
+/// Setting this environment variable to anything but `0` turns on
+/// `RobotOptions.trace` for every `Robot`.
+pub const TRACE_ENV_VAR: &str = "BLOCKS_TRACE";
+
+/// The largest number of blocks the problem allows (`0 < n < 25`).
+pub const UVA_MAX_BLOCKS: u32 = 24;
+
+/// What a `Robot` does with the commands it rejects while running
+/// its main loop, i.e., lines that cannot be parsed and illegal
+/// operations. Blank lines are never reported.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ErrorMode {
+    /// Ignore them, as the problem requires.
+    #[default]
+    Silent,
+    
+    /// Write each one onto `std::io::stderr` as soon as it happens.
+    Stderr,
+    
+    /// Keep them in `Robot::errors()`, and write a summary of them
+    /// onto `std::io::stderr` once the main loop is done.
+    CollectIntoSummary,
+}
+
+/// Options controlling how a `Robot` reads its input. The defaults
+/// behave exactly as the problem requires.
+#[derive(Debug, Clone)]
+pub struct RobotOptions {
+    /// The command grammar. See `ParseMode`.
+    pub parse_mode: ParseMode,
+    
+    /// If set, every accepted command is recorded into a script at
+    /// this path. See `Robot::record_to()`. If the input holds several
+    /// datasets, each one replaces the script of the one before.
+    pub record: Option<PathBuf>,
+    
+    /// Interactive mode: prompt for each line of input, report
+    /// commands that cannot be parsed or executed, and print the
+    /// world after each executed command. The problem forbids any
+    /// of this output, so it is off by default.
+    pub interactive: bool,
+    
+    /// Trace mode: write every executed operation, and the world it
+    /// results in, onto `std::io::stderr`. Also turned on by the
+    /// `TRACE_ENV_VAR` environment variable.
+    pub trace: bool,
+    
+    /// How the world is drawn by `print`, `quit`, interactive mode and
+    /// trace mode. Anything but `RenderStyle::Lines` breaks the output
+    /// the problem requires.
+    pub render_style: RenderStyle,
+    
+    /// What to do with rejected commands. Only `std::io::stderr` is
+    /// written to, so the output the problem requires is unchanged.
+    /// See `ErrorMode`.
+    pub report_errors: ErrorMode,
+    
+    /// Lenient mode: skip blank lines and `#` comments, so annotated
+    /// scripts can be used as input. See `Command::parse_lenient()`.
+    pub lenient: bool,
+    
+    /// Strict mode: the main loop stops with an `io::ErrorKind::InvalidData`
+    /// error at the first rejected command, instead of ignoring it.
+    /// Unrelated to `ParseMode::Strict`, which is about the grammar.
+    pub strict: bool,
+    
+    /// If set, the main loop stops with an `io::ErrorKind::InvalidData`
+    /// error once it reads more than this many commands without a
+    /// `quit`, guarding against runaway scripted input. Lines that
+    /// hold no command are not counted.
+    pub max_commands: Option<usize>,
+    
+    /// Step mode, for demonstrations: write every executed command,
+    /// followed by the world it results in, onto the output. The
+    /// problem forbids this output, so it is off by default.
+    pub step: bool,
+    
+    /// How long to pause after each step in step mode, so that the
+    /// world can be watched as it changes.
+    pub step_delay: Duration,
+    
+    /// Write a summary of `Robot::stats()` onto `std::io::stderr`
+    /// once the main loop is done.
+    pub stats: bool,
+    
+    /// The largest number of blocks a dataset read by
+    /// `Robot::run_with_options()` may ask for, or `None` for no
+    /// limit. Defaults to `Some(UVA_MAX_BLOCKS)`. Larger setups are
+    /// re-prompted for in interactive mode, and are an
+    /// `io::ErrorKind::InvalidData` error otherwise.
+    pub max_blocks: Option<u32>,
+    
+    /// Keep the history of executed operations. See
+    /// `Robot::history()`. Always kept in interactive mode, where the
+    /// `history` keyword prints it.
+    pub keep_history: bool,
+    
+    /// The rules `move` and `pile` operations follow. Defaults to
+    /// `StandardRules`, the problem's rules. See `BlockRules`.
+    pub rules: Arc<dyn BlockRules>,
+}
+
+impl Default for RobotOptions {
+    fn default() -> RobotOptions {
+        RobotOptions {
+            parse_mode: ParseMode::default(),
+            record: None,
+            interactive: false,
+            trace: false,
+            render_style: RenderStyle::default(),
+            report_errors: ErrorMode::default(),
+            lenient: false,
+            strict: false,
+            max_commands: None,
+            step: false,
+            step_delay: Duration::default(),
+            stats: false,
+            max_blocks: Some(UVA_MAX_BLOCKS),
+            keep_history: false,
+            rules: Arc::new(StandardRules),
+        }
+    }
+}
+
+/// Why a line of a script passed to `Robot::execute_script()` was
+/// rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptError {
+    /// The line is not a command.
+    Parse(ParseError),
+    
+    /// The command is an illegal operation on the world.
+    Illegal(BlockError),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptError::Parse(error) => write!(f, "{}", error),
+            ScriptError::Illegal(error) => write!(f, "Error! Illegal command: {}.", error),
+        }
+    }
+}
+
+impl Error for ScriptError {}
+
+// A `Robot`, and so its `Blocks` world, can be moved to or shared
+// with another thread, so that independent worlds can be simulated in
+// parallel. Fail to compile if that ever stops being true.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Robot>();
+    assert_send_sync::<Blocks>();
+};
 
 /// A robot struct that both runs and provides the main loop to
 /// a fictional robot that manipulates blocks on a table.
-#[derive(Debug)]
 pub struct Robot {
     pub blocks: Blocks,
+    pub options: RobotOptions,
+    
+    /// The script that accepted commands are recorded into, if any.
+    recorder: Option<io::BufWriter<File>>,
+    
+    /// The callbacks registered with `on_operation()`.
+    observers: Vec<Observer>,
+    
+    /// The commands rejected by the main loop, with their line
+    /// numbers, when collecting them. See `errors()`.
+    errors: Vec<(usize, ScriptError)>,
+    
+    /// What has been done so far. See `stats()`.
+    stats: RobotStats,
+    
+    /// The operations executed so far, if kept. See `history()`.
+    history: Vec<HistoryEntry>,
+}
+
+/// A builder for a `Robot`, returned by `Robot::builder()`. Every
+/// option not set keeps its default, as in `RobotOptions::default()`.
+#[derive(Debug, Clone)]
+pub struct RobotBuilder {
+    num_blocks: u32,
+    options: RobotOptions,
+}
+
+impl RobotBuilder {
+    /// Set the number of blocks in the world. Defaults to `1`.
+    pub fn blocks(mut self, num_blocks: u32) -> Self {
+        self.num_blocks = num_blocks;
+        self
+    }
+    
+    /// Set every option at once, replacing any set before.
+    pub fn options(mut self, options: RobotOptions) -> Self {
+        self.options = options;
+        self
+    }
+    
+    /// Set the command grammar. See `RobotOptions.parse_mode`.
+    pub fn parse_mode(mut self, parse_mode: ParseMode) -> Self {
+        self.options.parse_mode = parse_mode;
+        self
+    }
+    
+    /// Turn lenient mode on or off. See `RobotOptions.lenient`.
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.options.lenient = lenient;
+        self
+    }
+    
+    /// Turn strict mode on or off. See `RobotOptions.strict`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+    
+    /// Limit the number of commands the main loop reads. See
+    /// `RobotOptions.max_commands`.
+    pub fn max_commands(mut self, max_commands: usize) -> Self {
+        self.options.max_commands = Some(max_commands);
+        self
+    }
+    
+    /// Set what is done with rejected commands. See
+    /// `RobotOptions.report_errors`.
+    pub fn report_errors(mut self, report_errors: ErrorMode) -> Self {
+        self.options.report_errors = report_errors;
+        self
+    }
+    
+    /// Turn step mode on, pausing for `delay` after each step. See
+    /// `RobotOptions.step`.
+    pub fn step(mut self, delay: Duration) -> Self {
+        self.options.step = true;
+        self.options.step_delay = delay;
+        self
+    }
+    
+    /// Set how the world is drawn. See `RobotOptions.render_style`.
+    pub fn render_style(mut self, render_style: RenderStyle) -> Self {
+        self.options.render_style = render_style;
+        self
+    }
+    
+    /// Set the largest number of blocks a dataset may ask for, or
+    /// `None` for no limit. See `RobotOptions.max_blocks`.
+    pub fn max_blocks(mut self, max_blocks: Option<u32>) -> Self {
+        self.options.max_blocks = max_blocks;
+        self
+    }
+    
+    /// Set the rules `move` and `pile` operations follow. See
+    /// `RobotOptions.rules`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::{Blocks, BlockError};
+    /// use rust::robot::Robot;
+    /// use rust::rules::BlockRules;
+    ///
+    /// /// A variant where `pile a over b` is not allowed.
+    /// #[derive(Debug)]
+    /// struct NoPilingOver;
+    ///
+    /// impl BlockRules for NoPilingOver {
+    ///     fn pile_over(&self, _: &mut Blocks, _: u32, _: u32) -> Result<(), BlockError> {
+    ///         Err(BlockError::NotAnOperation)
+    ///     }
+    /// }
+    ///
+    /// let mut out = Vec::new();
+    /// Robot::builder()
+    ///     .rules(NoPilingOver)
+    ///     .run_to(&mut "3\npile 1 over 0\nmove 2 over 0\nquit\n".as_bytes(), &mut out)
+    ///     .unwrap();
+    /// assert_eq!(out, b"0: 0 2\n1: 1\n2:\n");
+    /// ```
+    pub fn rules(mut self, rules: impl BlockRules + 'static) -> Self {
+        self.options.rules = Arc::new(rules);
+        self
+    }
+    
+    /// Return the `Robot`, as `Robot::with_options()` would.
+    pub fn build(self) -> Robot {
+        Robot::with_options(self.num_blocks, self.options)
+    }
+    
+    /// Run every dataset in `buf` with the options set so far,
+    /// printing onto `out`, as `Robot::run_with_options()` would. The
+    /// number of blocks set with `blocks()` is not used, since each
+    /// dataset starts with its own.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::Robot;
+    ///
+    /// let mut out = Vec::new();
+    /// let error = Robot::builder().run_to(&mut "30\nquit\n".as_bytes(), &mut out).unwrap_err();
+    /// assert_eq!(error.to_string(), "Error! Blocks size must be at most 24, got 30.");
+    ///
+    /// Robot::builder()
+    ///     .max_blocks(None)
+    ///     .run_to(&mut "30\nquit\n".as_bytes(), &mut out)
+    ///     .unwrap();
+    /// assert_eq!(String::from_utf8(out).unwrap().lines().count(), 30);
+    /// ```
+    pub fn run_to(
+        self,
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        Robot::run_with_options(buf, out, self.options)
+    }
+}
+
+impl fmt::Debug for Robot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Robot")
+            .field("blocks", &self.blocks)
+            .field("options", &self.options)
+            .field("recorder", &self.recorder)
+            .field("observers", &self.observers.len())
+            .field("errors", &self.errors)
+            .field("stats", &self.stats)
+            .field("history", &self.history)
+            .finish()
+    }
 }
 
 impl Robot {
     /// Return a new `Robot` instance containing a `Blocks` world.
     pub fn new(num_blocks: u32) -> Robot {
-        let blocks = match Blocks::new(num_blocks) {
-            Ok(blocks) => blocks,
-            Err(_) => Blocks {
-                state: BlockState::Init,
-                world: vec![vec![0]],
-                a: None,
-                b: None,
-            },
-        };
+        Robot::with_options(num_blocks, RobotOptions::default())
+    }
+    
+    /// Return a `RobotBuilder`, for configuring a `Robot` one option
+    /// at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::io;
+    /// use rust::robot::Robot;
+    ///
+    /// let mut robot = Robot::builder().blocks(10).strict(true).max_commands(1000).build();
+    /// let mut input = "move 9 onto 1\nmove 9 onto 9\nquit\n".as_bytes();
+    ///
+    /// let error = robot.main_loop_to(&mut input, &mut Vec::new()).unwrap_err();
+    /// assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    /// assert_eq!(error.to_string(), "line 2: Error! Illegal command: `a` and `b` are the same block (9).");
+    /// ```
+    pub fn builder() -> RobotBuilder {
+        RobotBuilder {
+            num_blocks: 1,
+            options: RobotOptions::default(),
+        }
+    }
+    
+    /// Return a new `Robot` instance containing a `Blocks` world,
+    /// configured by `options`.
+    pub fn with_options(num_blocks: u32, mut options: RobotOptions) -> Robot {
+        options.trace |= env::var_os(TRACE_ENV_VAR).is_some_and(|value| value != "0");
+        
+        // A world of no blocks is made a world of one.
+        let blocks = Blocks::new(num_blocks.max(1)).expect("there is at least one block");
         Robot {
-            blocks
+            blocks,
+            options,
+            recorder: None,
+            observers: Vec::new(),
+            errors: Vec::new(),
+            stats: RobotStats::default(),
+            history: Vec::new(),
         }
     }
     
@@ -70,6 +439,12 @@ impl Robot {
     /// until the user `quit`s. `buf` must implement `io::BufRead` (and
     /// thus can be from `io::stdin` or `io::BufReader`).
     ///
+    /// The input may hold several independent datasets, one after the
+    /// other. Each starts with its number of blocks and ends with
+    /// `quit`, which prints its world; the next dataset then starts
+    /// with a fresh world. Reading stops at the end of the input, or
+    /// when `quit` is given instead of a number of blocks.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -102,13 +477,110 @@ impl Robot {
     ///     Ok(())
     /// }
     /// ```
-    pub fn run(mut buf: &mut impl io::BufRead) -> Result<(), io::Error> {
+    pub fn run(buf: &mut impl io::BufRead) -> Result<(), io::Error> {
+        Robot::run_to(buf, &mut io::stdout())
+    }
+    
+    /// Like `Robot::run()`, but read the input from the file at
+    /// `path`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust::robot::Robot;
+    ///
+    /// Robot::run_from_path("input.txt").unwrap();
+    /// ```
+    pub fn run_from_path<P: AsRef<Path>>(path: P) -> Result<(), io::Error> {
+        let mut reader = io::BufReader::new(File::open(path)?);
+        Robot::run(&mut reader)
+    }
+    
+    /// Like `Robot::run()`, but print the blocks world onto `out`
+    /// instead of `std::io::stdout`, so the output can be captured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::Robot;
+    ///
+    /// let mut input = "3\nmove 2 onto 0\nquit\n".as_bytes();
+    /// let mut out = Vec::new();
+    ///
+    /// Robot::run_to(&mut input, &mut out).unwrap();
+    /// assert_eq!(out, b"0: 0 2\n1: 1\n2:\n");
+    /// ```
+    pub fn run_to(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write
+    ) -> Result<(), io::Error> {
+        Robot::run_with_options(buf, out, RobotOptions::default())
+    }
+    
+    /// Run the whole `input` (the number of blocks followed by the
+    /// commands, possibly for several datasets) and return everything
+    /// that would have been printed, without touching
+    /// `std::io::stdin` or `std::io::stdout`. Returns an `Err()` as
+    /// `Robot::run_to()` does, e.g., for a dataset of more than
+    /// `UVA_MAX_BLOCKS` blocks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::Robot;
+    ///
+    /// let output = Robot::run_str("3\nmove 2 onto 0\npile 0 over 1\nquit\n").unwrap();
+    /// assert_eq!(output, "0:\n1: 1 0 2\n2:\n");
+    ///
+    /// assert!(Robot::run_str("25\nquit\n").is_err());
+    /// ```
+    pub fn run_str(input: &str) -> Result<String, io::Error> {
+        let mut out = Vec::new();
+        Robot::run_to(&mut input.as_bytes(), &mut out)?;
+        
+        Ok(String::from_utf8(out).expect("the world is printed as UTF-8"))
+    }
+    
+    /// Like `Robot::run_to()`, but the `Robot` is configured by
+    /// `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::ParseMode;
+    /// use rust::robot::{Robot, RobotOptions};
+    ///
+    /// let mut input = "3\nmove 2 onto 0\nswap 2 1\nquit\n".as_bytes();
+    /// let mut out = Vec::new();
+    /// let options = RobotOptions {
+    ///     parse_mode: ParseMode::Extended,
+    ///     ..RobotOptions::default()
+    /// };
+    ///
+    /// Robot::run_with_options(&mut input, &mut out, options).unwrap();
+    /// assert_eq!(out, b"0: 0 1\n1: 2\n2:\n");
+    /// ```
+    pub fn run_with_options(
+        mut buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        options: RobotOptions
+    ) -> Result<(), io::Error> {
         // Read one line of setup input to determine the blocks size.
         // Reading in a loop so we can re-prompt the user if they
-        // enter an invalid value.
+        // enter an invalid value, and so we can read the next
+        // dataset once one is done.
         loop {
+            if options.interactive {
+                write!(out, "Number of blocks: ")?;
+                out.flush()?;
+            }
+            
             let mut setup = String::new();
-            buf.read_line(&mut setup)?;
+            
+            // There are no more datasets once the input is exhausted.
+            if buf.read_line(&mut setup)? == 0 {
+                return Ok(());
+            }
             
             if let Ok(num_blocks) = setup.trim().parse::<u32>() {
                 if num_blocks == 0 {
@@ -116,13 +588,34 @@ impl Robot {
                     continue;
                 }
                 
+                if let Some(max_blocks) = options.max_blocks.filter(|&max| num_blocks > max) {
+                    let message = format!(
+                        "Error! Blocks size must be at most {}, got {}.",
+                        max_blocks, num_blocks
+                    );
+                    if options.interactive {
+                        eprintln!("{}", message);
+                        continue;
+                    }
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+                }
+                
                 // Create a Robot instance containing Blocks of the
                 // specified size, and run it.
-                return Robot::new(num_blocks).main_loop(&mut buf);
+                let mut robot = Robot::with_options(num_blocks, options.clone());
+                if let Some(path) = &options.record {
+                    robot.record_to(path)?;
+                }
+                
+                robot.main_loop_to(&mut buf, out)?;
             }
             else if setup.trim() == "q" || setup.trim() == "quit" {
                 return Ok(());
             }
+            else if setup.trim().is_empty() {
+                // Blank lines may separate datasets.
+                continue;
+            }
             else {
                 eprintln!("Error! Please enter the desired blocks size as a positive integer.");
             }
@@ -131,7 +624,8 @@ impl Robot {
     
     /// The main program loop.
     ///
-    /// This loop runs until a `quit` command is received. On each
+    /// This loop runs until a `quit` command is received, or the input
+    /// ends, which is treated the same. On each
     /// iteration of the loop the `Robot` waits for a line of input,
     /// which it parses into a `Command` that it then executes.
     ///
@@ -148,86 +642,1133 @@ impl Robot {
     /// Robot::new(num_blocks).main_loop(&mut buf);
     /// ```
     pub fn main_loop(&mut self, buf: &mut impl io::BufRead) -> Result<(), io::Error> {
+        self.main_loop_to(buf, &mut io::stdout())
+    }
+    
+    /// Like `Robot::main_loop()`, but print the blocks world onto `out`
+    /// instead of `std::io::stdout`.
+    pub fn main_loop_to(
+        &mut self,
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write
+    ) -> Result<(), io::Error> {
         let mut input = String::new();
+        let mut line = 0;
+        let mut commands = 0;
         
         loop {
+            if self.options.interactive {
+                write!(out, "> ")?;
+                out.flush()?;
+            }
+            
             // Empty the buffer without touching its capacity.
             input.clear();
             
-            // Read a command from our input.
-            buf.read_line(&mut input)?;
+            // Read a command from our input. The end of the input
+            // counts as `quit`, since otherwise every further read
+            // would be an empty line.
+            if buf.read_line(&mut input)? == 0 {
+                self.print_to(out)?;
+                break;
+            }
+            line += 1;
+            
+            // Interactive sessions have a few keywords of their own,
+            // which are not commands.
+            if self.options.interactive && self.session_command(&input, out)? {
+                continue;
+            }
             
-            // Parse the input command.
-            let command = Command::parse(&input);
+            // Parse the input command, skipping lines that hold none
+            // if we're lenient.
+            let command = match self.parse(&input) {
+                Ok(Some(command)) => Ok(command),
+                Ok(None) => continue,
+                Err(error_msg) => Err(error_msg),
+            };
             
-            // Based on the state of the parsed command, we match the
-            // command state with its appropriate arms to produce the
-            // desired output.
-            match command.state {
-                // This should theoretically never happen.
-                CommandState::Init => eprintln!("Command is init??"),
+            commands += 1;
+            if self.options.max_commands.is_some_and(|max| commands > max) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: more than {} commands without a `quit`", line, commands - 1)
+                ));
+            }
+            
+            // Based on the parsed command, we match it with its
+            // appropriate arms to produce the desired output.
+            match command {
+                // Print the state of the blocks world onto `out`.
+                Ok(Command::Print) => self.print_to(out)?,
                 
-                // Print the state of the blocks world onto
-                // `std::io::stdout`.
-                CommandState::Print => self.blocks.print(),
+                // Print the state of the blocks world onto `out` and
+                // then quit the program.
+                Ok(Command::Quit) => { self.print_to(out)?; break; },
                 
-                // Print the state of the blocks world onto
-                // `std::io::stdout` and then quit the program.
-                CommandState::Quit => { self.blocks.print(); break; },
+                // Perform the requested command operation (including
+                // the extended grammar's, if enabled). This is where
+                // the magic happens. Illegal operations are ignored,
+                // as required by the problem, unless we're
+                // interactive.
+                Ok(command) => match self.execute(&command) {
+                    Ok(()) => {
+                        self.record(&input)?;
+                        if self.options.step {
+                            self.step_to(out, &command)?;
+                        }
+                        else if self.options.interactive {
+                            self.print_to(out)?;
+                        }
+                    },
+                    Err(error) => {
+                        let error = ScriptError::Illegal(error);
+                        if self.options.interactive {
+                            writeln!(out, "{}", error)?;
+                        }
+                        self.report(line, error)?;
+                    },
+                },
                 
                 // During development, we printed the error messages
                 // onto `std::io::stdout`, but since this program
-                // can't output any error messages, we ignore them.
-                CommandState::Error => {},
-                
-                // Perform the requested command operation. This is
-                // where the magic happens.
-                CommandState::Do => {
-                    match command.from {
-                        // Move `a`.
-                        CommandState::Move => {
-                            match command.to {
-                                // Over `b`.
-                                CommandState::Over => {
-                                    self.blocks.move_a(command.a as u32).over_b(command.b as u32);
-                                },
-                                
-                                // Onto `b`.
-                                CommandState::Onto => {
-                                    self.blocks.move_a(command.a as u32).onto_b(command.b as u32);
-                                },
-                                
-                                // Catch all.
-                                _ => {},
-                            }
-                        },
-                        
-                        // Pile `a`.
-                        CommandState::Pile => {
-                            match command.to {
-                                // Over `b`.
-                                CommandState::Over => {
-                                    self.blocks.pile_a(command.a as u32).over_b(command.b as u32);
-                                },
-                                
-                                // Onto `b`.
-                                CommandState::Onto => {
-                                    self.blocks.pile_a(command.a as u32).onto_b(command.b as u32);
-                                },
-                                _ => {},
-                            }
-                        },
-                        
-                        // Catch all.
-                        _ => {},
+                // can't output any error messages, we ignore them
+                // unless we're interactive, reporting errors or
+                // strict.
+                Err(error_msg) => {
+                    if self.options.interactive {
+                        writeln!(out, "{}", error_msg)?;
+                        if !input.trim().is_empty() {
+                            writeln!(out, "{}", error_msg.underline(&input))?;
+                        }
+                    }
+                    if !input.trim().is_empty() {
+                        self.report(line, ScriptError::Parse(error_msg))?;
                     }
                 },
-                
-                // Catch all.
-                _ => {},
             }
         }
         
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.flush()?;
+        }
+        
+        if self.options.report_errors == ErrorMode::CollectIntoSummary {
+            // Reporting is best effort, like tracing.
+            let _ = self.summary_to(&mut io::stderr().lock());
+        }
+        
+        if self.options.stats {
+            eprintln!("{}", self.stats);
+        }
+        
+        Ok(())
+    }
+    
+    /// Return the commands rejected by the main loop so far, with the
+    /// number of the line they were on, counting from the first line
+    /// after the number of blocks. Only collected when
+    /// `RobotOptions.report_errors` is `ErrorMode::CollectIntoSummary`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::BlockError;
+    /// use rust::robot::{ErrorMode, Robot, RobotOptions, ScriptError};
+    ///
+    /// let mut robot = Robot::with_options(3, RobotOptions {
+    ///     report_errors: ErrorMode::CollectIntoSummary,
+    ///     ..RobotOptions::default()
+    /// });
+    /// let mut input = "move 1 onto 0\nmove 0 onto 1\nquit\n".as_bytes();
+    /// robot.main_loop_to(&mut input, &mut Vec::new()).unwrap();
+    ///
+    /// assert_eq!(robot.errors(), &[(2, ScriptError::Illegal(BlockError::SameStack(0, 1)))]);
+    /// ```
+    pub fn errors(&self) -> &[(usize, ScriptError)] {
+        &self.errors
+    }
+    
+    /// Consume the `Robot`, returning the stacks of blocks of its
+    /// world, e.g., once its main loop is done. See `Blocks::world()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::Robot;
+    ///
+    /// let mut robot = Robot::new(3);
+    /// robot.main_loop_to(&mut "pile 0 over 2\nquit\n".as_bytes(), &mut Vec::new()).unwrap();
+    ///
+    /// assert_eq!(robot.into_world(), vec![vec![], vec![1], vec![2, 0]]);
+    /// ```
+    pub fn into_world(self) -> Vec<Vec<u32>> {
+        self.blocks.into_world()
+    }
+    
+    /// Return how many commands this `Robot` has parsed, executed and
+    /// ignored so far, through its main loop, `execute_script()` or
+    /// `execute()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::Robot;
+    /// use rust::stats::RobotStats;
+    ///
+    /// let mut robot = Robot::new(3);
+    /// robot.execute_script("move 1 onto 0\nmove 0 onto 1\nfly away\nquit\n");
+    ///
+    /// assert_eq!(robot.stats(), RobotStats { parsed: 3, unparsed: 1, executed: 1, illegal: 1 });
+    /// ```
+    pub fn stats(&self) -> RobotStats {
+        self.stats
+    }
+    
+    /// Return every operation executed so far, in order, with its
+    /// result, if `RobotOptions.keep_history` is set or the `Robot` is
+    /// interactive. Otherwise, nothing is kept.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::robot::{Robot, RobotOptions};
+    ///
+    /// let mut robot = Robot::with_options(3, RobotOptions {
+    ///     keep_history: true,
+    ///     ..RobotOptions::default()
+    /// });
+    /// robot.execute_script("move 1 onto 0\nmove 0 onto 1\n");
+    ///
+    /// let history: Vec<String> = robot.history().iter().map(|entry| entry.to_string()).collect();
+    /// assert_eq!(history, vec![
+    ///     "move 1 onto 0",
+    ///     "move 0 onto 1 (rejected: blocks 0 and 1 are in the same stack)",
+    /// ]);
+    /// ```
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.history
+    }
+    
+    /// Register `observer` to be called with an `OpEvent` for every
+    /// operation this `Robot` executes, e.g., to animate or log the
+    /// world as it changes. Illegal operations, which are ignored,
+    /// are not reported.
+    ///
+    /// # Example
+    ///
+    /// Observers must be `Send + Sync`, so share any state they update
+    /// through an `Arc<Mutex<_>>` rather than an `Rc<RefCell<_>>`.
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use rust::command::Command;
+    /// use rust::robot::Robot;
+    ///
+    /// let events = Arc::new(Mutex::new(Vec::new()));
+    /// let mut robot = Robot::new(3);
+    ///
+    /// let log = Arc::clone(&events);
+    /// robot.on_operation(move |event| log.lock().unwrap().push(event.clone()));
+    ///
+    /// robot.execute(&Command::parse("move 2 onto 0").unwrap()).unwrap();
+    /// robot.execute(&Command::parse("move 0 onto 2").unwrap()).unwrap_err();
+    ///
+    /// let events = events.lock().unwrap();
+    /// assert_eq!(events.len(), 1);
+    /// assert_eq!(events[0].moved(2).map(|m| (m.from, m.to)), Some(((2, 0), (0, 1))));
+    /// ```
+    pub fn on_operation<F: FnMut(&OpEvent) + Send + Sync + 'static>(&mut self, observer: F) {
+        self.observers.push(Box::new(observer));
+    }
+    
+    /// Execute `command` on the blocks world, like
+    /// `Blocks::execute_with()` with `RobotOptions.rules`, tracing it if trace mode is on and
+    /// reporting it to every observer registered with
+    /// `on_operation()`.
+    pub fn execute(&mut self, command: &Command) -> Result<(), BlockError> {
+        // Only pay for a snapshot when someone is watching.
+        let before = if self.observers.is_empty() {
+            None
+        }
+        else {
+            Some(self.blocks.snapshot())
+        };
+        
+        let result = self.blocks.execute_with(command, &*self.options.rules);
+        match result {
+            Ok(()) => self.stats.executed += 1,
+            Err(_) => self.stats.illegal += 1,
+        }
+        
+        if self.options.keep_history || self.options.interactive {
+            self.history.push(HistoryEntry {
+                command: command.clone(),
+                result: result.clone(),
+            });
+        }
+        
+        if self.options.trace {
+            // Tracing is best effort; failing to write onto
+            // `std::io::stderr` must not stop the robot.
+            let _ = self.trace_to(&mut io::stderr().lock(), command, &result);
+        }
+        
+        if let (Ok(()), Some(before)) = (&result, before) {
+            let event = OpEvent {
+                command: command.clone(),
+                moves: before.diff(&self.blocks.snapshot()),
+            };
+            for observer in self.observers.iter_mut() {
+                observer(&event);
+            }
+        }
+        
+        result
+    }
+    
+    /// Parse and execute every line of `script` in order, like
+    /// `execute()`, returning the result of each line. Blank lines are
+    /// skipped and have no result, as are comments if
+    /// `RobotOptions.lenient` is set, `print` does nothing, and `quit`
+    /// stops the script. Lines that cannot be parsed or executed are
+    /// ignored, so the rest of the script is still executed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::BlockError;
+    /// use rust::robot::{Robot, ScriptError};
+    ///
+    /// let mut robot = Robot::new(3);
+    /// let results = robot.execute_script("move 1 onto 0\nmove 0 onto 1\n\nfly 2\nquit\nmove 2 over 0\n");
+    ///
+    /// assert_eq!(results.len(), 4);
+    /// assert_eq!(results[0], Ok(()));
+    /// assert_eq!(results[1], Err(ScriptError::Illegal(BlockError::SameStack(0, 1))));
+    /// assert!(matches!(results[2], Err(ScriptError::Parse(_))));
+    /// assert_eq!(robot.blocks.world(), vec![vec![0, 1], vec![], vec![2]]);
+    /// ```
+    pub fn execute_script(&mut self, script: &str) -> Vec<Result<(), ScriptError>> {
+        let mut results = Vec::new();
+        
+        for line in script.lines().filter(|line| !line.trim().is_empty()) {
+            let result = match self.parse(line) {
+                Ok(None) => continue,
+                Ok(Some(Command::Print)) => Ok(()),
+                Ok(Some(Command::Quit)) => {
+                    results.push(Ok(()));
+                    break;
+                },
+                Ok(Some(command)) => self.execute(&command).map_err(ScriptError::Illegal),
+                Err(message) => Err(ScriptError::Parse(message)),
+            };
+            results.push(result);
+        }
+        
+        results
+    }
+    
+    /// Start recording every command that this `Robot` accepts into a
+    /// new script at `path`, replacing any existing file.
+    ///
+    /// The script is itself valid input: its first line is the number
+    /// of blocks, followed by one command per line. Only commands that
+    /// were executed are recorded, so replaying it with
+    /// `Robot::replay()` reproduces the same world.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// fn main() -> std::io::Result<()> {
+    ///     use std::io;
+    ///     use rust::robot::Robot;
+    ///
+    ///     let stdin = io::stdin();
+    ///     let mut handle = stdin.lock();
+    ///
+    ///     let mut robot = Robot::new(10);
+    ///     robot.record_to("session.txt")?;
+    ///     robot.main_loop(&mut handle)?;
+    ///
+    ///     let replayed = Robot::replay("session.txt")?;
+    ///     assert_eq!(replayed.blocks, robot.blocks);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn record_to<P: AsRef<Path>>(&mut self, path: P) -> Result<(), io::Error> {
+        let mut recorder = io::BufWriter::new(File::create(path)?);
+        writeln!(recorder, "{}", self.blocks.world().len())?;
+        self.recorder = Some(recorder);
+        
+        Ok(())
+    }
+    
+    /// Re-execute the script at `path`, as written by
+    /// `Robot::record_to()`, against a fresh world. Returns the
+    /// `Robot` in its final state without printing anything.
+    ///
+    /// The extended grammar is accepted, so scripts recorded with it
+    /// replay as well. Lines that are not block operations, and
+    /// operations that are illegal, are ignored just like during a
+    /// normal run. Returns an `Err()` if the script cannot be read or
+    /// doesn't start with a valid number of blocks.
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<Robot, io::Error> {
+        let script = std::fs::read_to_string(path)?;
+        let mut lines = script.lines();
+        
+        let num_blocks = lines
+            .next()
+            .and_then(|line| line.trim().parse::<u32>().ok())
+            .filter(|&num_blocks| num_blocks > 0)
+            .ok_or_else(|| io::Error::new(
+                io::ErrorKind::InvalidData,
+                "script must start with a positive number of blocks"
+            ))?;
+        
+        let options = RobotOptions {
+            parse_mode: ParseMode::Extended,
+            ..RobotOptions::default()
+        };
+        let mut robot = Robot::with_options(num_blocks, options);
+        
+        for line in lines {
+            if let Ok(command) = Command::parse_with_mode(line, ParseMode::Extended) {
+                let _ = robot.blocks.execute(&command);
+            }
+        }
+        
+        Ok(robot)
+    }
+    
+    //
+    // Private methods.
+    //
+    
+    /// Handle `input` if it is one of the keywords of interactive
+    /// sessions, writing its output onto `out`, and return whether it
+    /// was:
+    ///
+    ///   * `history`: print the numbered list of operations executed
+    ///     so far, including the rejected ones
+    ///   * `save <path>`: save the world into a file, see
+    ///     `Blocks::save()`
+    ///   * `load <path>`: replace the world with the one saved in a
+    ///     file, see `Blocks::load()`. Refused while recording, as the
+    ///     recording couldn't replay it. See `Robot::record_to()`.
+    fn session_command(&mut self, input: &str, out: &mut impl io::Write) -> Result<bool, io::Error> {
+        let input = input.trim();
+        let (keyword, path) = match input.split_once(char::is_whitespace) {
+            Some((keyword, path)) => (keyword, path.trim()),
+            None => (input, ""),
+        };
+        
+        match (keyword, path) {
+            ("history", "") => history::write_history(&self.history, out)?,
+            ("save", path) if !path.is_empty() => match self.blocks.save(path) {
+                Ok(()) => writeln!(out, "Saved the world into `{}`.", path)?,
+                Err(error) => writeln!(out, "Error! Cannot save `{}`: {}", path, error)?,
+            },
+            ("load", path) if !path.is_empty() && self.recorder.is_some() => {
+                writeln!(out, "Error! Cannot load `{}` while recording, as the recording couldn't replay it.", path)?;
+            },
+            ("load", path) if !path.is_empty() => match Blocks::load(path) {
+                Ok(blocks) => {
+                    self.blocks = blocks;
+                    self.print_to(out)?;
+                },
+                Err(error) => writeln!(out, "Error! Cannot load `{}`: {}", path, error)?,
+            },
+            _ => return Ok(false),
+        }
+        
+        Ok(true)
+    }
+    
+    /// Parse a line of `input` with the configured grammar, counting
+    /// it in the stats. `None` is only returned for lines that hold no
+    /// command when lenient.
+    fn parse(&mut self, input: &str) -> Result<Option<Command>, ParseError> {
+        let command = if self.options.lenient {
+            Command::parse_lenient(input, self.options.parse_mode)
+        }
+        else {
+            Command::parse_with_mode(input, self.options.parse_mode).map(Some)
+        };
+        
+        match command {
+            Ok(Some(_)) => self.stats.parsed += 1,
+            Err(_) if !input.trim().is_empty() => self.stats.unparsed += 1,
+            _ => {},
+        }
+        
+        command
+    }
+    
+    /// Write the step for an executed `command` onto `out`: the
+    /// command, then the world. Then pause for the step delay.
+    fn step_to(&self, out: &mut impl io::Write, command: &Command) -> Result<(), io::Error> {
+        writeln!(out, "{}", command)?;
+        self.print_to(out)?;
+        out.flush()?;
+        
+        if self.options.step_delay > Duration::from_secs(0) {
+            thread::sleep(self.options.step_delay);
+        }
+        
+        Ok(())
+    }
+    
+    /// Write the world onto `out` in the configured render style.
+    fn print_to(&self, out: &mut impl io::Write) -> io::Result<()> {
+        self.blocks.render_to(out, self.options.render_style)
+    }
+    
+    /// Report the command rejected on `line` as configured by
+    /// `RobotOptions.report_errors`, then return it as an error if
+    /// we're strict.
+    fn report(&mut self, line: usize, error: ScriptError) -> Result<(), io::Error> {
+        let message = format!("line {}: {}", line, error);
+        
+        match self.options.report_errors {
+            ErrorMode::Silent => {},
+            ErrorMode::Stderr => eprintln!("{}", message),
+            ErrorMode::CollectIntoSummary => self.errors.push((line, error)),
+        }
+        
+        if self.options.strict {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        
+        Ok(())
+    }
+    
+    /// Write a summary of the collected errors onto `out`, if there
+    /// are any.
+    fn summary_to(&self, out: &mut impl io::Write) -> Result<(), io::Error> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        
+        writeln!(out, "{} command(s) rejected:", self.errors.len())?;
+        for (line, error) in self.errors.iter() {
+            writeln!(out, "  line {}: {}", line, error)?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Write the trace of an executed `command`, given its `result`,
+    /// onto `out`: the command, followed by either the world it
+    /// resulted in or the reason it was ignored.
+    fn trace_to(
+        &self,
+        out: &mut impl io::Write,
+        command: &Command,
+        result: &Result<(), BlockError>
+    ) -> Result<(), io::Error> {
+        match result {
+            Ok(()) => {
+                writeln!(out, "[trace] {}", command)?;
+                self.print_to(out)
+            },
+            Err(error) => writeln!(out, "[trace] {} (ignored: {})", command, error),
+        }
+    }
+    
+    /// Record the accepted command `input` into the script, if one is
+    /// being recorded.
+    fn record(&mut self, input: &str) -> Result<(), io::Error> {
+        if let Some(recorder) = self.recorder.as_mut() {
+            writeln!(recorder, "{}", input.trim())?;
+        }
+        
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::command::Placement;
+    use crate::snapshot::BlockMove;
+    
+    #[test]
+    fn robot_sample_input_output() {
+        let mut input = "10\n\
+                         move 9 onto 1\n\
+                         move 8 over 1\n\
+                         move 7 over 1\n\
+                         move 6 over 1\n\
+                         pile 8 over 6\n\
+                         pile 8 over 5\n\
+                         move 2 over 1\n\
+                         move 4 over 9\n\
+                         quit\n".as_bytes();
+        let mut out = Vec::new();
+        
+        Robot::run_to(&mut input, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0: 0\n\
+             1: 1 9 2 4\n\
+             2:\n\
+             3: 3\n\
+             4:\n\
+             5: 5 8 7 6\n\
+             6:\n\
+             7:\n\
+             8:\n\
+             9:\n"
+        );
+    }
+    
+    #[test]
+    fn robot_extended_commands() {
+        let input = "4\nmove 1 onto 0\nreturn 1\npile 3 over 2\nquit\n";
+        
+        // The problem's grammar ignores `return 1`.
+        let mut out = Vec::new();
+        Robot::run_to(&mut input.as_bytes(), &mut out).unwrap();
+        assert_eq!(out, b"0: 0 1\n1:\n2: 2 3\n3:\n");
+        
+        let mut out = Vec::new();
+        let options = RobotOptions {
+            parse_mode: ParseMode::Extended,
+            ..RobotOptions::default()
+        };
+        Robot::run_with_options(&mut input.as_bytes(), &mut out, options).unwrap();
+        assert_eq!(out, b"0: 0\n1: 1\n2: 2 3\n3:\n");
+    }
+    
+    #[test]
+    fn robot_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("uva101-record-{}.txt", std::process::id()));
+        let input = "6\n\
+                     move 1 onto 0\n\
+                     move 1 onto 1\n\
+                     oops\n\
+                     pile 3 over 0\n\
+                     swap 3 5\n\
+                     move 4 over 3\n\
+                     quit\n";
+        
+        let mut out = Vec::new();
+        let options = RobotOptions {
+            parse_mode: ParseMode::Extended,
+            record: Some(path.clone()),
+            ..RobotOptions::default()
+        };
+        Robot::run_with_options(&mut input.as_bytes(), &mut out, options).unwrap();
+        
+        // Only the commands that were executed are recorded.
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "6\nmove 1 onto 0\npile 3 over 0\nswap 3 5\nmove 4 over 3\n"
+        );
+        
+        let robot = Robot::replay(&path).unwrap();
+        let mut replayed = Vec::new();
+        robot.blocks.print_to(&mut replayed).unwrap();
+        assert_eq!(replayed, out);
+        
+        std::fs::write(&path, "zero\nmove 1 onto 0\n").unwrap();
+        assert_eq!(
+            Robot::replay(&path).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        
+        std::fs::remove_file(&path).unwrap();
+    }
+    
+    #[test]
+    fn robot_run_from_path() {
+        let path = std::env::temp_dir().join(format!("uva101-missing-{}.txt", std::process::id()));
+        assert_eq!(
+            Robot::run_from_path(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+    
+    #[test]
+    fn robot_interactive() {
+        let input = "zero\n2\nmove 1 onto 0\nmove 0 onto 1\nmove 1\nquit\n";
+        let mut out = Vec::new();
+        let options = RobotOptions {
+            interactive: true,
+            ..RobotOptions::default()
+        };
+        
+        Robot::run_with_options(&mut input.as_bytes(), &mut out, options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Number of blocks: Number of blocks: \
+             > 0: 0 1\n1:\n\
+             > Error! Illegal command: blocks 0 and 1 are in the same stack.\n\
+             > Error! Expected 4 input parameters, got 2\n\
+             move 1\n\
+             ^^^^^^\n\
+             > 0: 0 1\n1:\n\
+             Number of blocks: "
+        );
+    }
+    
+    #[test]
+    fn robot_interactive_history() {
+        let input = "move 1 onto 0\nmove 0 onto 1\nhistory\nmove 2 over 1\nhistory\nquit\n";
+        let mut out = Vec::new();
+        let mut robot = Robot::with_options(3, RobotOptions {
+            interactive: true,
+            ..RobotOptions::default()
+        });
+        
+        robot.main_loop_to(&mut input.as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(
+            "> 1: move 1 onto 0\n\
+             2: move 0 onto 1 (rejected: blocks 0 and 1 are in the same stack)\n\
+             > "
+        ));
+        assert!(out.contains("3: move 2 over 1\n> 0: 0 1 2\n"));
+        assert_eq!(robot.history().len(), 3);
+        assert_eq!(robot.stats().parsed, 4);
+        
+        // `history` is only a keyword in interactive sessions, and
+        // nothing is kept unless asked for.
+        let mut robot = Robot::new(3);
+        robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).unwrap();
+        assert!(robot.history().is_empty());
+        assert_eq!(robot.stats().unparsed, 2);
+    }
+    
+    #[test]
+    fn robot_interactive_save_and_load() {
+        let path = env::temp_dir().join(format!("robot_save_{}.txt", std::process::id()));
+        let path = path.display();
+        let input = format!(
+            "move 1 onto 0\nsave {path}\nmove 2 over 0\nload {path}\nload /no/such/file\nquit\n",
+            path = path
+        );
+        let mut out = Vec::new();
+        let mut robot = Robot::with_options(3, RobotOptions {
+            interactive: true,
+            ..RobotOptions::default()
+        });
+        
+        robot.main_loop_to(&mut input.as_bytes(), &mut out).unwrap();
+        std::fs::remove_file(path.to_string()).unwrap();
+        
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("> Saved the world into `{}`.\n", path)));
+        assert!(out.contains("> Error! Cannot load `/no/such/file`: "));
+        assert_eq!(robot.blocks.world(), vec![vec![0, 1], vec![], vec![2]]);
+    }
+    
+    #[test]
+    fn robot_interactive_load_while_recording() {
+        let saved = env::temp_dir().join(format!("robot_saved_{}.txt", std::process::id()));
+        let recorded = env::temp_dir().join(format!("robot_recorded_{}.txt", std::process::id()));
+        let input = format!("save {path}\nmove 2 over 0\nload {path}\nquit\n", path = saved.display());
+        let mut out = Vec::new();
+        let mut robot = Robot::with_options(3, RobotOptions {
+            interactive: true,
+            ..RobotOptions::default()
+        });
+        
+        robot.record_to(&recorded).unwrap();
+        robot.main_loop_to(&mut input.as_bytes(), &mut out).unwrap();
+        let replayed = Robot::replay(&recorded).unwrap();
+        std::fs::remove_file(&saved).unwrap();
+        std::fs::remove_file(&recorded).unwrap();
+        
+        // The world isn't replaced, so the recording still replays it.
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("> Error! Cannot load `{}` while recording, ", saved.display())));
+        assert_eq!(robot.blocks.world(), vec![vec![0, 2], vec![1], vec![]]);
+        assert_eq!(replayed.blocks, robot.blocks);
+    }
+    
+    #[test]
+    fn robot_multiple_datasets() {
+        let mut input = "2\n\
+                         move 1 onto 0\n\
+                         quit\n\
+                         \n\
+                         3\n\
+                         pile 0 over 2\n\
+                         quit\n".as_bytes();
+        let mut out = Vec::new();
+        
+        Robot::run_to(&mut input, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0: 0 1\n1:\n\
+             0:\n1: 1\n2: 2 0\n"
+        );
+        
+        // `quit` instead of a number of blocks stops reading.
+        let mut input = "1\nquit\nquit\n2\nquit\n".as_bytes();
+        let mut out = Vec::new();
+        
+        Robot::run_to(&mut input, &mut out).unwrap();
+        assert_eq!(out, b"0: 0\n");
+    }
+    
+    #[test]
+    fn robot_trace() {
+        let mut robot = Robot::new(3);
+        let mut out = Vec::new();
+        
+        let command = Command::parse("move 2 onto 0").unwrap();
+        let result = robot.blocks.execute(&command);
+        robot.trace_to(&mut out, &command, &result).unwrap();
+        
+        let command = Command::parse("pile 0 over 2").unwrap();
+        let result = robot.blocks.execute(&command);
+        robot.trace_to(&mut out, &command, &result).unwrap();
+        
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[trace] move 2 onto 0\n\
+             0: 0 2\n1: 1\n2:\n\
+             [trace] pile 0 over 2 (ignored: blocks 0 and 2 are in the same stack)\n"
+        );
+    }
+    
+    #[test]
+    fn robot_observers() {
+        use std::sync::{Arc, Mutex};
+        
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let count = Arc::new(Mutex::new(0));
+        let mut robot = Robot::new(5);
+        
+        let log = Arc::clone(&events);
+        robot.on_operation(move |event| log.lock().unwrap().push(event.clone()));
+        let counter = Arc::clone(&count);
+        robot.on_operation(move |_| *counter.lock().unwrap() += 1);
+        
+        let mut input = "pile 1 over 0\nmove 1 onto 0\nmove 3 onto 1\nquit\n".as_bytes();
+        robot.main_loop_to(&mut input, &mut Vec::new()).unwrap();
+        
+        // `move 1 onto 0` is illegal, so only two operations happened.
+        assert_eq!(*count.lock().unwrap(), 2);
+        let events = events.lock().unwrap();
+        assert_eq!(events[0].command, Command::parse("pile 1 over 0").unwrap());
+        assert_eq!(
+            events[1].moves,
+            vec![BlockMove { block: 3, from: (3, 0), to: (0, 2) }]
+        );
+    }
+    
+    #[test]
+    fn robot_execute_script() {
+        let mut robot = Robot::new(10);
+        let script = include_str!("../input.txt");
+        
+        // Skip the number of blocks.
+        let results = robot.execute_script(&script[script.find('\n').unwrap()..]);
+        assert_eq!(results.len(), 9);
+        
+        // Only `pile 8 over 6` is illegal, since 8 and 6 are in the
+        // same stack.
+        assert_eq!(results[4], Err(ScriptError::Illegal(BlockError::SameStack(8, 6))));
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        assert_eq!(robot.blocks.world()[1], vec![1, 9, 2, 4]);
+        
+        let mut robot = Robot::new(4);
+        let results = robot.execute_script("return 1\nswap 1 2\nmove 4 onto 1\nprint\n");
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "Error! Expected 4 input parameters, got 2"
+        );
+        assert_eq!(results[2], Err(ScriptError::Illegal(BlockError::OutOfRange(4))));
+        assert_eq!(results[3], Ok(()));
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "Error! Illegal command: block 4 is not in the world."
+        );
+        
+        let mut robot = Robot::with_options(4, RobotOptions {
+            parse_mode: ParseMode::Extended,
+            ..RobotOptions::default()
+        });
+        let results = robot.execute_script("swap 1 2\nreturn 1\n");
+        assert_eq!(results, vec![Ok(()), Ok(())]);
+        assert_eq!(robot.blocks.world(), vec![vec![0], vec![2, 1], vec![], vec![3]]);
+    }
+    
+    #[test]
+    fn robot_report_errors() {
+        let input = "move 1 onto 0\nmove 0 onto 1\n\nfly 2 over 1\nmove 5 over 0\nprint\nquit\n";
+        let options = RobotOptions {
+            report_errors: ErrorMode::CollectIntoSummary,
+            ..RobotOptions::default()
+        };
+        let mut robot = Robot::with_options(3, options);
+        let mut out = Vec::new();
+        robot.main_loop_to(&mut input.as_bytes(), &mut out).unwrap();
+        
+        // The output the problem requires is unchanged.
+        assert_eq!(String::from_utf8(out).unwrap(), "0: 0 1\n1:\n2: 2\n".repeat(2));
+        
+        // The blank line on line 3 is not a mistake.
+        let lines: Vec<usize> = robot.errors().iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![2, 4, 5]);
+        match &robot.errors()[1].1 {
+            ScriptError::Parse(error) => assert_eq!(error.to_string(), "Error! `fly` is not a valid command."),
+            error => panic!("unexpected error: {}", error),
+        }
+        
+        let mut summary = Vec::new();
+        robot.summary_to(&mut summary).unwrap();
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            "3 command(s) rejected:\n\
+             \x20 line 2: Error! Illegal command: blocks 0 and 1 are in the same stack.\n\
+             \x20 line 4: Error! `fly` is not a valid command.\n\
+             \x20 line 5: Error! Illegal command: block 5 is not in the world.\n"
+        );
+        
+        // Nothing is collected unless asked for.
+        let mut robot = Robot::new(3);
+        robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).unwrap();
+        assert!(robot.errors().is_empty());
+    }
+    
+    #[test]
+    fn robot_parallel_worlds() {
+        use std::sync::mpsc;
+        use std::thread;
+        
+        let (sender, receiver) = mpsc::channel();
+        let mut robot = Robot::new(10);
+        robot.on_operation(move |event| sender.send(event.command.clone()).unwrap());
+        
+        // Move the robot, observer and all, onto another thread.
+        let handle = thread::spawn(move || {
+            let results = robot.execute_script(&include_str!("../input.txt")[3..]);
+            (robot, results.len())
+        });
+        let (robot, count) = handle.join().unwrap();
+        assert_eq!(count, 9);
+        assert_eq!(robot.blocks.world()[1], vec![1, 9, 2, 4]);
+        assert_eq!(receiver.try_iter().count(), 7);
+        
+        // Simulate several independent worlds at once.
+        let handles: Vec<_> = (1..=8u32)
+            .map(|n| thread::spawn(move || {
+                let mut robot = Robot::new(n * 10);
+                for a in 1..n * 10 {
+                    robot.execute(&Command::Pile { a, b: 0, placement: Placement::Over }).unwrap();
+                }
+                robot.blocks
+            }))
+            .collect();
+        for (n, handle) in (1..=8u32).zip(handles) {
+            let blocks = handle.join().unwrap();
+            assert_eq!(blocks.height_of(n * 10 - 1), Some(n as usize * 10 - 1));
+        }
+    }
+    
+    #[test]
+    fn robot_lenient() {
+        let input = "# The sample, annotated.\n\nmove 9 onto 1   # start the tower\nmove  8 over 1\nquit # done\n";
+        let options = RobotOptions {
+            lenient: true,
+            report_errors: ErrorMode::CollectIntoSummary,
+            ..RobotOptions::default()
+        };
+        let mut robot = Robot::with_options(10, options);
+        robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).unwrap();
+        assert_eq!(robot.blocks.world()[1], vec![1, 9, 8]);
+        assert!(robot.errors().is_empty());
+        
+        let results = robot.execute_script("# reset\nmove 8 onto 2 # comment\n");
+        assert_eq!(results, vec![Ok(())]);
+        
+        // Without lenient mode, the comments are mistakes.
+        let options = RobotOptions {
+            report_errors: ErrorMode::CollectIntoSummary,
+            ..RobotOptions::default()
+        };
+        let mut robot = Robot::with_options(10, options);
+        let input = format!("{}quit\n", input);
+        robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).unwrap();
+        let lines: Vec<usize> = robot.errors().iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![1, 3, 5]);
+    }
+    
+    #[test]
+    fn robot_builder() {
+        let robot = Robot::builder()
+            .blocks(4)
+            .parse_mode(ParseMode::Extended)
+            .lenient(true)
+            .render_style(RenderStyle::Vertical)
+            .build();
+        assert_eq!(robot.blocks.world().len(), 4);
+        assert_eq!(robot.options.parse_mode, ParseMode::Extended);
+        assert!(robot.options.lenient);
+        assert!(!robot.options.strict);
+        assert_eq!(robot.options.max_commands, None);
+        
+        // Strict mode stops at the first mistake, including parse
+        // errors, but not at blank lines.
+        let mut robot = Robot::builder().blocks(4).strict(true).build();
+        let mut input = "move 1 onto 0\n\nfly 2 over 1\nquit\n".as_bytes();
+        let error = robot.main_loop_to(&mut input, &mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 3: Error! `fly` is not a valid command.");
+        assert_eq!(robot.blocks.world()[0], vec![0, 1]);
+        
+        // The limit allows exactly `max_commands` commands.
+        let input = "move 1 onto 0\n# a comment\nmove 2 onto 0\nquit\n";
+        let mut robot = Robot::builder().blocks(4).lenient(true).max_commands(3).build();
+        assert!(robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).is_ok());
+        
+        let mut robot = Robot::builder().blocks(4).lenient(true).max_commands(2).build();
+        let error = robot.main_loop_to(&mut input.as_bytes(), &mut Vec::new()).unwrap_err();
+        assert_eq!(error.to_string(), "line 4: more than 2 commands without a `quit`");
+        assert_eq!(robot.blocks.world()[0], vec![0, 2]);
+    }
+    
+    #[test]
+    fn robot_run_str() {
+        let cases = [
+            (include_str!("../input.txt"), "0: 0\n1: 1 9 2 4\n2:\n3: 3\n4:\n5: 5 8 7 6\n6:\n7:\n8:\n9:\n"),
+            ("1\nmove 0 onto 0\nquit\n", "0: 0\n"),
+            ("2\npile 1 over 0\nmove 0 over 1\nquit\n", "0: 0 1\n1:\n"),
+            ("4\npile 1 onto 0\npile 2 over 1\nmove 1 onto 3\nquit\n", "0: 0\n1:\n2: 2\n3: 3 1\n"),
+            ("3\nmove 1 onto 0\nprint\nquit\n2\nquit\n", "0: 0 1\n1:\n2: 2\n0: 0 1\n1:\n2: 2\n0: 0\n1: 1\n"),
+            ("", ""),
+        ];
+        
+        for (input, expected) in cases.iter() {
+            assert_eq!(&Robot::run_str(input).unwrap(), expected, "input: {:?}", input);
+        }
+        
+        // More blocks than the problem allows are an error, not a
+        // panic.
+        let error = Robot::run_str("25\nquit\n").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "Error! Blocks size must be at most 24, got 25.");
+    }
+    
+    #[test]
+    fn robot_step_mode() {
+        let mut input = "move 1 onto 0\nmove 0 onto 1\npile 2 over 0\nquit\n".as_bytes();
+        let mut out = Vec::new();
+        let mut robot = Robot::builder().blocks(3).step(Duration::from_millis(1)).build();
+        
+        robot.main_loop_to(&mut input, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "move 1 onto 0\n0: 0 1\n1:\n2: 2\n\
+             pile 2 over 0\n0: 0 1 2\n1:\n2:\n\
+             0: 0 1 2\n1:\n2:\n"
+        );
+    }
+    
+    #[test]
+    fn robot_stats() {
+        let mut robot = Robot::new(10);
+        let mut input = &include_str!("../input.txt").as_bytes()[3..];
+        robot.main_loop_to(&mut input, &mut Vec::new()).unwrap();
+        assert_eq!(
+            robot.stats(),
+            RobotStats { parsed: 9, unparsed: 0, executed: 7, illegal: 1 }
+        );
+        
+        // Blank lines are not counted, and `print` and `quit` are
+        // parsed but not executed.
+        let mut robot = Robot::new(3);
+        let mut input = "move 1 onto 0\n\nprint\nmove 3 onto 0\nmove 1\nquit\n".as_bytes();
+        robot.main_loop_to(&mut input, &mut Vec::new()).unwrap();
+        assert_eq!(
+            robot.stats(),
+            RobotStats { parsed: 4, unparsed: 1, executed: 1, illegal: 1 }
+        );
+        assert_eq!(
+            robot.stats().to_string(),
+            "4 commands parsed (1 line could not be parsed): 1 executed, 1 ignored as illegal"
+        );
+    }
+    
+    #[test]
+    fn robot_eof_is_quit() {
+        let mut input = "move 1 onto 0\nmove 2 over 0".as_bytes();
+        let mut out = Vec::new();
+        let mut robot = Robot::new(3);
+        robot.main_loop_to(&mut input, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "0: 0 1 2\n1:\n2:\n");
+        assert_eq!(robot.stats().parsed, 2);
+        
+        // Every dataset but the last one still needs its `quit`.
+        assert_eq!(
+            Robot::run_str("2\nmove 1 onto 0\nquit\n2\n").unwrap(),
+            "0: 0 1\n1:\n0: 0\n1: 1\n"
+        );
+        assert_eq!(Robot::run_str("3\n").unwrap(), "0: 0\n1: 1\n2: 2\n");
+    }
+    
+    #[test]
+    fn robot_max_blocks() {
+        let mut out = Vec::new();
+        let mut input = "24\nquit\n25\nquit\n".as_bytes();
+        let error = Robot::run_to(&mut input, &mut out).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "Error! Blocks size must be at most 24, got 25.");
+        
+        // The first dataset was fine, so its world was printed.
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 24);
+        
+        let mut out = Vec::new();
+        let mut input = "5\nquit\n".as_bytes();
+        assert!(Robot::builder().max_blocks(Some(4)).run_to(&mut input, &mut out).is_err());
+        assert!(out.is_empty());
+        
+        // Interactive sessions are asked again.
+        let mut out = Vec::new();
+        let mut input = "5\n3\nquit\nquit\n".as_bytes();
+        let options = RobotOptions {
+            interactive: true,
+            max_blocks: Some(4),
+            ..RobotOptions::default()
+        };
+        Robot::run_with_options(&mut input, &mut out, options).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Number of blocks: Number of blocks: > 0: 0\n1: 1\n2: 2\nNumber of blocks: "
+        );
+    }
+    
+    #[test]
+    fn robot_print_command() {
+        let mut input = "move 1 onto 0\nprint\nmove 2 over 0\nq\n".as_bytes();
+        let mut out = Vec::new();
+        
+        Robot::new(3).main_loop_to(&mut input, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0: 0 1\n1:\n2: 2\n0: 0 1 2\n1:\n2:\n"
+        );
+    }
+    
+    #[test]
+    fn robot_render_style() {
+        let mut input = "move 1 onto 0\nprint\nmove 2 over 0\nq\n".as_bytes();
+        let mut out = Vec::new();
+        let options = RobotOptions {
+            render_style: RenderStyle::Vertical,
+            ..RobotOptions::default()
+        };
+        
+        Robot::with_options(3, options).main_loop_to(&mut input, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1\n0   2\n-----\n0 1 2\n2\n1\n0\n-----\n0 1 2\n"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! `rules` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! The rules behind the four operations of the problem. Several
+//! variants of the problem tweak what `move` and `pile` do, so the
+//! rules a `Robot` follows can be swapped for another set. See
+//! `RobotOptions.rules` and `Blocks::execute_with()`.
+
+use std::fmt;
+
+use crate::blocks::{Blocks, BlockError};
+
+/// A set of rules for the four operations of the problem. Every
+/// method defaults to the problem's own rules, so a rule set only
+/// overrides the operations it changes. Like the problem's, every
+/// operation should leave the world untouched and return an `Err()`
+/// when it is illegal.
+///
+/// The extended grammar's `return`, `swap` and `reset` are not part
+/// of the problem, so they always follow the same rules.
+///
+/// # Example
+///
+/// ```
+/// use rust::blocks::{Blocks, BlockError};
+/// use rust::command::Command;
+/// use rust::rules::BlockRules;
+///
+/// /// A variant where `move` carries the blocks above `a` along, like
+/// /// `pile` does.
+/// #[derive(Debug)]
+/// struct CarryRules;
+///
+/// impl BlockRules for CarryRules {
+///     fn move_onto(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+///         blocks.pile_a_onto_b(a, b)
+///     }
+///
+///     fn move_over(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+///         blocks.pile_a_over_b(a, b)
+///     }
+/// }
+///
+/// let mut blocks = Blocks::new(3).unwrap();
+/// blocks.move_a_onto_b(1, 0).unwrap();
+///
+/// blocks.execute_with(&Command::parse("move 0 onto 2").unwrap(), &CarryRules).unwrap();
+/// assert_eq!(blocks.world(), vec![vec![], vec![], vec![2, 0, 1]]);
+/// ```
+pub trait BlockRules: fmt::Debug + Send + Sync {
+    /// `move a onto b`. See `Blocks::move_a_onto_b()`.
+    fn move_onto(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+        blocks.move_a_onto_b(a, b)
+    }
+    
+    /// `move a over b`. See `Blocks::move_a_over_b()`.
+    fn move_over(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+        blocks.move_a_over_b(a, b)
+    }
+    
+    /// `pile a onto b`. See `Blocks::pile_a_onto_b()`.
+    fn pile_onto(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+        blocks.pile_a_onto_b(a, b)
+    }
+    
+    /// `pile a over b`. See `Blocks::pile_a_over_b()`.
+    fn pile_over(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+        blocks.pile_a_over_b(a, b)
+    }
+}
+
+/// The rules of the problem, as implemented by `Blocks`. These are
+/// the rules `Blocks::execute()` and a `Robot` follow by default.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StandardRules;
+
+impl BlockRules for StandardRules {}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::command::{Command, ParseMode};
+    
+    /// A variant where `a` and `b` may be in the same stack, in which
+    /// case nothing happens.
+    #[derive(Debug)]
+    struct ForgivingRules;
+    
+    impl ForgivingRules {
+        fn forgive(result: Result<(), BlockError>) -> Result<(), BlockError> {
+            match result {
+                Err(BlockError::SameStack(_, _)) => Ok(()),
+                result => result,
+            }
+        }
+    }
+    
+    impl BlockRules for ForgivingRules {
+        fn pile_onto(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+            ForgivingRules::forgive(blocks.pile_a_onto_b(a, b))
+        }
+        
+        fn pile_over(&self, blocks: &mut Blocks, a: u32, b: u32) -> Result<(), BlockError> {
+            ForgivingRules::forgive(blocks.pile_a_over_b(a, b))
+        }
+    }
+    
+    #[test]
+    fn rules_standard_matches_execute() {
+        let input = include_str!("../input.txt");
+        let commands: Vec<Command> = input
+            .lines()
+            .skip(1)
+            .filter_map(|line| Command::parse_with_mode(line, ParseMode::Extended).ok())
+            .collect();
+        
+        let mut standard = Blocks::new(10).unwrap();
+        let mut blocks = Blocks::new(10).unwrap();
+        for command in commands.iter() {
+            assert_eq!(standard.execute_with(command, &StandardRules), blocks.execute(command));
+        }
+        assert_eq!(standard.world(), blocks.world());
+    }
+    
+    #[test]
+    fn rules_override_only_some_operations() {
+        let mut blocks = Blocks::new(3).unwrap();
+        let parse = |input| Command::parse(input).unwrap();
+        
+        blocks.execute_with(&parse("pile 1 over 0"), &ForgivingRules).unwrap();
+        assert_eq!(blocks.execute_with(&parse("pile 1 over 0"), &ForgivingRules), Ok(()));
+        assert_eq!(
+            blocks.execute_with(&parse("move 1 over 0"), &ForgivingRules),
+            Err(BlockError::SameStack(1, 0))
+        );
+        assert_eq!(
+            blocks.execute_with(&parse("pile 3 over 0"), &ForgivingRules),
+            Err(BlockError::OutOfRange(3))
+        );
+        assert_eq!(blocks.world(), vec![vec![0, 1], vec![], vec![2]]);
+    }
+}
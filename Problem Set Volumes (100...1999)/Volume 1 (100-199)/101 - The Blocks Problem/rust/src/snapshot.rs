@@ -0,0 +1,160 @@
+//! `snapshot` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! Frozen copies of a `Blocks` world, and the differences between
+//! them. Taking a snapshot before and after a command shows exactly
+//! which blocks the command moved, which makes for step-by-step
+//! output and precise test assertions.
+
+use std::fmt;
+
+use crate::blocks::Blocks;
+
+/// A block that is in a different place in two snapshots. Places are
+/// `(stack, height)` coordinates in the world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockMove {
+    /// The block number.
+    pub block: u32,
+    
+    /// Where the block is in the first snapshot.
+    pub from: (usize, usize),
+    
+    /// Where the block is in the second snapshot.
+    pub to: (usize, usize),
+}
+
+impl fmt::Display for BlockMove {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "block {}: stack {} height {} -> stack {} height {}",
+            self.block, self.from.0, self.from.1, self.to.0, self.to.1
+        )
+    }
+}
+
+/// A frozen copy of a `Blocks` world. See `Blocks::snapshot()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorldSnapshot {
+    /// The stacks of blocks, as in `Blocks::world()`.
+    world: Vec<Vec<u32>>,
+    
+    /// The `(stack, height)` of every block, as in `Blocks::blocks()`.
+    positions: Vec<(usize, usize)>,
+}
+
+impl WorldSnapshot {
+    /// Return a snapshot of the world of `blocks`.
+    pub fn new(blocks: &Blocks) -> WorldSnapshot {
+        WorldSnapshot {
+            world: blocks.world().to_vec(),
+            positions: blocks.blocks().map(|(_, stack, height)| (stack, height)).collect(),
+        }
+    }
+    
+    /// Return the stacks of blocks in the snapshot.
+    pub fn world(&self) -> &[Vec<u32>] {
+        &self.world
+    }
+    
+    /// Return the `(stack, height)` of `block` in the snapshot, or
+    /// `None` if the block is not in the world.
+    pub fn position_of(&self, block: u32) -> Option<(usize, usize)> {
+        self.positions.get(block as usize).copied()
+    }
+    
+    /// Return every block that is in a different place in `other`
+    /// than in this snapshot, ordered by block number. Only blocks
+    /// that are in both snapshots are compared.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::blocks::Blocks;
+    /// use rust::snapshot::BlockMove;
+    ///
+    /// let mut blocks = Blocks::new(4).unwrap();
+    /// blocks.move_a_onto_b(3, 1).unwrap();
+    /// let before = blocks.snapshot();
+    ///
+    /// blocks.pile_a_over_b(1, 0).unwrap();
+    /// let after = blocks.snapshot();
+    ///
+    /// assert_eq!(
+    ///     before.diff(&after),
+    ///     vec![
+    ///         BlockMove { block: 1, from: (1, 0), to: (0, 1) },
+    ///         BlockMove { block: 3, from: (1, 1), to: (0, 2) },
+    ///     ]
+    /// );
+    /// assert!(after.diff(&after).is_empty());
+    /// ```
+    pub fn diff(&self, other: &WorldSnapshot) -> Vec<BlockMove> {
+        self.positions
+            .iter()
+            .zip(&other.positions)
+            .enumerate()
+            .filter(|(_, (from, to))| from != to)
+            .map(|(block, (&from, &to))| BlockMove { block: block as u32, from, to })
+            .collect()
+    }
+}
+
+impl fmt::Display for WorldSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, stack) in self.world.iter().enumerate() {
+            write!(f, "{}:", index)?;
+            for &item in stack.iter() {
+                write!(f, " {}", item)?;
+            }
+            writeln!(f)?;
+        }
+        
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn snapshot_is_frozen() {
+        let mut blocks = Blocks::new(3).unwrap();
+        let snapshot = blocks.snapshot();
+        
+        blocks.move_a_onto_b(2, 0).unwrap();
+        assert_eq!(snapshot.world(), &[vec![0], vec![1], vec![2]][..]);
+        assert_eq!(snapshot.position_of(2), Some((2, 0)));
+        assert_eq!(snapshot.position_of(3), None);
+        assert_eq!(snapshot.to_string(), "0: 0\n1: 1\n2: 2\n");
+        assert_eq!(blocks.snapshot().to_string(), blocks.to_string());
+    }
+    
+    #[test]
+    fn snapshot_diff_move_onto_returns_blocks() {
+        let mut blocks = Blocks::new(5).unwrap();
+        blocks.pile_a_over_b(1, 0).unwrap();
+        blocks.move_a_over_b(4, 2).unwrap();
+        let before = blocks.snapshot();
+        
+        // Block 1 goes home and block 4 leaves the stack of block 2,
+        // which it was on top of.
+        blocks.move_a_onto_b(2, 0).unwrap();
+        let moves = before.diff(&blocks.snapshot());
+        
+        assert_eq!(
+            moves,
+            vec![
+                BlockMove { block: 1, from: (0, 1), to: (1, 0) },
+                BlockMove { block: 2, from: (2, 0), to: (0, 1) },
+                BlockMove { block: 4, from: (2, 1), to: (4, 0) },
+            ]
+        );
+        assert_eq!(moves[1].to_string(), "block 2: stack 2 height 0 -> stack 0 height 1");
+    }
+}
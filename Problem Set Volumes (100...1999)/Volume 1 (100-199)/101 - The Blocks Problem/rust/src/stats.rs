@@ -0,0 +1,84 @@
+//! `stats` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-04
+//!
+//! Counts of what a `Robot` did with its input, for sanity-checking
+//! large command files. See `Robot::stats()`.
+
+use std::fmt;
+
+/// How many commands a `Robot` parsed and executed. Lines that hold
+/// no command, such as blank lines, are not counted at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RobotStats {
+    /// Lines that were parsed into a command, including `print` and
+    /// `quit`.
+    pub parsed: usize,
+    
+    /// Lines that could not be parsed into a command.
+    pub unparsed: usize,
+    
+    /// Operations that were executed.
+    pub executed: usize,
+    
+    /// Operations that were ignored because they were illegal.
+    pub illegal: usize,
+}
+
+/// Format the statistics as a one-line summary.
+///
+/// # Example
+///
+/// ```
+/// use rust::stats::RobotStats;
+///
+/// let stats = RobotStats { parsed: 9, unparsed: 1, executed: 7, illegal: 1 };
+/// assert_eq!(
+///     stats.to_string(),
+///     "9 commands parsed (1 line could not be parsed): 7 executed, 1 ignored as illegal"
+/// );
+/// ```
+impl fmt::Display for RobotStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} command{} parsed", self.parsed, plural(self.parsed))?;
+        if self.unparsed > 0 {
+            write!(f, " ({} line{} could not be parsed)", self.unparsed, plural(self.unparsed))?;
+        }
+        write!(f, ": {} executed, {} ignored as illegal", self.executed, self.illegal)
+    }
+}
+
+/// Return the suffix that makes a noun plural for `count` of them.
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    }
+    else {
+        "s"
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn stats_display() {
+        assert_eq!(
+            RobotStats::default().to_string(),
+            "0 commands parsed: 0 executed, 0 ignored as illegal"
+        );
+        
+        let stats = RobotStats { parsed: 1, unparsed: 2, executed: 0, illegal: 0 };
+        assert_eq!(
+            stats.to_string(),
+            "1 command parsed (2 lines could not be parsed): 0 executed, 0 ignored as illegal"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! `million_lines` benchmark
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Measure how many cases a second are solved, both on their own and
+//! read from, and written to, a million lines of text: separated by
+//! whitespace or by commas, a line at a time or in batches. Only
+//! `cargo bench` solves a million cases; other runs, such as `cargo
+//! test --all-targets`, solve a thousand, once, to check that every
+//! scenario still works. Name scenarios after `cargo bench --` to run
+//! only those, and set `BINS_CASES` to change the number of cases.
+
+use std::env;
+use std::hint::black_box;
+use std::io;
+use std::time::{Duration, Instant};
+
+use rust::{solve, Bins, BinsOptions, InputFormat};
+
+/// The number of cases `cargo bench` solves, unless `BINS_CASES` says
+/// otherwise.
+const CASES: usize = 1_000_000;
+
+/// The number of cases any other run solves.
+const CHECK_CASES: usize = 1_000;
+
+/// How the cases are solved.
+#[derive(Debug, Clone, Copy)]
+enum Scenario {
+    /// With `solve()`, without any input or output.
+    Solve,
+    
+    /// With `Bins::run_with_options()`, from lines of input in
+    /// `InputFormat`.
+    Lines(InputFormat),
+    
+    /// With `Bins::run_batch()` on every available CPU, reading the
+    /// input a line at a time, or `chunked`.
+    Batch { chunked: bool },
+}
+
+impl Scenario {
+    /// Every scenario, in the order they are run.
+    const ALL: [Scenario; 5] = [
+        Scenario::Solve,
+        Scenario::Lines(InputFormat::Whitespace),
+        Scenario::Lines(InputFormat::Csv),
+        Scenario::Batch { chunked: false },
+        Scenario::Batch { chunked: true },
+    ];
+    
+    /// Return the name the scenario is selected by.
+    fn name(self) -> &'static str {
+        match self {
+            Scenario::Solve => "solve",
+            Scenario::Lines(InputFormat::Whitespace) => "run_lines",
+            Scenario::Lines(InputFormat::Csv) => "run_csv",
+            Scenario::Batch { chunked: false } => "run_batch",
+            Scenario::Batch { chunked: true } => "run_chunked",
+        }
+    }
+    
+    /// Solve every one of `cases`, from `whitespace` or `csv`, the
+    /// same cases as lines of input, discarding the output.
+    fn run(self, cases: &[[[u64; 3]; 3]], whitespace: &str, csv: &str) {
+        let mut options = BinsOptions {
+            judge: true,
+            ..BinsOptions::default()
+        };
+        let mut out = io::BufWriter::new(io::sink());
+        
+        match self {
+            Scenario::Solve => {
+                for &bins in cases {
+                    black_box(solve(black_box(bins)));
+                }
+            },
+            Scenario::Lines(format) => {
+                options.format = format;
+                let input = if format == InputFormat::Csv { csv } else { whitespace };
+                Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut io::sink(), options).unwrap();
+            },
+            Scenario::Batch { chunked } => {
+                options.chunked = chunked;
+                Bins::run_batch(&mut whitespace.as_bytes(), &mut out, &mut io::sink(), options).unwrap();
+            },
+        }
+    }
+}
+
+/// Return `count` cases, the same ones on every run. Their counts are
+/// those of a Lehmer generator, below 100,000, so that no case holds
+/// more than `MAX_BOTTLES` bottles.
+fn cases(count: usize) -> Vec<[[u64; 3]; 3]> {
+    let mut counts = std::iter::successors(Some(1u64), |state| Some(state * 48_271 % 0x7fff_ffff))
+        .skip(1)
+        .map(|state| state % 100_000);
+    let mut bins = || [0; 3].map(|_| counts.next().unwrap());
+    
+    (0..count).map(|_| [bins(), bins(), bins()]).collect()
+}
+
+/// Return `cases` as lines of input, their counts separated by
+/// `separator`.
+fn lines(cases: &[[[u64; 3]; 3]], separator: &str) -> String {
+    let mut input = String::new();
+    for bins in cases {
+        let counts: Vec<String> = bins.iter().flatten().map(u64::to_string).collect();
+        input.push_str(&counts.join(separator));
+        input.push('\n');
+    }
+    
+    input
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let benching = args.iter().any(|arg| arg == "--bench");
+    let selected: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    
+    let count = match env::var("BINS_CASES").ok().and_then(|cases| cases.parse().ok()) {
+        Some(count) => count,
+        None if benching => CASES,
+        None => CHECK_CASES,
+    };
+    let cases = cases(count);
+    let whitespace = lines(&cases, " ");
+    let csv = lines(&cases, ",");
+    
+    for scenario in Scenario::ALL.iter() {
+        let name = scenario.name();
+        if !selected.is_empty() && !selected.iter().any(|selected| name.contains(selected.as_str())) {
+            continue;
+        }
+        
+        // The best of a few runs, when benchmarking.
+        let runs = if benching { 5 } else { 1 };
+        let best = (0..runs)
+            .map(|_| {
+                let start = Instant::now();
+                scenario.run(&cases, &whitespace, &csv);
+                start.elapsed()
+            })
+            .min()
+            .unwrap_or(Duration::ZERO);
+        
+        println!(
+            "{:<12} {:>8} cases {:>10.3} ms {:>10.1} ns/case {:>12.0} cases/s",
+            name,
+            count,
+            best.as_secs_f64() * 1e3,
+            best.as_nanos() as f64 / count as f64,
+            count as f64 / best.as_secs_f64()
+        );
+    }
+}
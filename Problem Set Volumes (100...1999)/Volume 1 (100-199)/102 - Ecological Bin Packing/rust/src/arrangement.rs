@@ -0,0 +1,166 @@
+//! `arrangement` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! The six ways the three colors of glass can be assigned to the
+//! three bins.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// `Arrangement` enum. Each variant names the color of glass that
+/// ends up in bin one, bin two, and bin three, in that order, where
+/// `B` is Brown, `G` is Green, and `C` is Clear. E.g.,
+/// `Arrangement::GBC` puts the Green glass in bin one.
+///
+/// The variants are declared in alphabetical order, so comparing two
+/// arrangements compares their names, as the problem's tie-breaking
+/// rule requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Arrangement {
+    /// Brown, Clear, Green.
+    BCG,
+    
+    /// Brown, Green, Clear.
+    BGC,
+    
+    /// Clear, Brown, Green.
+    CBG,
+    
+    /// Clear, Green, Brown.
+    CGB,
+    
+    /// Green, Brown, Clear.
+    GBC,
+    
+    /// Green, Clear, Brown.
+    GCB,
+}
+
+impl Arrangement {
+    /// Every arrangement, in alphabetical order.
+    pub const ALL: [Arrangement; 6] = [
+        Arrangement::BCG,
+        Arrangement::BGC,
+        Arrangement::CBG,
+        Arrangement::CGB,
+        Arrangement::GBC,
+        Arrangement::GCB,
+    ];
+    
+    /// Return the column of the color that ends up in each bin, where
+    /// column `0` is Brown, `1` is Green, and `2` is Clear, as in
+    /// the input.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Arrangement;
+    ///
+    /// assert_eq!(Arrangement::GBC.columns(), [1, 0, 2]);
+    /// ```
+    pub fn columns(self) -> [usize; 3] {
+        match self {
+            Arrangement::BCG => [0, 2, 1],
+            Arrangement::BGC => [0, 1, 2],
+            Arrangement::CBG => [2, 0, 1],
+            Arrangement::CGB => [2, 1, 0],
+            Arrangement::GBC => [1, 0, 2],
+            Arrangement::GCB => [1, 2, 0],
+        }
+    }
+    
+    /// Return the arrangement that puts the color in column
+    /// `columns[bin]` in each bin, or `None` if there is none. See
+    /// `columns()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Arrangement;
+    ///
+    /// assert_eq!(Arrangement::from_columns(&[1, 0, 2]), Some(Arrangement::GBC));
+    /// assert_eq!(Arrangement::from_columns(&[1, 1, 2]), None);
+    /// ```
+    pub fn from_columns(columns: &[usize]) -> Option<Arrangement> {
+        Arrangement::ALL
+            .iter()
+            .copied()
+            .find(|arrangement| arrangement.columns() == columns)
+    }
+}
+
+/// Formats the arrangement as its three letters, e.g., `GBC`, as the
+/// problem's output requires.
+impl fmt::Display for Arrangement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Arrangement::BCG => "BCG",
+            Arrangement::BGC => "BGC",
+            Arrangement::CBG => "CBG",
+            Arrangement::CGB => "CGB",
+            Arrangement::GBC => "GBC",
+            Arrangement::GCB => "GCB",
+        })
+    }
+}
+
+/// Parses the arrangement's three letters, e.g., `GBC`, as `Display`
+/// formats them.
+///
+/// # Example
+///
+/// ```
+/// use rust::Arrangement;
+///
+/// assert_eq!("GBC".parse(), Ok(Arrangement::GBC));
+/// assert!("GGC".parse::<Arrangement>().is_err());
+/// ```
+impl FromStr for Arrangement {
+    type Err = ParseArrangementError;
+    
+    fn from_str(s: &str) -> Result<Arrangement, ParseArrangementError> {
+        Arrangement::ALL
+            .iter()
+            .copied()
+            .find(|arrangement| arrangement.to_string() == s)
+            .ok_or(ParseArrangementError)
+    }
+}
+
+/// `ParseArrangementError` struct. Returned when parsing a string
+/// that isn't the name of an arrangement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseArrangementError;
+
+impl fmt::Display for ParseArrangementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error! An arrangement is one of BCG, BGC, CBG, CGB, GBC, or GCB.")
+    }
+}
+
+impl Error for ParseArrangementError {}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn arrangement_round_trips() {
+        for &arrangement in Arrangement::ALL.iter() {
+            assert_eq!(arrangement.to_string().parse(), Ok(arrangement));
+            assert_eq!(Arrangement::from_columns(&arrangement.columns()), Some(arrangement));
+        }
+        
+        assert_eq!("".parse::<Arrangement>(), Err(ParseArrangementError));
+        assert_eq!("gbc".parse::<Arrangement>(), Err(ParseArrangementError));
+        assert_eq!(" GBC".parse::<Arrangement>(), Err(ParseArrangementError));
+    }
+}
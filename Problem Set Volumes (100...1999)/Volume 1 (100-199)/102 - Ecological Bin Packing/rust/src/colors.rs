@@ -0,0 +1,235 @@
+//! `colors` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! How the colors of glass are displayed. The problem names them `B`,
+//! `G`, and `C`, in the order of the input's columns, but variants of
+//! the problem, or demonstrations in another language, may name them
+//! differently.
+
+use std::fmt;
+
+use crate::arrangement::Arrangement;
+use crate::BinResult;
+use crate::solver;
+use crate::tie_break::TieBreak;
+
+/// `ColorScheme` struct. The character each column of the input is
+/// displayed as. Arrangements are displayed as the characters of the
+/// colors that end up in bin one, bin two, and bin three, and ties
+/// are broken by sorting them alphabetically, as the problem does for
+/// its own names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorScheme {
+    /// `labels[column]` is the character of the color in `column`.
+    labels: [char; 3],
+}
+
+impl ColorScheme {
+    /// Return a scheme displaying the color in column `column` as
+    /// `labels[column]`, or `None` if two colors would be displayed
+    /// the same.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Arrangement;
+    /// use rust::colors::ColorScheme;
+    ///
+    /// // Marrón, Verde, Transparente.
+    /// let spanish = ColorScheme::new(['M', 'V', 'T']).unwrap();
+    /// assert_eq!(spanish.name(Arrangement::GBC), "VMT");
+    ///
+    /// assert_eq!(ColorScheme::new(['B', 'B', 'C']), None);
+    /// ```
+    pub fn new(labels: [char; 3]) -> Option<ColorScheme> {
+        if labels[0] == labels[1] || labels[0] == labels[2] || labels[1] == labels[2] {
+            return None;
+        }
+        
+        Some(ColorScheme { labels })
+    }
+    
+    /// Return the character of the color in each column.
+    pub fn labels(&self) -> [char; 3] {
+        self.labels
+    }
+    
+    /// Return the characters of the colors `arrangement` puts in bin
+    /// one, bin two, and bin three.
+    pub fn name(&self, arrangement: Arrangement) -> String {
+        self.display(arrangement).to_string()
+    }
+    
+    /// Return a value that formats as `ColorScheme::name()`, without
+    /// allocating a `String` for it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, ColorScheme};
+    ///
+    /// let spanish = ColorScheme::new(['M', 'V', 'T']).unwrap();
+    /// assert_eq!(format!("{} 2", spanish.display(Arrangement::GBC)), "VMT 2");
+    /// ```
+    pub fn display(&self, arrangement: Arrangement) -> Named {
+        Named {
+            colors: *self,
+            arrangement,
+        }
+    }
+    
+    /// Return the arrangement that moves the fewest bottles, and how
+    /// many bottles it moves, as `solve()` does, but breaking ties by
+    /// the alphabetical order of this scheme's names.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{solve, Arrangement};
+    /// use rust::colors::ColorScheme;
+    ///
+    /// let bins = [[1, 1, 1], [1, 1, 1], [1, 1, 1]];
+    /// assert_eq!(solve(bins).arrangement, Arrangement::BCG);
+    ///
+    /// let scheme = ColorScheme::new(['X', 'Y', 'A']).unwrap();
+    /// assert_eq!(scheme.display_result(scheme.solve(bins)).to_string(), "AXY 6");
+    /// ```
+    pub fn solve(&self, bins: [[u64; 3]; 3]) -> BinResult {
+        self.solve_by(bins, TieBreak::Alphabetical)
+    }
+    
+    /// Like `ColorScheme::solve()`, but breaking ties by `tie_break`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, BinResult, ColorScheme, TieBreak};
+    ///
+    /// let colors = ColorScheme::default();
+    /// let bins = [[1, 1, 1], [1, 1, 1], [1, 1, 1]];
+    ///
+    /// assert_eq!(colors.solve_by(bins, TieBreak::Reverse), BinResult::new(Arrangement::GCB, 6));
+    /// assert_eq!(
+    ///     colors.solve_by(bins, TieBreak::Custom(|a, b| a.columns()[2].cmp(&b.columns()[2]))),
+    ///     BinResult::new(Arrangement::CGB, 6)
+    /// );
+    /// ```
+    pub fn solve_by(&self, bins: [[u64; 3]; 3], tie_break: TieBreak) -> BinResult {
+        Arrangement::ALL
+            .iter()
+            .map(|&arrangement| BinResult::new(arrangement, solver::moves(&bins, &arrangement.columns())))
+            .min_by(|a, b| {
+                a.moves.cmp(&b.moves).then_with(|| tie_break.compare(self, a.arrangement, b.arrangement))
+            })
+            .expect("there are 6 arrangements")
+    }
+    
+    /// Return a value that formats `result` as the judge expects a
+    /// line of output, e.g., `GBC 2`, but with this scheme's names.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, BinResult, ColorScheme};
+    ///
+    /// let spanish = ColorScheme::new(['M', 'V', 'T']).unwrap();
+    /// let result = BinResult::new(Arrangement::GBC, 2);
+    ///
+    /// assert_eq!(spanish.display_result(result).to_string(), "VMT 2");
+    /// assert_eq!(result.to_string(), "GBC 2");
+    /// ```
+    pub fn display_result(&self, result: BinResult) -> NamedResult {
+        NamedResult {
+            colors: *self,
+            result,
+        }
+    }
+}
+
+/// The problem's own scheme: `B` for Brown, `G` for Green, and `C`
+/// for Clear.
+impl Default for ColorScheme {
+    fn default() -> ColorScheme {
+        ColorScheme { labels: ['B', 'G', 'C'] }
+    }
+}
+
+/// `Named` struct. An arrangement as named by a color scheme,
+/// returned by `ColorScheme::display()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Named {
+    /// The scheme naming the colors.
+    colors: ColorScheme,
+    
+    /// The arrangement being named.
+    arrangement: Arrangement,
+}
+
+impl fmt::Display for Named {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Each label takes at most 4 bytes as UTF-8.
+        let mut name = [0; 12];
+        let mut len = 0;
+        for &column in self.arrangement.columns().iter() {
+            len += self.colors.labels[column].encode_utf8(&mut name[len..]).len();
+        }
+        
+        f.pad(std::str::from_utf8(&name[..len]).expect("labels are UTF-8"))
+    }
+}
+
+/// `NamedResult` struct. A result as named by a color scheme,
+/// returned by `ColorScheme::display_result()`.
+#[derive(Debug, Clone, Copy)]
+pub struct NamedResult {
+    /// The scheme naming the colors.
+    colors: ColorScheme,
+    
+    /// The result being named.
+    result: BinResult,
+}
+
+impl fmt::Display for NamedResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.colors.display(self.result.arrangement), self.result.moves)
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn colors_default_matches_arrangement() {
+        let scheme = ColorScheme::default();
+        
+        for &arrangement in Arrangement::ALL.iter() {
+            assert_eq!(scheme.name(arrangement), arrangement.to_string());
+        }
+        for line in include_str!("../input.txt").lines() {
+            let counts: Vec<u64> = line.split_whitespace().map(|count| count.parse().unwrap()).collect();
+            let bins = [
+                [counts[0], counts[1], counts[2]],
+                [counts[3], counts[4], counts[5]],
+                [counts[6], counts[7], counts[8]],
+            ];
+            assert_eq!(scheme.solve(bins), crate::solve(bins));
+            assert_eq!(scheme.solve_by(bins, TieBreak::Alphabetical), crate::solve(bins));
+        }
+    }
+    
+    #[test]
+    fn colors_display_any_characters() {
+        let scheme = ColorScheme::new(['é', '緑', '🍾']).unwrap();
+        
+        assert_eq!(scheme.name(Arrangement::CGB), "🍾緑é");
+        assert_eq!(format!("[{:>5}]", scheme.display(Arrangement::BGC)), "[  é緑🍾]");
+    }
+}
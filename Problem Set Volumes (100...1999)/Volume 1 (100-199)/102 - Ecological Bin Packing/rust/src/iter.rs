@@ -0,0 +1,129 @@
+//! `iter` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Solving the cases of an input one at a time, for callers that want
+//! the results themselves rather than `Bins::run()`'s output.
+
+use std::io;
+
+use crate::{BinResult, BinState, Bins, BinsError, BinsOptions};
+
+/// `BinsIter` struct. An iterator over the results of the cases read
+/// from a reader, one per line, until the input ends or the user
+/// quits. Lines that aren't a case yield the `BinsError` saying why,
+/// and iterating goes on; an input that can't be read yields
+/// `BinsError::Read` and ends the iteration. Requests for help are
+/// skipped.
+#[derive(Debug)]
+pub struct BinsIter<R> {
+    /// The reader the cases are read from.
+    reader: R,
+    
+    /// Options controlling how the cases are read and solved.
+    options: BinsOptions,
+    
+    /// Whether the input has ended, or the user has quit.
+    done: bool,
+}
+
+impl<R: io::BufRead> BinsIter<R> {
+    /// Return an iterator over the results of the cases in `reader`,
+    /// read and solved as `Bins::run()` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, BinResult, BinsError, BinsIter};
+    ///
+    /// let input = "1 2 3 4 5 6 7 8 9\n1 2 3\n5 10 5 20 10 5 10 20 10\nquit\n1 1 1 1 1 1 1 1 1\n";
+    /// let results: Vec<_> = BinsIter::new(input.as_bytes()).collect();
+    ///
+    /// assert_eq!(results, [
+    ///     Ok(BinResult { arrangement: Arrangement::BCG, moves: 30 }),
+    ///     Err(BinsError::WrongFieldCount { got: 3 }),
+    ///     Ok(BinResult { arrangement: Arrangement::CBG, moves: 50 }),
+    /// ]);
+    ///
+    /// // Only the valid cases that move the most bottles.
+    /// let moves: Vec<u64> = BinsIter::new(input.as_bytes())
+    ///     .filter_map(Result::ok)
+    ///     .map(|result| result.moves)
+    ///     .filter(|&moves| moves > 40)
+    ///     .collect();
+    /// assert_eq!(moves, [50]);
+    /// ```
+    pub fn new(reader: R) -> BinsIter<R> {
+        BinsIter::with_options(reader, BinsOptions::default())
+    }
+    
+    /// Like `BinsIter::new()`, but configured by `options`.
+    pub fn with_options(reader: R, options: BinsOptions) -> BinsIter<R> {
+        BinsIter {
+            reader,
+            options,
+            done: false,
+        }
+    }
+}
+
+impl<R: io::BufRead> Iterator for BinsIter<R> {
+    type Item = Result<BinResult, BinsError>;
+    
+    fn next(&mut self) -> Option<Result<BinResult, BinsError>> {
+        while !self.done {
+            let bins = match Bins::new(&mut self.reader, &self.options) {
+                Ok(bins) => bins,
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(BinsError::Read(error.kind())));
+                }
+            };
+            
+            match bins.state {
+                BinState::Ok => {
+                    return Some(Ok(bins.calculate(&self.options)));
+                },
+                BinState::Err(error) => return Some(Err(error)),
+                BinState::Help => {},
+                BinState::Quit => self.done = true,
+            }
+        }
+        
+        None
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    use crate::Arrangement;
+    
+    #[test]
+    fn iter_judge_mode_and_read_errors() {
+        let options = BinsOptions {
+            judge: true,
+            ..BinsOptions::default()
+        };
+        let results: Vec<_> = BinsIter::with_options("help\n\n1 1 1 1 1 1 1 1 1".as_bytes(), options).collect();
+        assert_eq!(results, [
+            Err(BinsError::WrongFieldCount { got: 1 }),
+            Err(BinsError::WrongFieldCount { got: 0 }),
+            Ok(BinResult { arrangement: Arrangement::BCG, moves: 6 }),
+        ]);
+        
+        // Help is skipped, and a read error ends the iteration.
+        let mut iter = BinsIter::new(&b"h\n1 2 3 4 5 6 7 8 9\n\xff\n1 1 1 1 1 1 1 1 1\n"[..]);
+        assert_eq!(iter.next(), Some(Ok(BinResult { arrangement: Arrangement::BCG, moves: 30 })));
+        assert_eq!(iter.next(), Some(Err(BinsError::Read(io::ErrorKind::InvalidData))));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.take(1).count(), 0);
+    }
+}
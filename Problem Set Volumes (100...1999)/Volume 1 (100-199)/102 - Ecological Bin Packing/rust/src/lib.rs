@@ -4,7 +4,113 @@
 //!
 //! Date: 2020-06-06
 
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 use std::io;
+use std::num::NonZeroUsize;
+use std::thread;
+
+pub mod arrangement;
+pub mod colors;
+pub mod iter;
+pub mod permutations;
+pub mod solver;
+pub mod stats;
+pub mod tie_break;
+pub mod verify;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use arrangement::{Arrangement, ParseArrangementError};
+pub use colors::ColorScheme;
+pub use iter::BinsIter;
+pub use stats::BinsStats;
+pub use tie_break::TieBreak;
+
+/// The most bottles the problem allows in all three bins together.
+pub const MAX_BOTTLES: u64 = 1 << 31;
+
+/// How many bytes of input `Bins::run_batch()` reads before solving
+/// them, give or take a line.
+const BATCH_BYTES: usize = 8 << 20;
+
+/// Solve one case of the problem without any input parsing. `bins`
+/// holds the number of Brown, Green, and Clear bottles, in that
+/// order, in each of the three bins. Return the arrangement of colors
+/// that moves the fewest bottles, and how many bottles it moves. If
+/// several arrangements move the fewest bottles, the alphabetically
+/// first is returned.
+///
+/// # Example
+///
+/// ```
+/// use rust::{solve, Arrangement, BinResult};
+///
+/// assert_eq!(solve([[1, 2, 3], [4, 5, 6], [7, 8, 9]]), BinResult::new(Arrangement::BCG, 30));
+///
+/// let result = solve([[5, 10, 5], [20, 10, 5], [10, 20, 10]]);
+/// assert_eq!((result.arrangement, result.moves), (Arrangement::CBG, 50));
+/// assert_eq!(result.to_string(), "CBG 50");
+/// ```
+pub fn solve(bins: [[u64; 3]; 3]) -> BinResult {
+    ColorScheme::default().solve(bins)
+}
+
+/// `BinResult` struct. The answer to one case: the arrangement of
+/// colors that moves the fewest bottles, and how many it moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinResult {
+    /// The arrangement of colors.
+    pub arrangement: Arrangement,
+    
+    /// The number of bottles moved.
+    pub moves: u64,
+}
+
+impl BinResult {
+    /// Return the result of `arrangement` moving `moves` bottles.
+    pub fn new(arrangement: Arrangement, moves: u64) -> BinResult {
+        BinResult { arrangement, moves }
+    }
+}
+
+/// Formats the result exactly as the judge expects a line of output,
+/// e.g., `GBC 2`. See `ColorScheme::display_result()` to name the
+/// colors differently.
+impl fmt::Display for BinResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        ColorScheme::default().display_result(*self).fmt(f)
+    }
+}
+
+/// Return every arrangement, with how many bottles it moves for
+/// `bins`, from best to worst. Arrangements that move the same number
+/// of bottles are in alphabetical order, as the problem's tie-breaking
+/// rule requires, so the first one is the answer `solve()` returns.
+///
+/// # Example
+///
+/// ```
+/// use rust::{ranked_arrangements, Arrangement};
+///
+/// let ranked = ranked_arrangements([[5, 10, 5], [20, 10, 5], [10, 20, 10]]);
+///
+/// assert_eq!(ranked[0], (Arrangement::CBG, 50));
+/// assert_eq!(ranked[1], (Arrangement::GBC, 55));
+/// assert_eq!(ranked[3..], [(Arrangement::BGC, 70), (Arrangement::CGB, 70), (Arrangement::GCB, 70)]);
+/// ```
+pub fn ranked_arrangements(bins: [[u64; 3]; 3]) -> [(Arrangement, u64); 6] {
+    let mut ranked = Arrangement::ALL.map(|arrangement| {
+        (arrangement, solver::moves(&bins, &arrangement.columns()))
+    });
+    
+    // The sort is stable, and `Arrangement::ALL` is in alphabetical
+    // order.
+    ranked.sort_by_key(|&(_, moves)| moves);
+    
+    ranked
+}
 
 /// `Bin` enum. Has three variants, `Bin::One`, `Bin::Two`,
 /// and `Bin::Three`. Each variant holds a 3-tuple of unsigned
@@ -15,18 +121,19 @@ use std::io;
 #[derive(Debug, PartialEq)]
 enum Bin {
     /// Bin One. Colors are: `(Brown, Green, Clear)`.
-    One(usize, usize, usize),
+    One(u64, u64, u64),
     
     /// Bin Two. Colors are: `(Brown, Green, Clear)`.
-    Two(usize, usize, usize),
+    Two(u64, u64, u64),
     
     /// Bin Three. Colors are: `(Brown, Green, Clear)`.
-    Three(usize, usize, usize),
+    Three(u64, u64, u64),
 }
 
 /// `BinState` enum. Each `Bins` instance has a `state`
-/// field which corresponds to one of these three variants:
-/// `BinState::Ok`, `BinState::Err`, and `BinState::Quit`.
+/// field which corresponds to one of these four variants:
+/// `BinState::Ok`, `BinState::Err`, `BinState::Help`, and
+/// `BinState::Quit`.
 #[derive(Debug, PartialEq)]
 enum BinState {
     /// `BinState::Ok` variant. Indicates that the input
@@ -37,8 +144,13 @@ enum BinState {
     /// `BinState::Err` variant. Indicates that there was an
     /// error during input parameter parsing: 1) there was not
     /// exactly 9 input parameters; or 2) one of the 9 input
-    /// parameters did not parse into an unsigned integer.
-    Err,
+    /// parameters did not parse into an unsigned integer. Holds
+    /// which.
+    Err(BinsError),
+    
+    /// `BinState::Help` variant. Indicates that the user
+    /// entered "h" or "help" as input.
+    Help,
     
     /// `BinState::Quit` variant. Indicates that the user
     /// input no parameters (an empty input string) or that
@@ -46,6 +158,156 @@ enum BinState {
     Quit,
 }
 
+/// `TooManyBottles` struct. Returned when building a `Bins`
+/// instance that holds more than `MAX_BOTTLES` bottles. Holds the
+/// number of bottles it would have held, if that fits in a `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TooManyBottles(pub Option<u64>);
+
+impl fmt::Display for TooManyBottles {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(total) => write!(f, "Error! The bins hold {} bottles, more than {}.", total, MAX_BOTTLES),
+            None => write!(f, "Error! The bins hold more than {} bottles.", u64::MAX),
+        }
+    }
+}
+
+impl Error for TooManyBottles {}
+
+/// `BinsError` enum. Why a line of input is not exactly 9
+/// unsigned integers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinsError {
+    /// The line holds `got` fields rather than 9.
+    WrongFieldCount {
+        /// How many fields the line holds.
+        got: usize,
+    },
+    
+    /// A field isn't an unsigned integer.
+    InvalidInteger {
+        /// The position of the field on the line, from `0`.
+        index: usize,
+        
+        /// The field.
+        token: String,
+    },
+    
+    /// The input couldn't be read.
+    Read(io::ErrorKind),
+}
+
+impl fmt::Display for BinsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BinsError::WrongFieldCount { got } => {
+                write!(f, "Error! Invalid parameters: expected 9 bottle counts, got {}.", got)
+            },
+            BinsError::InvalidInteger { index, token } => {
+                write!(f, "Error! Invalid parameters: bottle count {}, `{}`, is not an unsigned integer.",
+                       index + 1, token)
+            },
+            BinsError::Read(kind) => write!(f, "Error! Cannot read the input: {}.", kind),
+        }
+    }
+}
+
+impl Error for BinsError {}
+
+/// `ParsedLine` enum. The result of parsing a line of input.
+/// See `Bins::parse()`.
+#[derive(Debug, PartialEq)]
+enum ParsedLine {
+    /// The 9 bottle counts: Bin One's Brown, Green, and Clear,
+    /// then Bin Two's, then Bin Three's.
+    Bins([u64; 9]),
+    
+    /// The user input no parameters (an empty input string), or
+    /// entered "q" or "quit" as input.
+    Quit,
+    
+    /// The user entered "h" or "help" as input.
+    Help,
+    
+    /// The input is not exactly 9 unsigned integers.
+    Err(BinsError),
+}
+
+/// `InputFormat` enum. How the 9 bottle counts are separated on a
+/// line of input.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InputFormat {
+    /// Separated by whitespace, e.g., `1 2 3 4 5 6 7 8 9`, as the
+    /// problem's input is.
+    #[default]
+    Whitespace,
+    
+    /// Comma-separated values, e.g., `1,2,3,4,5,6,7,8,9`, as
+    /// spreadsheets export them. Whitespace around each value is
+    /// ignored.
+    Csv,
+}
+
+/// `OutputFormat` enum. How each solved case is written.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    /// The arrangement and the number of bottles moved, e.g.,
+    /// `GBC 2`, as the problem's output is.
+    #[default]
+    Text,
+    
+    /// A JSON object per line, e.g.,
+    /// `{"arrangement":"GBC","moves":2}`, for other programs to read.
+    JsonLines,
+}
+
+/// `BinsOptions` struct. Options controlling how `Bins` reads its
+/// input and writes its output. The defaults behave as an
+/// interactive session would.
+#[derive(Debug, Clone, Default)]
+pub struct BinsOptions {
+    /// Judge mode: process every line until the input ends, as the
+    /// judge's input has no `quit` line. Lines holding `q`, `quit`,
+    /// or nothing are then invalid parameters rather than quitting.
+    pub judge: bool,
+    
+    /// Skip lines holding nothing but whitespace, as some judge
+    /// mirrors and hand-made inputs separate their cases with them,
+    /// rather than quitting, or, in judge mode, rejecting them. Only
+    /// the end of the input, or `quit`, then stops reading.
+    pub skip_blank_lines: bool,
+    
+    /// Write each case's bins, as they were understood, onto `err`
+    /// before writing its answer, to catch counts given in the wrong
+    /// order. See `Bins`'s `Display`.
+    pub verbose: bool,
+    
+    /// How the bottle counts are separated. See `InputFormat`.
+    pub format: InputFormat,
+    
+    /// How the colors are named in the output, and so how ties are
+    /// broken. See `ColorScheme`.
+    pub colors: ColorScheme,
+    
+    /// Which arrangement is written when several move the fewest
+    /// bottles. See `TieBreak`.
+    pub tie_break: TieBreak,
+    
+    /// How each solved case is written. See `OutputFormat`.
+    pub output: OutputFormat,
+    
+    /// How many threads `Bins::run_batch()` solves the cases on, or
+    /// `0` for one per available CPU.
+    pub threads: usize,
+    
+    /// Have `Bins::run_batch()` read each block of the input a whole
+    /// chunk of the reader's buffer at a time, and split it into
+    /// lines itself, rather than reading it a line at a time. Faster
+    /// for inputs of millions of lines.
+    pub chunked: bool,
+}
+
 /// `Bins` struct. Contains the `state` and the bins (one, two,
 /// and three) that holds the Brown, Green, and Clear recycling
 /// glass.
@@ -81,6 +343,9 @@ impl Bins {
     /// `io::stderr` an error message. When the user inputs "q",
     /// "quit", or an empty line of input, quit the program.
     ///
+    /// Returns an `Err()` if reading the input or writing the
+    /// output fails.
+    ///
     /// # Example
     ///
     /// ```no_run
@@ -92,28 +357,128 @@ impl Bins {
     ///     let stdin = io::stdin();
     ///     let mut _reader = stdin.lock();
     ///     
-    ///     Bins::run(&mut _reader);
+    ///     Bins::run(&mut _reader)?;
     ///     
     ///     Ok(())
     /// }
     /// ```
-    pub fn run(mut buf: &mut impl io::BufRead) {
+    pub fn run(buf: &mut impl io::BufRead) -> io::Result<()> {
+        Bins::run_with(buf, &mut io::stdout(), &mut io::stderr())
+    }
+    
+    /// Like `Bins::run()`, but write the results onto `out` and the
+    /// error messages onto `err` instead of `io::stdout` and
+    /// `io::stderr`, so they can be captured.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Bins;
+    ///
+    /// let mut input = "1 2 3 4 5 6 7 8 9\n1 2 3\n5 10 5 20 10 5 10 20 10\n".as_bytes();
+    /// let mut out = Vec::new();
+    /// let mut err = Vec::new();
+    ///
+    /// Bins::run_with(&mut input, &mut out, &mut err).unwrap();
+    /// assert_eq!(out, b"BCG 30\nCBG 50\n");
+    /// assert_eq!(err, b"Error! Invalid parameters: expected 9 bottle counts, got 3.\n");
+    /// ```
+    pub fn run_with(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write
+    ) -> io::Result<()> {
+        Bins::run_with_options(buf, out, err, BinsOptions::default())
+    }
+    
+    /// Like `Bins::run_with()`, but configured by `options`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Bins, BinsOptions};
+    ///
+    /// let mut input = "1 2 3 4 5 6 7 8 9\n\nquit\n5 10 5 20 10 5 10 20 10\n".as_bytes();
+    /// let mut out = Vec::new();
+    /// let mut err = Vec::new();
+    /// let options = BinsOptions {
+    ///     judge: true,
+    ///     ..BinsOptions::default()
+    /// };
+    ///
+    /// Bins::run_with_options(&mut input, &mut out, &mut err, options).unwrap();
+    /// assert_eq!(out, b"BCG 30\nCBG 50\n");
+    /// assert_eq!(
+    ///     String::from_utf8(err).unwrap(),
+    ///     "Error! Invalid parameters: expected 9 bottle counts, got 0.\n\
+    ///      Error! Invalid parameters: expected 9 bottle counts, got 1.\n"
+    /// );
+    /// ```
+    pub fn run_with_options(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write,
+        options: BinsOptions
+    ) -> io::Result<()> {
         loop {
-            let bins = Bins::new(&mut buf);
+            let bins = Bins::new(buf, &options)?;
             
             match bins.state {
                 BinState::Ok => {
-                    let (output, count) = bins.calculate();
-                    println!("{} {}", output, count);
+                    if options.verbose {
+                        writeln!(err, "{}", bins)?;
+                    }
+                    Bins::write_answer(out, bins.calculate(&options), &options)?;
+                },
+                BinState::Err(error) => {
+                    writeln!(err, "{}", error)?;
                 },
-                BinState::Err => {
-                    eprintln!("Error! Invalid parameters.");
+                BinState::Help => {
+                    Bins::write_help(err, &options)?;
                 },
                 BinState::Quit => {
                     break;
                 }
             }
         }
+        
+        out.flush()
+    }
+    
+    /// Like `Bins::run_with_options()`, but read the input a block of
+    /// a few megabytes at a time, and solve each block's cases on
+    /// `options.threads` threads before reading the next, so that
+    /// however large the input is, only a block of it is held at
+    /// once. The results and error messages are written in the order
+    /// of the input, as `Bins::run_with_options()` writes them, so
+    /// this is only faster, for large inputs, not different. Reading
+    /// stops at the end of the input, or after the block holding
+    /// `quit`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Bins, BinsOptions};
+    ///
+    /// let mut input = "1 2 3 4 5 6 7 8 9\n1 2 3\n5 10 5 20 10 5 10 20 10\nquit\n1 1 1 1 1 1 1 1 1\n".as_bytes();
+    /// let mut out = Vec::new();
+    /// let mut err = Vec::new();
+    /// let options = BinsOptions {
+    ///     threads: 2,
+    ///     ..BinsOptions::default()
+    /// };
+    ///
+    /// Bins::run_batch(&mut input, &mut out, &mut err, options).unwrap();
+    /// assert_eq!(out, b"BCG 30\nCBG 50\n");
+    /// assert_eq!(err, b"Error! Invalid parameters: expected 9 bottle counts, got 3.\n");
+    /// ```
+    pub fn run_batch(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write,
+        options: BinsOptions
+    ) -> io::Result<()> {
+        Bins::run_blocks(buf, out, err, options, BATCH_BYTES)
     }
     
     /// Return a new `Bins` instance of the parsing result. Takes
@@ -121,30 +486,132 @@ impl Bins {
     /// trait. For the line of input, parse out 9 unsigned
     /// integers into their respective bins, each representing
     /// the number of Brown, Green, and Clear glass bottles,
-    /// respectively.
-    fn new(mut buf: &mut impl io::BufRead) -> Bins {
-        if let Ok((a, b, c, d, e, f, g, h, i)) = Bins::read_line(&mut buf) {
-            if a == -1 || b == -1 || c == -1 ||
-               d == -1 || e == -1 || f == -1 ||
-               g == -1 || h == -1 || i == -1
-            {
-                Bins::error()
-            }
-            else if a == -2 || b == -2 || c == -2 ||
-                    d == -2 || e == -2 || f == -2 ||
-                    g == -2 || h == -2 || i == -2
-            {
-                Bins::quit()
-            }
-            else {
-                Bins::ok(a as usize, b as usize, c as usize,
-                         d as usize, e as usize, f as usize,
-                         g as usize, h as usize, i as usize)
-            }
+    /// respectively. Returns an `Err()` if reading fails.
+    fn new(buf: &mut impl io::BufRead, options: &BinsOptions) -> io::Result<Bins> {
+        Ok(Bins::from_parsed(Bins::read_line(buf, options)?))
+    }
+    
+    /// Return a new `Bins` instance holding `rows`, the number of
+    /// Brown, Green, and Clear bottles, in that order, in each bin,
+    /// without any input parsing. Returns an `Err()` if the bins hold
+    /// more than `MAX_BOTTLES` bottles, which the problem never asks
+    /// for.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, Bins, TooManyBottles, MAX_BOTTLES};
+    ///
+    /// let bins = Bins::from_rows([[1, 2, 3], [4, 5, 6], [7, 8, 9]]).unwrap();
+    /// assert_eq!(bins.solve().arrangement, Arrangement::BCG);
+    ///
+    /// assert_eq!(
+    ///     Bins::from_rows([[MAX_BOTTLES, 1, 0], [0; 3], [0; 3]]),
+    ///     Err(TooManyBottles(Some(MAX_BOTTLES + 1)))
+    /// );
+    /// ```
+    pub fn from_rows(rows: [[u64; 3]; 3]) -> Result<Bins, TooManyBottles> {
+        let total = rows
+            .iter()
+            .flatten()
+            .try_fold(0u64, |total, &count| total.checked_add(count))
+            .ok_or(TooManyBottles(None))?;
+        if total > MAX_BOTTLES {
+            return Err(TooManyBottles(Some(total)));
         }
-        else {
-            Bins::error()
+        
+        let [[a, b, c], [d, e, f], [g, h, i]] = rows;
+        Ok(Bins::ok([a, b, c, d, e, f, g, h, i]))
+    }
+    
+    /// Return the number of Brown, Green, and Clear bottles, in
+    /// that order, in each bin.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::convert::TryFrom;
+    ///
+    /// use rust::Bins;
+    ///
+    /// let bins = Bins::try_from([1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+    /// assert_eq!(bins.counts(), [[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+    /// ```
+    pub fn counts(&self) -> [[u64; 3]; 3] {
+        let mut counts = [[0; 3]; 3];
+        
+        for (count, bin) in counts.iter_mut().zip([&self.bin1, &self.bin2, &self.bin3].iter()) {
+            let (brown, green, clear) = match bin {
+                Bin::One(a, b, c) | Bin::Two(a, b, c) | Bin::Three(a, b, c) => (a, b, c),
+            };
+            *count = [*brown, *green, *clear];
         }
+        
+        counts
+    }
+    
+    /// Return the arrangement of colors that moves the fewest
+    /// bottles, and how many bottles it moves, as `solve()` does.
+    pub fn solve(&self) -> BinResult {
+        solve(self.counts())
+    }
+    
+    /// Return the stats of these bins, as `BinsStats::new()` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Bins;
+    ///
+    /// let bins = Bins::from_rows([[1, 0, 0], [0, 2, 0], [0, 0, 3]]).unwrap();
+    /// let stats = bins.stats();
+    ///
+    /// assert_eq!((stats.total, stats.best, stats.worst), (6, 0, 6));
+    /// ```
+    pub fn stats(&self) -> BinsStats {
+        BinsStats::new(self.counts())
+    }
+    
+    /// Return how many bottles `arrangement` moves, whether or not
+    /// it moves the fewest. An arrangement can also be parsed from
+    /// its name, e.g., `"GBC"`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::{Arrangement, Bins};
+    ///
+    /// let bins = Bins::from_rows([[5, 10, 5], [20, 10, 5], [10, 20, 10]]).unwrap();
+    ///
+    /// assert_eq!(bins.moves_for(Arrangement::CBG), 50);
+    /// assert_eq!(bins.moves_for("GBC".parse().unwrap()), 55);
+    /// ```
+    pub fn moves_for(&self, arrangement: Arrangement) -> u64 {
+        solver::moves(&self.counts(), &arrangement.columns())
+    }
+    
+    /// Return how many bottles `arrangement` moves out of Bin One,
+    /// Bin Two, and Bin Three. They add up to
+    /// `Bins::moves_for(arrangement)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::Bins;
+    ///
+    /// let bins = Bins::from_rows([[5, 10, 5], [20, 10, 5], [10, 20, 10]]).unwrap();
+    /// let best = bins.solve();
+    ///
+    /// // Clear stays in Bin One, Brown in Bin Two, Green in Bin Three.
+    /// assert_eq!(bins.moves_per_bin(best.arrangement), [15, 15, 20]);
+    /// assert_eq!(bins.moves_per_bin(best.arrangement).iter().sum::<u64>(), best.moves);
+    /// ```
+    pub fn moves_per_bin(&self, arrangement: Arrangement) -> [u64; 3] {
+        let [bin1, bin2, bin3] = solver::moves_per_bin(&self.counts(), &arrangement.columns())[..] else {
+            unreachable!("there are 3 bins");
+        };
+        
+        [bin1, bin2, bin3]
     }
     
     //
@@ -152,106 +619,246 @@ impl Bins {
     //
     
     /// Read a line of input from `buf` and return a `Result<T>`
-    /// variant that either holds the 9 integers read from input,
-    /// or an `io::Error`.
-    fn read_line(buf: &mut impl io::BufRead) -> io::Result<(isize, isize, isize,
-                                                            isize, isize, isize,
-                                                            isize, isize, isize)>
-    {
+    /// variant that either holds the parsed line, or an
+    /// `io::Error`. The end of the input is `ParsedLine::Quit`.
+    /// Blank lines are skipped if `options.skip_blank_lines` says
+    /// so.
+    fn read_line(buf: &mut impl io::BufRead, options: &BinsOptions) -> io::Result<ParsedLine> {
         let mut input = String::new();
         
-        buf.read_line(&mut input)?;
-        
-        Ok(Bins::parse(input))
+        loop {
+            if buf.read_line(&mut input)? == 0 {
+                return Ok(ParsedLine::Quit);
+            }
+            if !options.skip_blank_lines || !input.trim().is_empty() {
+                return Ok(Bins::parse(&input, options));
+            }
+            
+            input.clear();
+        }
     }
     
-    /// Given an input string, parse out the command from the
-    /// user and process the command accordingly. Return values
-    /// of `-1`s indicated invalid parameters. Return values of
-    /// `-2`s indicates that the user wants to quit the program.
-    ///
-    /// If any return result contains a `-1` value, it's treated
-    /// like an invalid parameters error (either parameter count
-    /// was not exactly 9 or could not parse exactly 9 unsigned
-    /// integers).
-    fn parse(input: String) -> (isize, isize, isize,
-                                isize, isize, isize,
-                                isize, isize, isize)
-    {
-        match input.trim().to_lowercase().as_str() {
-            "quit" | "q" | "" => return (-2, -2, -2,
-                                         -2, -2, -2,
-                                         -2, -2, -2),
-            _ => {},
-        }
-        
-        let parts: Vec<Result<usize, _>> = input
-            .split_whitespace()
-            .map(|x| x.parse::<usize>())
-            .collect();
+    /// `Bins::run_batch()`, reading blocks of at least `block_bytes`
+    /// bytes of input, but for the last one, each ending with a whole
+    /// line.
+    fn run_blocks(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write,
+        options: BinsOptions,
+        block_bytes: usize
+    ) -> io::Result<()> {
+        let threads = match options.threads {
+            0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            threads => threads,
+        };
         
-        if parts.len() != 9 {
-            return (-1, -1, -1,
-                    -1, -1, -1,
-                    -1, -1, -1)
+        // The input read, but not solved yet. The block ends at `end`,
+        // and the rest, if any, is part of the next block's first line.
+        let mut input = Vec::new();
+        loop {
+            let (end, ended) = if options.chunked {
+                Bins::read_chunked(buf, &mut input, block_bytes)?
+            }
+            else {
+                Bins::read_lines(buf, &mut input, block_bytes)?
+            };
+            
+            let block = std::str::from_utf8(&input[..end])
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+            if Bins::solve_block(block, out, err, &options, threads)? || ended {
+                break;
+            }
+            input.drain(..end);
         }
         
-        let mut a = -1; // Bin One, Brown
-        let mut b = -1; // Bin One, Green
-        let mut c = -1; // Bin One, Clear
-        let mut d = -1; // Bin Two, Brown
-        let mut e = -1; // Bin Two, Green
-        let mut f = -1; // Bin Two, Clear
-        let mut g = -1; // Bin Three, Brown
-        let mut h = -1; // Bin Three, Green
-        let mut i = -1; // Bin Three, Clear
-        
-        if let Ok(a_) = parts[0] {
-            a = a_ as isize;
+        out.flush()
+    }
+    
+    /// Read lines of `buf` onto the end of `input` until it holds at
+    /// least `block_bytes` bytes, or the input ends. Returns the
+    /// length of `input`, and whether the input has ended, or an
+    /// `Err()` if reading fails.
+    fn read_lines(buf: &mut impl io::BufRead, input: &mut Vec<u8>, block_bytes: usize) -> io::Result<(usize, bool)> {
+        while input.len() < block_bytes {
+            if buf.read_until(b'\n', input)? == 0 {
+                return Ok((input.len(), true));
+            }
         }
         
-        if let Ok(b_) = parts[1] {
-            b = b_ as isize;
+        Ok((input.len(), false))
+    }
+    
+    /// Like `read_lines()`, but read a whole chunk of `buf`'s buffer
+    /// at a time, so `input` may end in part of a line. Returns where
+    /// the last whole line read ends instead, or the length of
+    /// `input`, if the input has ended.
+    fn read_chunked(buf: &mut impl io::BufRead, input: &mut Vec<u8>, block_bytes: usize) -> io::Result<(usize, bool)> {
+        loop {
+            let chunk = buf.fill_buf()?;
+            if chunk.is_empty() {
+                return Ok((input.len(), true));
+            }
+            
+            input.extend_from_slice(chunk);
+            let read = chunk.len();
+            buf.consume(read);
+            
+            // The block is big enough once it ends with a whole line.
+            let start = input.len() - read;
+            if input.len() >= block_bytes {
+                if let Some(end) = input[start..].iter().rposition(|&byte| byte == b'\n') {
+                    return Ok((start + end + 1, false));
+                }
+            }
         }
+    }
+    
+    /// Solve the cases of each line of `block` on `threads` threads,
+    /// and write their results and errors onto `out` and `err`, in
+    /// order, as `Bins::run_with_options()` does. Returns whether a
+    /// line quit, after which nothing more is written, or an `Err()`
+    /// if writing fails.
+    fn solve_block(
+        block: &str,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write,
+        options: &BinsOptions,
+        threads: usize
+    ) -> io::Result<bool> {
+        let lines: Vec<&str> = block
+            .split_inclusive('\n')
+            .filter(|line| !options.skip_blank_lines || !line.trim().is_empty())
+            .collect();
         
-        if let Ok(c_) = parts[2] {
-            c = c_ as isize;
-        }
+        let chunk_size = lines.len().div_ceil(threads).max(1);
         
-        if let Ok(d_) = parts[3] {
-            d = d_ as isize;
-        }
+        // Each thread solves a contiguous chunk of the lines, so
+        // joining them in turn keeps the results in input order.
+        let results: Vec<(Bins, Option<BinResult>)> = thread::scope(|scope| {
+            let handles: Vec<_> = lines
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|line| {
+                                let bins = Bins::from_parsed(Bins::parse(line, options));
+                                let answer = match bins.state {
+                                    BinState::Ok => Some(bins.calculate(options)),
+                                    _ => None,
+                                };
+                                
+                                (bins, answer)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("solving a case doesn't panic"))
+                .collect()
+        });
         
-        if let Ok(e_) = parts[4] {
-            e = e_ as isize;
+        for (bins, answer) in results {
+            match bins.state {
+                BinState::Ok => {
+                    if options.verbose {
+                        writeln!(err, "{}", bins)?;
+                    }
+                    Bins::write_answer(out, answer.expect("every valid case is solved"), options)?;
+                },
+                BinState::Err(error) => {
+                    writeln!(err, "{}", error)?;
+                },
+                BinState::Help => {
+                    Bins::write_help(err, options)?;
+                },
+                BinState::Quit => {
+                    return Ok(true);
+                }
+            }
         }
         
-        if let Ok(f_) = parts[5] {
-            f = f_ as isize;
+        Ok(false)
+    }
+    
+    /// Given an input string, parse out the command from the
+    /// user. Return `ParsedLine::Quit` if the user wants to quit
+    /// the program, `ParsedLine::Help` if the user asks for help,
+    /// `ParsedLine::Err` if the input is not exactly 9 unsigned
+    /// integers, separated as `options.format` says, else
+    /// `ParsedLine::Bins` holding them. In judge mode, the user
+    /// can't quit or ask for help.
+    fn parse(input: &str, options: &BinsOptions) -> ParsedLine {
+        let trimmed = input.trim();
+        if !options.judge && ["quit", "q", ""].iter().any(|quit| trimmed.eq_ignore_ascii_case(quit)) {
+            return ParsedLine::Quit;
         }
-        
-        if let Ok(g_) = parts[6] {
-            g = g_ as isize;
+        if !options.judge && ["help", "h"].iter().any(|help| trimmed.eq_ignore_ascii_case(help)) {
+            return ParsedLine::Help;
         }
         
-        if let Ok(h_) = parts[7] {
-            h = h_ as isize;
+        match options.format {
+            InputFormat::Whitespace => Bins::parse_counts(input.split_whitespace()),
+            InputFormat::Csv => Bins::parse_counts(trimmed.split(',').map(str::trim)),
+        }
+    }
+    
+    /// Parse exactly 9 unsigned integers out of `parts`. Return
+    /// `ParsedLine::Bins` holding them, else `ParsedLine::Err`
+    /// holding why not. A wrong number of fields is reported
+    /// rather than the first invalid one.
+    fn parse_counts<'a>(parts: impl Iterator<Item = &'a str>) -> ParsedLine {
+        // In order: Bin One's Brown, Green, and Clear, then Bin Two's,
+        // then Bin Three's.
+        let mut counts = [0; 9];
+        let mut got = 0;
+        let mut invalid = None;
+        for (index, part) in parts.enumerate() {
+            got += 1;
+            
+            if let Some(count) = counts.get_mut(index) {
+                match part.parse::<u64>() {
+                    Ok(n) => *count = n,
+                    Err(_) => {
+                        invalid = invalid.or_else(|| Some(BinsError::InvalidInteger {
+                            index,
+                            token: part.to_string(),
+                        }));
+                    }
+                }
+            }
         }
         
-        if let Ok(i_) = parts[8] {
-            i = i_ as isize;
+        if got != counts.len() {
+            return ParsedLine::Err(BinsError::WrongFieldCount { got });
         }
         
-        (a, b, c,
-         d, e, f,
-         g, h, i)
+        match invalid {
+            Some(error) => ParsedLine::Err(error),
+            None => ParsedLine::Bins(counts),
+        }
+    }
+    
+    /// Return a `Bins` instance holding the bottle counts of
+    /// `parsed`, or indicating why it has none.
+    fn from_parsed(parsed: ParsedLine) -> Bins {
+        match parsed {
+            ParsedLine::Bins(counts) => Bins::ok(counts),
+            ParsedLine::Quit => Bins::quit(),
+            ParsedLine::Help => Bins::help(),
+            ParsedLine::Err(error) => Bins::error(error),
+        }
     }
     
     /// Return a `Bins` instance indicating an error has
-    /// occured during parsing.
-    fn error() -> Bins {
+    /// occured during parsing, namely `error`.
+    fn error(error: BinsError) -> Bins {
         Bins {
-            state: BinState::Err,
+            state: BinState::Err(error),
             bin1: Bin::One(0, 0, 0),
             bin2: Bin::Two(0, 0, 0),
             bin3: Bin::Three(0, 0, 0),
@@ -269,12 +876,59 @@ impl Bins {
         }
     }
     
+    /// Write `result` onto `out`, as `options` says.
+    fn write_answer(out: &mut impl io::Write, result: BinResult, options: &BinsOptions) -> io::Result<()> {
+        match options.output {
+            OutputFormat::Text => writeln!(out, "{}", options.colors.display_result(result)),
+            OutputFormat::JsonLines => {
+                write!(out, "{{\"arrangement\":\"")?;
+                for label in options.colors.name(result.arrangement).chars() {
+                    match label {
+                        '"' => write!(out, "\\\"")?,
+                        '\\' => write!(out, "\\\\")?,
+                        label if label.is_control() => write!(out, "\\u{:04x}", label as u32)?,
+                        label => write!(out, "{}", label)?,
+                    }
+                }
+                writeln!(out, "\",\"moves\":{}}}", result.moves)
+            }
+        }
+    }
+    
+    /// Return a `Bins` instance indicating that the user
+    /// asked for help.
+    fn help() -> Bins {
+        Bins {
+            state: BinState::Help,
+            bin1: Bin::One(0, 0, 0),
+            bin2: Bin::Two(0, 0, 0),
+            bin3: Bin::Three(0, 0, 0),
+        }
+    }
+    
+    /// Write how to use the program onto `err`: the input expected,
+    /// separated as `options.format` says, and the commands.
+    fn write_help(err: &mut impl io::Write, options: &BinsOptions) -> io::Result<()> {
+        let (separator, example) = match options.format {
+            InputFormat::Whitespace => ("spaces", "5 10 5 20 10 5 10 20 10"),
+            InputFormat::Csv => ("commas", "5,10,5,20,10,5,10,20,10"),
+        };
+        
+        writeln!(err, "Enter 9 unsigned integers, separated by {}: the number of Brown, Green, and", separator)?;
+        writeln!(err, "Clear bottles in Bin One, then in Bin Two, then in Bin Three, e.g.:")?;
+        writeln!(err)?;
+        writeln!(err, "    {}", example)?;
+        writeln!(err)?;
+        writeln!(err, "The colors to put in each bin, and the number of bottles moved, are printed.")?;
+        writeln!(err, "Enter `quit`, `q`, or nothing to quit, or `help` or `h` to see this again.")
+    }
+    
     /// Return a `Bins` instance with each stack of bottles
-    /// in their proper place.
-    fn ok(a: usize, b: usize, c: usize,
-          d: usize, e: usize, f: usize,
-          g: usize, h: usize, i: usize) -> Bins
-    {
+    /// in their proper place. `counts` holds Bin One's Brown,
+    /// Green, and Clear bottles, then Bin Two's, then Bin Three's.
+    fn ok(counts: [u64; 9]) -> Bins {
+        let [a, b, c, d, e, f, g, h, i] = counts;
+        
         Bins {
             state: BinState::Ok,
             bin1: Bin::One(a, b, c),
@@ -284,154 +938,459 @@ impl Bins {
     }
     
     /// Calculate the shortest combination of bottle moves
-    /// from one bin to another. Return a 2-tuple containing
-    /// the output bin arrangement and the smallest count of
-    /// bottle moves.
-    fn calculate(&self) -> (String, usize) {
-        let mut outputs = vec![          // E.g.: vec![
-            self.bgc(),                  //           ("BGC",  4),
-            self.bcg(),                  //           ("BCG", 10),
-            self.gbc(),                  //           ("GBC",  2),
-            self.gcb(),                  //           ("GCB",  2),
-            self.cbg(),                  //           ("CBG",  8),
-            self.cgb(),                  //           ("CGB", 10),
-        ];                               //       ];
-        
-        //      sort               by count           by output
-        outputs.sort_by(|a, b| a.1.cmp(&b.1).then(a.0.cmp(&b.0)));
-        
-        (outputs[0].0.clone(), outputs[0].1) // E.g.: ("GBC", 2)
-    }
-    
-    /// Calculate the `BGC` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn bgc(&self) -> (String, usize) {
-        let output = String::from("BGC");
-        
-        //              B
-        if let Bin::One(_, b, c) = self.bin1 {
-            //                 G
-            if let Bin::Two(d, _, f) = self.bin2 {
-                //                      C
-                if let Bin::Three(g, h, _) = self.bin3 {
-                    return (output, d + g + b + h + c + f);
-                }
+    /// from one bin to another. Return the output bin
+    /// arrangement, chosen as `options` says, and the smallest
+    /// count of bottle moves.
+    fn calculate(&self, options: &BinsOptions) -> BinResult {
+        options.colors.solve_by(self.counts(), options.tie_break) // E.g.: GBC 2
+    }
+}
+
+/// Formats the bottle counts of each bin, e.g.,
+/// `bin1: brown=1 green=2 clear=3, bin2: brown=4 green=5 clear=6,
+/// bin3: brown=7 green=8 clear=9`, on one line.
+///
+/// # Example
+///
+/// ```
+/// use rust::Bins;
+///
+/// let bins = Bins::from_rows([[1, 2, 3], [4, 5, 6], [7, 8, 9]]).unwrap();
+///
+/// assert_eq!(
+///     bins.to_string(),
+///     "bin1: brown=1 green=2 clear=3, bin2: brown=4 green=5 clear=6, bin3: brown=7 green=8 clear=9"
+/// );
+/// ```
+impl fmt::Display for Bins {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (bin, [brown, green, clear]) in self.counts().iter().enumerate() {
+            if bin > 0 {
+                write!(f, ", ")?;
             }
+            write!(f, "bin{}: brown={} green={} clear={}", bin + 1, brown, green, clear)?;
         }
         
-        // This should never happen.
-        (output, 0)
+        Ok(())
     }
+}
+
+/// Build a `Bins` instance from Bin One's Brown, Green, and Clear
+/// bottles, then Bin Two's, then Bin Three's, as they would be given
+/// on a line of input. See `Bins::from_rows()`.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use rust::{Arrangement, BinResult, Bins};
+///
+/// let bins = Bins::try_from([5, 10, 5, 20, 10, 5, 10, 20, 10]).unwrap();
+/// assert_eq!(bins.solve(), BinResult::new(Arrangement::CBG, 50));
+/// ```
+impl TryFrom<[usize; 9]> for Bins {
+    type Error = TooManyBottles;
     
-    /// Calculate the `BCG` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn bcg(&self) -> (String, usize) {
-        let output = String::from("BCG");
+    fn try_from(counts: [usize; 9]) -> Result<Bins, TooManyBottles> {
+        let [a, b, c, d, e, f, g, h, i] = counts.map(|count| count as u64);
         
-        //              B
-        if let Bin::One(_, b, c) = self.bin1 {
-            //                    C
-            if let Bin::Two(d, e, _) = self.bin2 {
-                //                   G
-                if let Bin::Three(g, _, i) = self.bin3 {
-                    return (output, d + g + b + e + c + i);
-                }
-            }
+        Bins::from_rows([[a, b, c], [d, e, f], [g, h, i]])
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn bins_calculate_sample() {
+        let expected = [
+            ("BCG", 30),
+            ("CBG", 50),
+            ("BGC", 6),
+            ("BCG", 6),
+            ("GBC", 2126332),
+            ("BCG", 30000000),
+        ];
+        
+        for (line, &(output, count)) in include_str!("../input.txt").lines().zip(expected.iter()) {
+            let bins = match Bins::parse(line, &BinsOptions::default()) {
+                ParsedLine::Bins(counts) => Bins::ok(counts),
+                parsed => panic!("`{}` parsed into {:?}", line, parsed),
+            };
+            assert_eq!(bins.calculate(&BinsOptions::default()).to_string(), format!("{} {}", output, count));
         }
+    }
+    
+    #[test]
+    fn bins_run_with() {
+        let input = include_str!("../input.txt");
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        
+        Bins::run_with(&mut input.as_bytes(), &mut out, &mut err).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "BCG 30\nCBG 50\nBGC 6\nBCG 6\nGBC 2126332\nBCG 30000000\n"
+        );
+        assert!(err.is_empty());
+        
+        // Reading stops at the first `quit`.
+        let mut out = Vec::new();
+        Bins::run_with(&mut "x\nquit\n1 1 1 1 1 1 1 1 1\n".as_bytes(), &mut out, &mut err).unwrap();
+        assert!(out.is_empty());
+        assert_eq!(err, b"Error! Invalid parameters: expected 9 bottle counts, got 1.\n");
+        
+        // Help is written with the errors, and reading goes on.
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        Bins::run_with(&mut "help\n1 2 3 4 5 6 7 8 9\n".as_bytes(), &mut out, &mut err).unwrap();
+        assert_eq!(out, b"BCG 30\n");
+        let help = String::from_utf8_lossy(&err);
+        assert!(help.starts_with("Enter 9 unsigned integers, separated by spaces"), "{}", help);
+        assert!(help.contains("    5 10 5 20 10 5 10 20 10\n"), "{}", help);
         
-        // This should never happen.
-        (output, 0)
+        // Input that is not UTF-8 can't be read.
+        let error = Bins::run_with(&mut &b"\xff\n"[..], &mut out, &mut err).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
     }
     
-    /// Calculate the `GBC` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn gbc(&self) -> (String, usize) {
-        let output = String::from("GBC");
+    #[test]
+    fn bins_run_with_colors_and_tie_break() {
+        let options = BinsOptions {
+            colors: ColorScheme::new(['X', 'Y', 'A']).unwrap(),
+            ..BinsOptions::default()
+        };
+        let mut out = Vec::new();
+        let mut err = Vec::new();
         
-        //                 G
-        if let Bin::One(a, _, c) = self.bin1 {
-            //              B
-            if let Bin::Two(_, e, f) = self.bin2 {
-                //                      C
-                if let Bin::Three(g, h, _) = self.bin3 {
-                    return (output, a + g + e + h + c + f);
-                }
-            }
+        // Ties are broken by the scheme's names, so where the default
+        // scheme answers `BCG`, this one answers `AXY`, i.e., `CBG`.
+        let input = "1 2 3 4 5 6 7 8 9\n1 1 1 1 1 1 1 1 1\n1 0 0 0 1 0 0 0 1\n";
+        Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "AXY 30\nAXY 6\nXYA 0\n");
+        assert!(err.is_empty());
+        
+        let options = BinsOptions {
+            tie_break: TieBreak::Reverse,
+            ..BinsOptions::default()
+        };
+        let mut out = Vec::new();
+        Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "GCB 30\nGCB 6\nBGC 0\n");
+    }
+    
+    #[test]
+    fn bins_run_skipping_blank_lines() {
+        let input = "\n1 2 3 4 5 6 7 8 9\n\n \t\r\n5 10 5 20 10 5 10 20 10\n\nx\nquit\n1 1 1 1 1 1 1 1 1\n";
+        let expected_err = "Error! Invalid parameters: expected 9 bottle counts, got 1.\n";
+        
+        for &judge in [false, true].iter() {
+            let options = BinsOptions {
+                judge,
+                skip_blank_lines: true,
+                ..BinsOptions::default()
+            };
+            
+            // Only `quit` stops reading, unless in judge mode.
+            let expected_out = if judge { "BCG 30\nCBG 50\nBCG 6\n" } else { "BCG 30\nCBG 50\n" };
+            let expected_err = if judge { expected_err.repeat(2) } else { expected_err.to_string() };
+            
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected_out);
+            assert_eq!(String::from_utf8(err).unwrap(), expected_err);
+            
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            Bins::run_batch(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected_out);
+            assert_eq!(String::from_utf8(err).unwrap(), expected_err);
+            
+            assert_eq!(BinsIter::with_options(input.as_bytes(), options).filter(Result::is_ok).count(),
+                       if judge { 3 } else { 2 });
         }
+    }
+    
+    #[test]
+    fn bins_run_verbose() {
+        let options = BinsOptions {
+            verbose: true,
+            ..BinsOptions::default()
+        };
+        let input = "1 2 3 4 5 6 7 8 9\nx\n";
+        let expected_err = "bin1: brown=1 green=2 clear=3, bin2: brown=4 green=5 clear=6, bin3: brown=7 green=8 clear=9\n\
+                            Error! Invalid parameters: expected 9 bottle counts, got 1.\n";
         
-        // This should never happen.
-        (output, 0)
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+        assert_eq!(out, b"BCG 30\n");
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err);
+        
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        Bins::run_batch(&mut input.as_bytes(), &mut out, &mut err, options).unwrap();
+        assert_eq!(out, b"BCG 30\n");
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err);
     }
     
-    /// Calculate the `GCB` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn gcb(&self) -> (String, usize) {
-        let output = String::from("GCB");
+    #[test]
+    fn bins_run_with_json_lines() {
+        let options = BinsOptions {
+            output: OutputFormat::JsonLines,
+            ..BinsOptions::default()
+        };
+        let input = "1 2 3 4 5 6 7 8 9\nx\n5 10 5 20 10 5 10 20 10\n";
+        let (mut out, mut err) = (Vec::new(), Vec::new());
         
-        //                 G
-        if let Bin::One(a, _, c) = self.bin1 {
-            //                    C
-            if let Bin::Two(d, e, _) = self.bin2 {
-                //                B
-                if let Bin::Three(_, h, i) = self.bin3 {
-                    return (output, a + d + e + h + c + i);
-                }
+        Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"arrangement\":\"BCG\",\"moves\":30}\n{\"arrangement\":\"CBG\",\"moves\":50}\n"
+        );
+        assert_eq!(err, b"Error! Invalid parameters: expected 9 bottle counts, got 1.\n");
+        
+        // Names are escaped as JSON strings.
+        let options = BinsOptions {
+            colors: ColorScheme::new(['"', '\\', '\n']).unwrap(),
+            ..options
+        };
+        let mut out = Vec::new();
+        Bins::run_with_options(&mut "1 1 1 1 1 1 1 1 1".as_bytes(), &mut out, &mut err, options).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "{\"arrangement\":\"\\u000a\\\"\\\\\",\"moves\":6}\n");
+    }
+    
+    #[test]
+    fn bins_run_batch_matches_run_with_options() {
+        let mut input = String::new();
+        for n in 0..1000u64 {
+            match n % 7 {
+                0 => input.push_str("1 2 3\n"),
+                _ => input.push_str(&format!("{} {} {} {} {} {} {} {} {}\n",
+                                             n, n * 7 % 13, n % 5, n % 3, n * n % 17, 2, n % 11, n, 1)),
             }
         }
         
-        // This should never happen.
-        (output, 0)
+        for &judge in [false, true].iter() {
+            for &input in [input.as_str(), "", "quit\n", "1 1 1 1 1 1 1 1 1\n\n1 2 3 4 5 6 7 8 9\n"].iter() {
+                let options = BinsOptions { judge, ..BinsOptions::default() };
+                let (mut out, mut err) = (Vec::new(), Vec::new());
+                Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+                
+                for &threads in [0, 1, 3, 64].iter() {
+                    for &chunked in [false, true].iter() {
+                        let options = BinsOptions { threads, chunked, ..options.clone() };
+                        let (mut batch_out, mut batch_err) = (Vec::new(), Vec::new());
+                        Bins::run_batch(&mut input.as_bytes(), &mut batch_out, &mut batch_err, options).unwrap();
+                        
+                        assert_eq!(batch_out, out, "{} threads, chunked: {}", threads, chunked);
+                        assert_eq!(batch_err, err, "{} threads, chunked: {}", threads, chunked);
+                    }
+                }
+            }
+        }
     }
     
-    /// Calculate the `CBG` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn cbg(&self) -> (String, usize) {
-        let output = String::from("CBG");
+    #[test]
+    fn bins_run_batch_in_blocks() {
+        let mut input = String::new();
+        for n in 0..200u64 {
+            match n % 7 {
+                6 => input.push('\n'),
+                _ => input.push_str(&format!("{} {} {} {} {} {} {} {} {}\n", n, n % 13, 1, 2, n % 5, 3, n, 4, n % 3)),
+            }
+        }
+        input.push_str("quit\n1 1 1 1 1 1 1 1 1");
         
-        //                    C
-        if let Bin::One(a, b, _) = self.bin1 {
-            //              B
-            if let Bin::Two(_, e, f) = self.bin2 {
-                //                   G
-                if let Bin::Three(g, _, i) = self.bin3 {
-                    return (output, a + g + b + e + f + i);
+        for &(judge, skip_blank_lines) in [(false, false), (false, true), (true, false)].iter() {
+            let options = BinsOptions { judge, skip_blank_lines, threads: 3, ..BinsOptions::default() };
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            Bins::run_with_options(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+            
+            // Blocks of a line or more, read from buffers smaller and
+            // larger than a line.
+            for &block_bytes in [1, 10, 100, 10_000].iter() {
+                for &capacity in [1, 7, 64].iter() {
+                    for &chunked in [false, true].iter() {
+                        let options = BinsOptions { chunked, ..options.clone() };
+                        let mut reader = io::BufReader::with_capacity(capacity, input.as_bytes());
+                        let (mut batch_out, mut batch_err) = (Vec::new(), Vec::new());
+                        Bins::run_blocks(&mut reader, &mut batch_out, &mut batch_err, options, block_bytes).unwrap();
+                        
+                        let context = (judge, skip_blank_lines, block_bytes, capacity, chunked);
+                        assert_eq!(batch_out, out, "{:?}", context);
+                        assert_eq!(batch_err, err, "{:?}", context);
+                    }
                 }
             }
         }
+    }
+    
+    #[test]
+    fn bins_run_batch_chunked() {
+        let options = BinsOptions {
+            chunked: true,
+            skip_blank_lines: true,
+            ..BinsOptions::default()
+        };
         
-        // This should never happen.
-        (output, 0)
+        // The last line needs no line ending.
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let input = "1 2 3 4 5 6 7 8 9\r\n\r\n   \n5 10 5 20 10 5 10 20 10";
+        Bins::run_batch(&mut input.as_bytes(), &mut out, &mut err, options.clone()).unwrap();
+        assert_eq!(out, b"BCG 30\nCBG 50\n");
+        assert!(err.is_empty());
+        
+        // Nothing of a block is written if it isn't UTF-8.
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let input = b"1 2 3 4 5 6 7 8 9\n\xff\n";
+        let error = Bins::run_batch(&mut &input[..], &mut out, &mut err, options).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(out.is_empty() && err.is_empty());
     }
     
-    /// Calculate the `CGB` bin arrangement. Finds the number of
-    /// bottles that would need to be moved for this arrangement.
-    /// Returns a 2-tuple containing a String and the count of
-    /// bottle moves.
-    fn cgb(&self) -> (String, usize) {
-        let output = String::from("CGB");
+    #[test]
+    fn bins_constructors() {
+        let bins = Bins::try_from([1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+        assert_eq!(bins, Bins::from_rows([[1, 2, 3], [4, 5, 6], [7, 8, 9]]).unwrap());
+        assert_eq!(bins, Bins::ok([1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(bins.calculate(&BinsOptions::default()), BinResult::new(Arrangement::BCG, 30));
         
-        //                    C
-        if let Bin::One(a, b, _) = self.bin1 {
-            //                 G
-            if let Bin::Two(d, _, f) = self.bin2 {
-                //                B
-                if let Bin::Three(_, h, i) = self.bin3 {
-                    return (output, a + d + b + h + f + i);
-                }
+        assert!(Bins::from_rows([[0, 0, MAX_BOTTLES], [0; 3], [0; 3]]).is_ok());
+        assert_eq!(
+            Bins::try_from([1 << 30, 0, 0, 0, 1 << 30, 0, 0, 0, 1]),
+            Err(TooManyBottles(Some(MAX_BOTTLES + 1)))
+        );
+        assert_eq!(
+            Bins::from_rows([[u64::MAX, 0, 0], [0, 1, 0], [0; 3]]),
+            Err(TooManyBottles(None))
+        );
+        assert_eq!(TooManyBottles(None).to_string(), "Error! The bins hold more than 18446744073709551615 bottles.");
+    }
+    
+    #[test]
+    fn bins_parse() {
+        let options = BinsOptions::default();
+        let parse = |input| Bins::parse(input, &options);
+        
+        assert_eq!(parse("1 2 3 4 5 6 7 8 9\n"), ParsedLine::Bins([1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(parse(" 0\t0 0 0 0 0 0 0 18446744073709551615"),
+                   ParsedLine::Bins([0, 0, 0, 0, 0, 0, 0, 0, u64::MAX]));
+        
+        for input in ["\n", "", "q\n", " QUIT "].iter() {
+            assert_eq!(parse(input), ParsedLine::Quit);
+        }
+        for input in ["h\n", " Help "].iter() {
+            assert_eq!(parse(input), ParsedLine::Help);
+        }
+        
+        assert_eq!(parse("1 2 3 4 5 6 7 8"), ParsedLine::Err(BinsError::WrongFieldCount { got: 8 }));
+        assert_eq!(parse("1 2 3 4 5 6 7 8 9 10"), ParsedLine::Err(BinsError::WrongFieldCount { got: 10 }));
+        assert_eq!(parse("1 2 x 4 5 6 7 8"), ParsedLine::Err(BinsError::WrongFieldCount { got: 8 }));
+        assert_eq!(
+            parse("1 2 3 4 5 6 7 8 -9"),
+            ParsedLine::Err(BinsError::InvalidInteger { index: 8, token: String::from("-9") })
+        );
+        assert_eq!(
+            parse("1 2 3 4 x 6 7 y 9"),
+            ParsedLine::Err(BinsError::InvalidInteger { index: 4, token: String::from("x") })
+        );
+        assert_eq!(
+            BinsError::InvalidInteger { index: 4, token: String::from("x") }.to_string(),
+            "Error! Invalid parameters: bottle count 5, `x`, is not an unsigned integer."
+        );
+    }
+    
+    #[test]
+    fn bins_parse_csv() {
+        let options = BinsOptions {
+            format: InputFormat::Csv,
+            ..BinsOptions::default()
+        };
+        let parse = |input| Bins::parse(input, &options);
+        
+        assert_eq!(parse("1,2,3,4,5,6,7,8,9\r\n"), ParsedLine::Bins([1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(parse(" 1, 2 ,3,4,5,6,7,8, 9 "), ParsedLine::Bins([1, 2, 3, 4, 5, 6, 7, 8, 9]));
+        assert_eq!(parse("quit"), ParsedLine::Quit);
+        
+        let wrong_field_count = |got| ParsedLine::Err(BinsError::WrongFieldCount { got });
+        let invalid_integer = |index, token: &str| {
+            ParsedLine::Err(BinsError::InvalidInteger { index, token: token.to_string() })
+        };
+        assert_eq!(parse("1 2 3 4 5 6 7 8 9"), wrong_field_count(1));
+        assert_eq!(parse("1,2,3,4,5,6,7,8"), wrong_field_count(8));
+        assert_eq!(parse("1,2,3,4,5,6,7,8,9,"), wrong_field_count(10));
+        assert_eq!(parse("1,2,3,4,,6,7,8,9"), invalid_integer(4, ""));
+        assert_eq!(parse("brown1,green1,clear1,brown2,green2,clear2,brown3,green3,clear3"),
+                   invalid_integer(0, "brown1"));
+        
+        // Whitespace separated input doesn't accept commas.
+        assert_eq!(Bins::parse("1,2,3,4,5,6,7,8,9", &BinsOptions::default()), wrong_field_count(1));
+    }
+    
+    #[test]
+    fn bins_parse_judge_mode() {
+        let options = BinsOptions {
+            judge: true,
+            ..BinsOptions::default()
+        };
+        
+        for &(input, got) in [("\n", 0), ("", 0), ("q\n", 1), (" QUIT ", 1), ("help", 1)].iter() {
+            assert_eq!(Bins::parse(input, &options), ParsedLine::Err(BinsError::WrongFieldCount { got }));
+        }
+        
+        // Only the end of the input stops the judge.
+        let mut input = "1 1 1 1 1 1 1 1 1\nq\n1 2 3 4 5 6 7 8 9".as_bytes();
+        let mut out = Vec::new();
+        Bins::run_with_options(&mut input, &mut out, &mut Vec::new(), options).unwrap();
+        assert_eq!(out, b"BCG 6\nBCG 30\n");
+    }
+    
+    #[test]
+    fn ranked_arrangements_agree_with_solve() {
+        for line in include_str!("../input.txt").lines() {
+            let [a, b, c, d, e, f, g, h, i] = match Bins::parse(line, &BinsOptions::default()) {
+                ParsedLine::Bins(counts) => counts,
+                parsed => panic!("`{}` parsed into {:?}", line, parsed),
+            };
+            let bins = [[a, b, c], [d, e, f], [g, h, i]];
+            let ranked = ranked_arrangements(bins);
+            
+            let best = solve(bins);
+            assert_eq!(ranked[0], (best.arrangement, best.moves), "{}", line);
+            for &(arrangement, moves) in ranked.iter() {
+                let bins = Bins::ok([a, b, c, d, e, f, g, h, i]);
+                assert_eq!(bins.moves_for(arrangement), moves);
+                assert_eq!(bins.moves_per_bin(arrangement).iter().sum::<u64>(), moves);
             }
+            assert!(ranked.windows(2).all(|pair| pair[0].1 < pair[1].1 ||
+                                                 (pair[0].1 == pair[1].1 && pair[0].0 < pair[1].0)));
         }
         
-        // This should never happen.
-        (output, 0)
+        let ranked = ranked_arrangements([[1, 1, 1], [1, 1, 1], [1, 1, 1]]);
+        assert_eq!(ranked.map(|(arrangement, _)| arrangement), Arrangement::ALL);
+    }
+    
+    #[test]
+    fn solve_extreme_counts() {
+        let max = (1 << 31) - 1;
+        assert_eq!(solve([[max; 3]; 3]), BinResult::new(Arrangement::BCG, 6 * max));
+        assert_eq!(solve([[max, 0, 0], [0, 0, max], [0, max, 0]]), BinResult::new(Arrangement::BCG, 0));
+        assert_eq!(ranked_arrangements([[max, 0, 0], [0, 0, max], [0, max, 0]])[5].1, 3 * max);
+        
+        // Counts that don't fit in 32 bits.
+        let mut out = Vec::new();
+        let input = "4294967296 0 0 0 4294967296 0 0 0 4294967296\n18446744073709551615 1 0 0 0 0 0 0 0\n";
+        Bins::run_with(&mut input.as_bytes(), &mut out, &mut Vec::new()).unwrap();
+        assert_eq!(out, b"BGC 0\nBCG 1\n");
+    }
+    
+    #[test]
+    fn solve_ties_are_alphabetical() {
+        assert_eq!(solve([[1, 1, 1], [1, 1, 1], [1, 1, 1]]), BinResult::new(Arrangement::BCG, 6));
+        assert_eq!(solve([[0; 3]; 3]), BinResult::new(Arrangement::BCG, 0));
+        assert_eq!(solve([[0, 5, 0], [5, 0, 0], [0, 0, 5]]), BinResult::new(Arrangement::GBC, 0));
     }
 }
@@ -1,22 +1,119 @@
-#![allow(unused_imports)]
-#![allow(unused_must_use)]
-
+use std::env;
 use std::io;
 use std::fs::File;
+use std::path::PathBuf;
+use std::process;
 
-use rust::Bins;
+use rust::{Bins, BinsOptions, ColorScheme, InputFormat, OutputFormat, TieBreak};
 
 fn main() -> std::io::Result<()> {
+    let mut options = BinsOptions::default();
+    let mut batch = false;
+    let mut input = None;
+    let mut output = None;
+    let mut args = env::args().skip(1);
+    
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // Process every line until the input ends, as the judge
+            // does, instead of stopping at `quit` or an empty line.
+            "--judge" => options.judge = true,
+            
+            // Skip blank lines between cases instead of quitting.
+            "--skip-blank-lines" => options.skip_blank_lines = true,
+            
+            // Echo each case's bins, as they were understood, before
+            // its answer.
+            "--verbose" => options.verbose = true,
+            
+            // Read comma-separated values, e.g., exported from a
+            // spreadsheet, instead of whitespace separated ones.
+            "--csv" => options.format = InputFormat::Csv,
+            
+            // Write each result as a JSON object, for other programs
+            // to read.
+            "--json" => options.output = OutputFormat::JsonLines,
+            
+            // Break ties by choosing the alphabetically last
+            // arrangement instead of the first.
+            "--reverse-ties" => options.tie_break = TieBreak::Reverse,
+            
+            // Read the input a few megabytes at a time, solving each
+            // block on every CPU, for large input files.
+            "--batch" => batch = true,
+            
+            // As `--batch`, but read the input in large chunks, for
+            // input files of millions of lines.
+            "--chunked" => {
+                batch = true;
+                options.chunked = true;
+            },
+            
+            // Name Brown, Green, and Clear by three other characters,
+            // e.g., `--colors MVT`.
+            "--colors" => {
+                let labels: Vec<char> = args.next().unwrap_or_default().chars().collect();
+                
+                let colors = match labels.as_slice() {
+                    &[brown, green, clear] => ColorScheme::new([brown, green, clear]),
+                    _ => None,
+                };
+                
+                options.colors = colors.unwrap_or_else(|| {
+                    eprintln!("Error! `--colors` needs three different characters.");
+                    process::exit(2);
+                });
+            },
+            
+            // Anything else that isn't a flag is the input file, then
+            // the output file.
+            _ if !arg.starts_with("--") && input.is_none() => {
+                input = Some(PathBuf::from(arg));
+            },
+            _ if !arg.starts_with("--") && output.is_none() => {
+                output = Some(PathBuf::from(arg));
+            },
+            
+            _ => {
+                eprintln!("Error! Unknown argument `{}`, expected `--judge`, `--skip-blank-lines`, `--verbose`, `--csv`, `--json`, `--reverse-ties`, `--batch`, `--chunked`, `--colors <BGC>`, or an input file and output file.", arg);
+                process::exit(2);
+            },
+        }
+    }
+    
+    // Read the bins from the input file if one was given, else from
+    // `io::stdin`.
     let stdin = io::stdin();
-    let mut _reader = stdin.lock();
+    let mut reader: Box<dyn io::BufRead> = match input {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(io::BufReader::new(file)),
+            Err(error) => {
+                eprintln!("Error! Cannot open `{}`: {}", path.display(), error);
+                process::exit(1);
+            },
+        },
+        None => Box::new(stdin.lock()),
+    };
     
-    // Uncomment these two lines to read the commands
-    // from a file called "input.txt". TODO: pass in file
-    // as parameters to the execution of this program.
-    //let fh = File::open("input.txt")?;
-    //let mut _reader = io::BufReader::new(fh);
+    // Write the results into the output file if one was given, else
+    // onto `io::stdout`.
+    let mut writer: Box<dyn io::Write> = match output {
+        Some(path) => match File::create(&path) {
+            Ok(file) => Box::new(io::BufWriter::new(file)),
+            Err(error) => {
+                eprintln!("Error! Cannot create `{}`: {}", path.display(), error);
+                process::exit(1);
+            },
+        },
+        None => Box::new(io::stdout()),
+    };
     
-    Bins::run(&mut _reader);
+    if batch {
+        Bins::run_batch(&mut reader, &mut writer, &mut io::stderr(), options)?;
+    }
+    else {
+        Bins::run_with_options(&mut reader, &mut writer, &mut io::stderr(), options)?;
+    }
     
     Ok(())
 }
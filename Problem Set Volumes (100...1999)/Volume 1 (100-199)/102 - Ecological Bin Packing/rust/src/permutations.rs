@@ -0,0 +1,126 @@
+//! `permutations` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Enumerating the permutations of a handful of items, e.g., the
+//! ways colors can be assigned to bins. Only the standard library is
+//! used, so other problems can copy or reuse this as it is.
+
+/// `Permutations` struct. An iterator over every permutation of a
+/// slice, returned by `permutations_of()`.
+#[derive(Debug, Clone)]
+pub struct Permutations<'a, T> {
+    /// The items being permuted.
+    items: &'a [T],
+    
+    /// The positions in `items` of the next permutation, or `None`
+    /// once every permutation has been returned.
+    positions: Option<Vec<usize>>,
+}
+
+impl<'a, T: Clone> Iterator for Permutations<'a, T> {
+    type Item = Vec<T>;
+    
+    fn next(&mut self) -> Option<Vec<T>> {
+        let items = self.items;
+        let positions = self.positions.as_mut()?;
+        let permutation = positions.iter().map(|&position| items[position].clone()).collect();
+        
+        if !next_permutation(positions) {
+            self.positions = None;
+        }
+        
+        Some(permutation)
+    }
+}
+
+/// Return an iterator over every permutation of `items`, starting
+/// with `items` as they are. The permutations are in lexicographic
+/// order of the items' positions in `items`, so if `items` is sorted,
+/// so are the permutations. There are `n!` of them for `n` items,
+/// including a single empty permutation if `items` is empty.
+///
+/// # Example
+///
+/// ```
+/// use rust::permutations::permutations_of;
+///
+/// let permutations: Vec<String> = permutations_of(&['B', 'G', 'C'])
+///     .map(|permutation| permutation.into_iter().collect())
+///     .collect();
+///
+/// assert_eq!(permutations, ["BGC", "BCG", "GBC", "GCB", "CBG", "CGB"]);
+/// ```
+pub fn permutations_of<T: Clone>(items: &[T]) -> Permutations<'_, T> {
+    Permutations {
+        items,
+        positions: Some((0..items.len()).collect()),
+    }
+}
+
+/// Rearrange `items` into the next permutation in lexicographic
+/// order. Return `false`, leaving `items` untouched, if it is already
+/// the last permutation, i.e., in decreasing order.
+///
+/// # Example
+///
+/// ```
+/// use rust::permutations::next_permutation;
+///
+/// let mut items = [1, 3, 2];
+///
+/// assert!(next_permutation(&mut items));
+/// assert_eq!(items, [2, 1, 3]);
+///
+/// let mut items = [3, 2, 1];
+///
+/// assert!(!next_permutation(&mut items));
+/// assert_eq!(items, [3, 2, 1]);
+/// ```
+pub fn next_permutation<T: Ord>(items: &mut [T]) -> bool {
+    // Find the last item that is smaller than the one after it.
+    let pivot = match (1..items.len()).rev().find(|&i| items[i - 1] < items[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+    
+    // Swap it with the last item that is larger than it, then put
+    // everything after it in increasing order.
+    let successor = (pivot + 1..items.len())
+        .rev()
+        .find(|&i| items[i] > items[pivot])
+        .expect("the item after the pivot is larger than it");
+    items.swap(pivot, successor);
+    items[pivot + 1..].reverse();
+    
+    true
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn permutations_count() {
+        assert_eq!(permutations_of(&[0; 0]).collect::<Vec<_>>(), vec![Vec::<i32>::new()]);
+        assert_eq!(permutations_of(&[7]).collect::<Vec<_>>(), vec![vec![7]]);
+        assert_eq!(permutations_of(&[1, 2, 3, 4, 5]).count(), 120);
+        
+        // Equal items are still told apart by their positions.
+        assert_eq!(permutations_of(&[1, 1]).collect::<Vec<_>>(), vec![vec![1, 1], vec![1, 1]]);
+    }
+    
+    #[test]
+    fn permutations_are_sorted_and_distinct() {
+        let permutations: Vec<Vec<u8>> = permutations_of(&[1, 2, 3, 4]).collect();
+        
+        assert!(permutations.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(permutations.last(), Some(&vec![4, 3, 2, 1]));
+    }
+}
@@ -0,0 +1,212 @@
+//! `solver` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! A solver for any number of bins and colors, for variants of the
+//! problem with more than three of each. `solve()` solves the three
+//! bin, three color case on its own, trying the six arrangements
+//! without allocating, but agrees with `solve_matrix()` on it.
+
+use crate::permutations::permutations_of;
+
+/// `Assignment` struct. Which color of glass ends up in each bin, and
+/// how many bottles have to be moved for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Assignment {
+    /// `columns[bin]` is the column, in the input, of the color that
+    /// ends up in bin `bin`.
+    pub columns: Vec<usize>,
+    
+    /// The number of bottles that have to be moved.
+    pub moves: u64,
+}
+
+/// Return the assignment of colors to bins that moves the fewest
+/// bottles. `bins[bin][column]` is the number of bottles of the color
+/// in column `column` in bin `bin`, and `labels[column]` names that
+/// color. If several assignments move the fewest bottles, the one
+/// whose labels, read from the first bin to the last, sort first is
+/// returned.
+///
+/// Every permutation of the colors is tried, so this takes time
+/// proportional to `n!` for `n` bins.
+///
+/// Returns `None` if `bins` is not square, or if `labels` doesn't
+/// hold one label per color.
+///
+/// # Example
+///
+/// ```
+/// use rust::solver;
+///
+/// let bins = [
+///     [0, 3, 1, 0],
+///     [2, 0, 0, 0],
+///     [0, 0, 0, 4],
+///     [0, 0, 5, 1],
+/// ];
+/// let assignment = solver::solve_matrix(&bins, &['A', 'B', 'C', 'D']).unwrap();
+///
+/// assert_eq!(assignment.columns, vec![1, 0, 3, 2]);
+/// assert_eq!(assignment.moves, 2);
+/// ```
+pub fn solve_matrix<B, L>(bins: &[B], labels: &[L]) -> Option<Assignment>
+where
+    B: AsRef<[u64]>,
+    L: Ord,
+{
+    let n = bins.len();
+    if labels.len() != n || bins.iter().any(|bin| bin.as_ref().len() != n) {
+        return None;
+    }
+    
+    // Trying the permutations of the columns in the order of their
+    // labels, and only keeping strictly better ones, returns the
+    // first of several equal minimums.
+    let mut columns: Vec<usize> = (0..n).collect();
+    columns.sort_by(|&a, &b| labels[a].cmp(&labels[b]));
+    
+    let mut best: Option<Assignment> = None;
+    for columns in permutations_of(&columns) {
+        let moves = moves(bins, &columns);
+        
+        if best.as_ref().is_none_or(|best| moves < best.moves) {
+            best = Some(Assignment { columns, moves });
+        }
+    }
+    
+    best
+}
+
+/// Return how many bottles have to be moved to put the color in
+/// column `columns[bin]` in each bin `bin`, i.e., every bottle of any
+/// other color. `bins` is as for `solve_matrix()`, and `columns` must
+/// hold a column for each bin.
+///
+/// The count saturates at `u64::MAX` rather than overflowing, which
+/// only the most extreme inputs can reach: the problem's counts are
+/// all below `2^31`.
+///
+/// # Example
+///
+/// ```
+/// use rust::solver;
+///
+/// let bins = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+/// assert_eq!(solver::moves(&bins, &[1, 0, 2]), 30);
+///
+/// let bins = [[u64::MAX, 1, 0], [0, 0, 0], [0, 0, 0]];
+/// assert_eq!(solver::moves(&bins, &[1, 0, 2]), u64::MAX);
+/// ```
+pub fn moves<B: AsRef<[u64]>>(bins: &[B], columns: &[usize]) -> u64 {
+    bins.iter()
+        .zip(columns)
+        .map(|(bin, &column)| moves_out_of(bin.as_ref(), column))
+        .fold(0, u64::saturating_add)
+}
+
+/// Like `moves()`, but return how many bottles have to be moved out
+/// of each bin, rather than their total.
+///
+/// # Example
+///
+/// ```
+/// use rust::solver;
+///
+/// let bins = [[1, 2, 3], [4, 5, 6], [7, 8, 9]];
+/// assert_eq!(solver::moves_per_bin(&bins, &[1, 0, 2]), vec![4, 11, 15]);
+/// ```
+pub fn moves_per_bin<B: AsRef<[u64]>>(bins: &[B], columns: &[usize]) -> Vec<u64> {
+    bins.iter()
+        .zip(columns)
+        .map(|(bin, &column)| moves_out_of(bin.as_ref(), column))
+        .collect()
+}
+
+//
+// Private functions.
+//
+
+/// Return how many bottles have to be moved out of `bin` to leave
+/// only the color in column `column`, saturating at `u64::MAX`.
+fn moves_out_of(bin: &[u64], column: usize) -> u64 {
+    bin.iter()
+        .enumerate()
+        .filter(|&(other, _)| other != column)
+        .map(|(_, &count)| count)
+        .fold(0, u64::saturating_add)
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solve;
+    
+    #[test]
+    fn solver_agrees_with_solve() {
+        // Every case of counts below 3, so that ties are common, and
+        // a few of huge counts.
+        let mut cases: Vec<[[u64; 3]; 3]> = (0..3u64.pow(9))
+            .map(|n| {
+                let count = |i: u32| n / 3u64.pow(i) % 3;
+                [[count(0), count(1), count(2)], [count(3), count(4), count(5)], [count(6), count(7), count(8)]]
+            })
+            .collect();
+        cases.push([[(1 << 31) - 1; 3]; 3]);
+        cases.push([[0, u64::MAX, 1], [2, 0, u64::MAX], [u64::MAX, 3, 0]]);
+        
+        for bins in cases.iter() {
+            let solved = solve(*bins);
+            let assignment = solve_matrix(bins, &['B', 'G', 'C']).unwrap();
+            
+            assert_eq!(assignment.columns, solved.arrangement.columns(), "{:?}", bins);
+            assert_eq!(assignment.moves, solved.moves, "{:?}", bins);
+        }
+    }
+    
+    #[test]
+    fn solver_rejects_mismatched_sizes() {
+        assert_eq!(solve_matrix(&[[1, 2], [3, 4]], &['B', 'G', 'C']), None);
+        assert_eq!(solve_matrix(&[vec![1, 2], vec![3]], &['B', 'G']), None);
+        assert_eq!(
+            solve_matrix::<[u64; 0], char>(&[], &[]),
+            Some(Assignment { columns: vec![], moves: 0 })
+        );
+    }
+    
+    #[test]
+    fn solver_breaks_ties_by_label() {
+        let bins = [[1, 1], [1, 1]];
+        assert_eq!(solve_matrix(&bins, &['A', 'B']).unwrap().columns, vec![0, 1]);
+        assert_eq!(solve_matrix(&bins, &['B', 'A']).unwrap().columns, vec![1, 0]);
+    }
+    
+    #[test]
+    fn solver_extreme_counts() {
+        // The largest counts the problem allows can't overflow.
+        let max = (1 << 31) - 1;
+        let bins = [[max; 3]; 3];
+        assert_eq!(moves(&bins, &[0, 1, 2]), 6 * max);
+        assert_eq!(solve_matrix(&bins, &['B', 'G', 'C']).unwrap().moves, 6 * max);
+        
+        // Larger ones saturate. When every assignment does, the one
+        // whose labels sort first is returned.
+        let bins = [[0, u64::MAX, u64::MAX], [u64::MAX, 1, u64::MAX], [u64::MAX, u64::MAX, 2]];
+        assert_eq!(moves(&bins, &[1, 0, 2]), u64::MAX);
+        assert_eq!(
+            solve_matrix(&bins, &['B', 'G', 'C']),
+            Some(Assignment { columns: vec![0, 2, 1], moves: u64::MAX })
+        );
+        
+        // Only the bottles that move are counted, so huge counts that
+        // stay put don't saturate.
+        let bins = [[u64::MAX, 0], [0, u64::MAX]];
+        assert_eq!(solve_matrix(&bins, &[0, 1]), Some(Assignment { columns: vec![0, 1], moves: 0 }));
+    }
+}
@@ -0,0 +1,92 @@
+//! `stats` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Figures about a case beyond its answer, for analysing many cases
+//! or checking the answer from another angle.
+
+use crate::ranked_arrangements;
+
+/// `BinsStats` struct. How the bottles of a case are spread, and how
+/// much the choice of arrangement matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinsStats {
+    /// The number of bottles in all three bins.
+    pub total: u64,
+    
+    /// The number of bottles the best arrangement leaves where they
+    /// are, i.e., `total - best`.
+    pub in_place: u64,
+    
+    /// The number of bottles the best arrangement moves.
+    pub best: u64,
+    
+    /// The number of bottles the worst arrangement moves.
+    pub worst: u64,
+}
+
+impl BinsStats {
+    /// Return the stats of `bins`, which holds the number of Brown,
+    /// Green, and Clear bottles, in that order, in each of the three
+    /// bins, as for `solve()`. Counts saturate at `u64::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::BinsStats;
+    ///
+    /// let stats = BinsStats::new([[5, 10, 5], [20, 10, 5], [10, 20, 10]]);
+    ///
+    /// assert_eq!(stats.total, 95);
+    /// assert_eq!(stats.in_place, 45);
+    /// assert_eq!((stats.best, stats.worst), (50, 70));
+    /// assert_eq!(stats.spread(), 20);
+    /// ```
+    pub fn new(bins: [[u64; 3]; 3]) -> BinsStats {
+        let total = bins.iter().flatten().fold(0, |total: u64, &count| total.saturating_add(count));
+        let ranked = ranked_arrangements(bins);
+        let (best, worst) = (ranked[0].1, ranked[5].1);
+        
+        BinsStats {
+            total,
+            in_place: total.saturating_sub(best),
+            best,
+            worst,
+        }
+    }
+    
+    /// Return how many more bottles the worst arrangement moves than
+    /// the best one.
+    pub fn spread(&self) -> u64 {
+        self.worst - self.best
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn stats_edge_cases() {
+        assert_eq!(BinsStats::new([[0; 3]; 3]), BinsStats { total: 0, in_place: 0, best: 0, worst: 0 });
+        
+        // Every arrangement moves the same bottles.
+        let stats = BinsStats::new([[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+        assert_eq!(stats, BinsStats { total: 45, in_place: 15, best: 30, worst: 30 });
+        assert_eq!(stats.spread(), 0);
+        
+        // Only the diagonal is filled, so the best arrangement moves
+        // nothing, and the worst moves everything.
+        let stats = BinsStats::new([[1, 0, 0], [0, 2, 0], [0, 0, 3]]);
+        assert_eq!(stats, BinsStats { total: 6, in_place: 6, best: 0, worst: 6 });
+        
+        let stats = BinsStats::new([[u64::MAX; 3]; 3]);
+        assert_eq!((stats.total, stats.in_place, stats.spread()), (u64::MAX, 0, 0));
+    }
+}
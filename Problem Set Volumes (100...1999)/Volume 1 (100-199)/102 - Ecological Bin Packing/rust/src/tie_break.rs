@@ -0,0 +1,60 @@
+//! `tie_break` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Which arrangement is chosen when several move the fewest bottles.
+//! The problem chooses the alphabetically first, but variants of it
+//! may not.
+
+use std::cmp::Ordering;
+
+use crate::arrangement::Arrangement;
+use crate::colors::ColorScheme;
+
+/// `TieBreak` enum. How to choose between arrangements that move the
+/// same number of bottles: the one that compares as `Ordering::Less`
+/// is chosen.
+#[derive(Debug, Clone, Copy, Default)]
+pub enum TieBreak {
+    /// The arrangement whose name sorts first, as the problem
+    /// requires.
+    #[default]
+    Alphabetical,
+    
+    /// The arrangement whose name sorts last.
+    Reverse,
+    
+    /// The arrangement the comparator orders first.
+    Custom(fn(Arrangement, Arrangement) -> Ordering),
+}
+
+impl TieBreak {
+    /// Compare two arrangements by this policy. Names are those of
+    /// `colors`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use rust::{Arrangement, ColorScheme, TieBreak};
+    ///
+    /// let colors = ColorScheme::default();
+    /// let (bcg, gbc) = (Arrangement::BCG, Arrangement::GBC);
+    ///
+    /// assert_eq!(TieBreak::Alphabetical.compare(&colors, bcg, gbc), Ordering::Less);
+    /// assert_eq!(TieBreak::Reverse.compare(&colors, bcg, gbc), Ordering::Greater);
+    /// ```
+    pub fn compare(&self, colors: &ColorScheme, a: Arrangement, b: Arrangement) -> Ordering {
+        let labels = colors.labels();
+        let name = |arrangement: Arrangement| arrangement.columns().map(|column| labels[column]);
+        
+        match self {
+            TieBreak::Alphabetical => name(a).cmp(&name(b)),
+            TieBreak::Reverse => name(b).cmp(&name(a)),
+            TieBreak::Custom(compare) => compare(a, b),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! `verify` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! A second, slower way to solve a case, by actually moving the
+//! bottles around for every arrangement, to check `solve()` against.
+//! If the two ever disagree, one of them is wrong.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::{solve, Arrangement, BinResult};
+
+/// `Mismatch` struct. Returned by `verify()` when `solve()` and
+/// `brute_force()` disagree on a case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    /// What `solve()` returned.
+    pub solved: BinResult,
+    
+    /// What `brute_force()` returned.
+    pub simulated: BinResult,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Error! Solved `{}` but simulated `{}`.", self.solved, self.simulated)
+    }
+}
+
+impl Error for Mismatch {}
+
+/// Move every bottle in `bins` that isn't in the bin `arrangement`
+/// assigns its color to into that bin, a pile at a time, and return
+/// how many bottles were moved. `bins` is as for `solve()`. The count
+/// saturates at `u64::MAX`.
+///
+/// # Example
+///
+/// ```
+/// use rust::Arrangement;
+/// use rust::verify::simulate;
+///
+/// assert_eq!(simulate([[5, 10, 5], [20, 10, 5], [10, 20, 10]], Arrangement::CBG), 50);
+/// ```
+pub fn simulate(bins: [[u64; 3]; 3], arrangement: Arrangement) -> u64 {
+    let columns = arrangement.columns();
+    
+    // The bin each color ends up in.
+    let mut home = [0; 3];
+    for (bin, &column) in columns.iter().enumerate() {
+        home[column] = bin;
+    }
+    
+    let mut bins = bins;
+    let mut moved: u64 = 0;
+    for bin in 0..3 {
+        for color in 0..3 {
+            if home[color] == bin {
+                continue;
+            }
+            
+            let pile = bins[bin][color];
+            bins[bin][color] = 0;
+            bins[home[color]][color] = bins[home[color]][color].saturating_add(pile);
+            moved = moved.saturating_add(pile);
+        }
+    }
+    
+    // Every bin holds only its own color now.
+    for (bin, &column) in columns.iter().enumerate() {
+        assert!(
+            (0..3).all(|color| color == column || bins[bin][color] == 0),
+            "bin {} still holds other colors after simulating {}", bin + 1, arrangement
+        );
+    }
+    
+    moved
+}
+
+/// Solve a case by simulating every arrangement with `simulate()`,
+/// keeping the alphabetically first of those that move the fewest
+/// bottles. `bins` is as for `solve()`.
+pub fn brute_force(bins: [[u64; 3]; 3]) -> BinResult {
+    let mut best: Option<BinResult> = None;
+    for &arrangement in Arrangement::ALL.iter() {
+        let moves = simulate(bins, arrangement);
+        
+        if best.is_none_or(|best| moves < best.moves) {
+            best = Some(BinResult::new(arrangement, moves));
+        }
+    }
+    
+    best.expect("there are 6 arrangements")
+}
+
+/// Solve a case with both `solve()` and `brute_force()`, returning
+/// the result if they agree, else both results.
+///
+/// # Example
+///
+/// ```
+/// use rust::{Arrangement, BinResult};
+/// use rust::verify::verify;
+///
+/// assert_eq!(verify([[1, 2, 3], [4, 5, 6], [7, 8, 9]]), Ok(BinResult::new(Arrangement::BCG, 30)));
+/// ```
+pub fn verify(bins: [[u64; 3]; 3]) -> Result<BinResult, Mismatch> {
+    let solved = solve(bins);
+    let simulated = brute_force(bins);
+    
+    if solved == simulated {
+        Ok(solved)
+    }
+    else {
+        Err(Mismatch { solved, simulated })
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn verify_random_cases() {
+        // xorshift64, with small counts so that ties are common.
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state % 4
+        };
+        
+        for _ in 0..10_000 {
+            let bins = [[next(), next(), next()], [next(), next(), next()], [next(), next(), next()]];
+            assert_eq!(verify(bins).map(|_| ()), Ok(()), "{:?}", bins);
+        }
+        
+        let max = (1 << 31) - 1;
+        for bins in [[[max; 3]; 3], [[u64::MAX; 3]; 3], [[0, u64::MAX, 1], [2, 0, u64::MAX], [u64::MAX, 3, 0]]].iter() {
+            assert_eq!(verify(*bins).map(|_| ()), Ok(()), "{:?}", bins);
+        }
+    }
+    
+    #[test]
+    fn verify_reports_mismatches() {
+        let mismatch = Mismatch {
+            solved: BinResult::new(Arrangement::BCG, 30),
+            simulated: BinResult::new(Arrangement::GBC, 29),
+        };
+        
+        assert_eq!(mismatch.to_string(), "Error! Solved `BCG 30` but simulated `GBC 29`.");
+    }
+}
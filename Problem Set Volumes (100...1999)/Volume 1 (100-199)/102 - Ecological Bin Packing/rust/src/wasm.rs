@@ -0,0 +1,69 @@
+//! `wasm` module
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! `solve()` for JavaScript, so that a web page can solve a case as
+//! it is typed. Only built with the `wasm` feature, e.g., by
+//! `wasm-pack build -- --features wasm`. Answers and errors are the
+//! lines the program itself writes, e.g., `BCG 30`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{solve, Bins, BinsError, BinsOptions, ParsedLine};
+
+/// Solve the case held in `counts`, the number of Brown, Green, and
+/// Clear bottles, in that order, in each of the three bins, and
+/// return its answer, e.g., `BCG 30`. Throws the error message if
+/// there aren't exactly 9 counts.
+#[wasm_bindgen(js_name = solve)]
+pub fn solve_counts(counts: &[u32]) -> Result<String, String> {
+    match counts {
+        &[a, b, c, d, e, f, g, h, i] => {
+            let bins = [[a, b, c], [d, e, f], [g, h, i]].map(|bin| bin.map(u64::from));
+            
+            Ok(solve(bins).to_string())
+        },
+        _ => Err(BinsError::WrongFieldCount { got: counts.len() }.to_string()),
+    }
+}
+
+/// Solve the case held in `line`, 9 bottle counts separated by
+/// whitespace as in the program's input, and return its answer, e.g.,
+/// `BCG 30`. Throws the error message the program would write if the
+/// line isn't a case.
+#[wasm_bindgen(js_name = solveLine)]
+pub fn solve_line(line: &str) -> Result<String, String> {
+    let options = BinsOptions {
+        judge: true,
+        ..BinsOptions::default()
+    };
+    
+    match Bins::parse(line, &options) {
+        ParsedLine::Bins(counts) => Ok(Bins::ok(counts).calculate(&options).to_string()),
+        ParsedLine::Err(error) => Err(error.to_string()),
+        ParsedLine::Quit | ParsedLine::Help => unreachable!("judge mode neither quits nor helps"),
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn wasm_solve() {
+        assert_eq!(solve_counts(&[1, 2, 3, 4, 5, 6, 7, 8, 9]), Ok("BCG 30".to_string()));
+        assert_eq!(
+            solve_counts(&[1, 2, 3]),
+            Err("Error! Invalid parameters: expected 9 bottle counts, got 3.".to_string())
+        );
+        
+        assert_eq!(solve_line(" 5 10 5 20 10 5 10 20 10\n"), Ok("CBG 50".to_string()));
+        assert_eq!(solve_line(""), Err("Error! Invalid parameters: expected 9 bottle counts, got 0.".to_string()));
+    }
+}
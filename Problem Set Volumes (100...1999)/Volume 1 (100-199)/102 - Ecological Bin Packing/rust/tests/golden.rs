@@ -0,0 +1,77 @@
+//! `golden` tests
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-06
+//!
+//! Run every `tests/fixtures/*.in` file through `Bins::run_with()` and
+//! compare what is written onto `out` with the `.out` file of the same
+//! name, and what is written onto `err` with the `.err` file, if there
+//! is one, or nothing. To add a case, add its `.in` and `.out` files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rust::Bins;
+
+/// Return the paths of every `.in` file in `tests/fixtures`, sorted.
+fn fixtures() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures");
+    let mut inputs: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap_or_else(|error| panic!("Cannot read `{}`: {}", directory.display(), error))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "in"))
+        .collect();
+    inputs.sort();
+    
+    inputs
+}
+
+/// Return the first line where `actual` and `expected` differ, as a
+/// message naming `what`, or `None` if they are the same.
+fn diff(what: &Path, actual: &str, expected: &str) -> Option<String> {
+    if actual == expected {
+        return None;
+    }
+    
+    let mut actual_lines = actual.lines();
+    let mut expected_lines = expected.lines();
+    for line in 1.. {
+        match (actual_lines.next(), expected_lines.next()) {
+            (None, None) => break,
+            (a, e) if a == e => continue,
+            (a, e) => {
+                return Some(format!("{}, line {}:\n  expected: {:?}\n    actual: {:?}",
+                                    what.display(), line, e, a));
+            }
+        }
+    }
+    
+    // Only the line endings differ.
+    Some(format!("{}:\n  expected: {:?}\n    actual: {:?}", what.display(), expected, actual))
+}
+
+#[test]
+fn golden_fixtures() {
+    let inputs = fixtures();
+    assert!(!inputs.is_empty(), "there are no fixtures");
+    
+    let mut failures = Vec::new();
+    for input in inputs.iter() {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        Bins::run_with(&mut fs::read(input).unwrap().as_slice(), &mut out, &mut err).unwrap();
+        
+        let output = input.with_extension("out");
+        let expected_out = fs::read_to_string(&output)
+            .unwrap_or_else(|error| panic!("Cannot read `{}`: {}", output.display(), error));
+        let errors = input.with_extension("err");
+        let expected_err = fs::read_to_string(&errors).unwrap_or_default();
+        
+        failures.extend(diff(&output, &String::from_utf8_lossy(&out), &expected_out));
+        failures.extend(diff(&errors, &String::from_utf8_lossy(&err), &expected_err));
+    }
+    
+    assert!(failures.is_empty(), "{} of {} fixtures differ:\n{}",
+            failures.len(), inputs.len(), failures.join("\n"));
+}
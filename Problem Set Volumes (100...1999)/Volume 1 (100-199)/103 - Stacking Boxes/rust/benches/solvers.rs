@@ -0,0 +1,98 @@
+//! `solvers` benchmark
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-19
+//!
+//! Measure how fast each solver solves generated cases, for growing
+//! numbers of boxes and dimensions, to weigh `Solver::Dp`'s O(n²)
+//! against `Solver::Lis`'s O(n log n) on boxes of two dimensions,
+//! and see how much comparing many dimensions costs. `Solver::Lis`
+//! falls back to `Solver::Dp` for boxes of more than two dimensions,
+//! so it is only run on boxes of two. Run with `cargo bench`,
+//! optionally followed by part of a scenario's name, e.g., `graph` or
+//! `k=300`, to run only the scenarios matching it.
+
+use std::env;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rust::boxes::Solver;
+use rust::generator::{Case, Generator};
+
+/// The numbers of boxes of the cases.
+const BOXES: [usize; 3] = [30, 300, 3000];
+
+/// The numbers of dimensions of the cases.
+const DIMENSIONS: [usize; 3] = [2, 10, 50];
+
+/// About how many pairs of boxes each scenario compares, so that
+/// scenarios of few boxes solve many cases, and vice versa.
+const PAIRS: usize = 1_000_000;
+
+/// Return cases of `boxes` boxes of `dimensions` dimensions, the same
+/// ones on every run, with a nesting string of a tenth of the boxes
+/// planted in each.
+fn cases(boxes: usize, dimensions: usize) -> Vec<Case> {
+    let mut generator = Generator::new(0x2545_f491_4f6c_dd1d)
+        .boxes(boxes..=boxes)
+        .dimensions(dimensions..=dimensions)
+        .values(1..=1000)
+        .planted_chain(boxes / 10);
+    
+    (0..(PAIRS / (boxes * boxes)).max(1)).map(|_| generator.case()).collect()
+}
+
+/// Solve every case with `solver`, timing it, and print how long
+/// each case took.
+fn bench(name: &str, solver: Solver, cases: &[Case]) {
+    let mut best = Duration::from_secs(u64::MAX);
+    let mut length = 0;
+    
+    for _ in 0..5 {
+        let start = Instant::now();
+        length = cases.iter().map(|case| black_box(case.boxes.solve_with(black_box(solver))).length).sum();
+        best = best.min(start.elapsed());
+    }
+    
+    println!(
+        "{:<20} {:>6} cases {:>10.3} ms {:>12.1} us/case {:>8} boxes nested",
+        name,
+        cases.len(),
+        best.as_secs_f64() * 1e3,
+        best.as_secs_f64() * 1e6 / cases.len() as f64,
+        length
+    );
+}
+
+fn main() {
+    // `cargo bench` passes `--bench`; any other argument selects the
+    // scenarios to run.
+    let filters: Vec<String> = env::args().skip(1).filter(|arg| !arg.starts_with("--")).collect();
+    let solvers = [("dp", Solver::Dp), ("lis", Solver::Lis), ("graph", Solver::Graph)];
+    
+    for &boxes in BOXES.iter() {
+        for &dimensions in DIMENSIONS.iter() {
+            // `Solver::Lis` would only time `Solver::Dp` again.
+            let solvers: Vec<_> = solvers
+                .iter()
+                .filter(|&&(_, solver)| solver != Solver::Lis || dimensions <= 2)
+                .collect();
+            let names: Vec<String> = solvers
+                .iter()
+                .map(|(solver, _)| format!("{} k={} d={}", solver, boxes, dimensions))
+                .collect();
+            let selected = |name: &String| filters.is_empty() || filters.iter().any(|filter| name.contains(filter.as_str()));
+            if !names.iter().any(selected) {
+                continue;
+            }
+            
+            let cases = cases(boxes, dimensions);
+            for (name, &&(_, solver)) in names.iter().zip(solvers.iter()) {
+                if selected(name) {
+                    bench(name, solver, &cases);
+                }
+            }
+        }
+    }
+}
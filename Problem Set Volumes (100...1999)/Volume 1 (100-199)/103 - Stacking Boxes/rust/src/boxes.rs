@@ -10,183 +10,864 @@
 //! [Stacking Boxes problem]: https://onlinejudge.org/index.php?option=com_onlinejudge&Itemid=8&category=3&page=show_problem&problem=39
 
 use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
 
-#[derive(Debug, Eq)]
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The most dimensions a box of a case may have. The problem allows
+/// at most 10, but the solvers work for any number; this only keeps
+/// a case from asking for more work than it can be solved in. Every
+/// comparison of two boxes looks at up to this many dimensions, and
+/// `Solver::Dp` and `Solver::Graph` compare every pair of boxes, so
+/// a case of n boxes of d dimensions takes O(n²d) time.
+pub const MAX_DIMENSIONS: usize = 1000;
+
+/// How many dimensions `Box_::nests_inside()` compares at once.
+const CHUNK: usize = 8;
+
+/// `Box_` struct. A box of the problem, with its dimensions sorted,
+/// so that comparing two boxes position by position tells whether
+/// one nests inside the other. See `Box_::nests_inside()` and
+/// `BoxNesting`. With the `serde` feature, a box is serialized as its
+/// index and its dimensions as given, e.g.,
+/// `{"index":1,"dimensions":[3,7]}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(into = "RawBox", from = "RawBox"))]
 pub struct Box_ {
-     pub box_: Vec<usize>,
+    /// The box's dimensions, in ascending order.
+    box_: Vec<usize>,
+    
+    /// The box's dimensions, as given.
+    raw: Vec<usize>,
+    
+    /// The box's 1-based place in the input.
+    index: usize,
 }
 
-impl PartialOrd for Box_ {
-    fn partial_cmp(&self, other: &Box_) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl Box_ {
+    /// Return a new `Box_` instance of `dimensions`, the box at
+    /// 1-based place `index` in the input. The dimensions are sorted,
+    /// as a box may be turned any way to nest inside another.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::Box_;
+    ///
+    /// let box_ = Box_::new(3, vec![5, 2, 20]);
+    /// assert_eq!(box_.dimensions(), &[2, 5, 20]);
+    /// assert_eq!(box_.raw(), &[5, 2, 20]);
+    /// assert_eq!(box_.index(), 3);
+    ///
+    /// assert!(Box_::new(1, vec![1, 19, 4]).nests_inside(&box_));
+    /// ```
+    pub fn new(index: usize, dimensions: Vec<usize>) -> Box_ {
+        let mut box_ = dimensions.clone();
+        box_.sort_unstable();
+        
+        Box_ {
+            box_,
+            raw: dimensions,
+            index,
+        }
+    }
+    
+    /// Return the box's dimensions, in ascending order.
+    pub fn dimensions(&self) -> &[usize] {
+        &self.box_
+    }
+    
+    /// Return the box's dimensions, as given.
+    pub fn raw(&self) -> &[usize] {
+        &self.raw
+    }
+    
+    /// Return the box's 1-based place in the input.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    
+    /// Return whether the box nests inside `other`, i.e., whether
+    /// each of its sorted dimensions is strictly less than `other`'s
+    /// in the same position. A box never nests inside a box of
+    /// another number of dimensions, and a box of no dimensions never
+    /// nests inside anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::Box_;
+    ///
+    /// let box1 = Box_::new(1, vec![1, 2, 3]);
+    ///
+    /// assert!(box1.nests_inside(&Box_::new(2, vec![4, 3, 2])));
+    /// assert!(!box1.nests_inside(&Box_::new(3, vec![4, 3, 1])));
+    /// assert!(!box1.nests_inside(&box1));
+    /// ```
+    pub fn nests_inside(&self, other: &Box_) -> bool {
+        let (inner, outer) = (&self.box_[..], &other.box_[..]);
+        if inner.len() != outer.len() || inner.is_empty() {
+            return false;
+        }
+        
+        // The dimensions are sorted, so the smallest and the largest
+        // ones alone tell that most boxes don't nest, without looking
+        // at the rest.
+        let last = inner.len() - 1;
+        if inner[0] >= outer[0] || inner[last] >= outer[last] {
+            return false;
+        }
+        
+        // Compare the rest a chunk at a time, with no branch inside a
+        // chunk, so that each chunk compiles to a few vector
+        // instructions, and stop at the first chunk that doesn't nest.
+        inner
+            .chunks(CHUNK)
+            .zip(outer.chunks(CHUNK))
+            .all(|(inner, outer)| inner.iter().zip(outer).fold(true, |nests, (a, b)| nests & (a < b)))
     }
 }
 
-impl Ord for Box_ {
-    fn cmp(&self, other: &Box_) -> Ordering {
-        let self_box_len = self.box_.len();
-        let other_box_len = other.box_.len();
+/// A `Box_` as it is serialized, without its sorted dimensions, which
+/// are sorted again when it is deserialized.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct RawBox {
+    index: usize,
+    dimensions: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Box_> for RawBox {
+    fn from(box_: Box_) -> RawBox {
+        RawBox {
+            index: box_.index,
+            dimensions: box_.raw,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<RawBox> for Box_ {
+    fn from(raw: RawBox) -> Box_ {
+        Box_::new(raw.index, raw.dimensions)
+    }
+}
+
+/// `BoxNesting` trait. Orders boxes by whether one nests inside the
+/// other. This is only a partial order: of two boxes of which neither
+/// nests inside the other, neither is the lesser.
+pub trait BoxNesting {
+    /// Return `Some(Ordering::Less)` if `self` nests inside `other`,
+    /// `Some(Ordering::Greater)` if `other` nests inside `self`,
+    /// `Some(Ordering::Equal)` if they have the same dimensions, and
+    /// `None` otherwise, including if they have different numbers of
+    /// dimensions.
+    fn partial_nest_cmp(&self, other: &Self) -> Option<Ordering>;
+}
+
+impl BoxNesting for Box_ {
+    /// # Example
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use rust::boxes::{Box_, BoxNesting};
+    ///
+    /// let box1 = Box_::new(1, vec![1, 2, 3]);
+    ///
+    /// assert_eq!(box1.partial_nest_cmp(&Box_::new(2, vec![4, 3, 2])), Some(Ordering::Less));
+    /// assert_eq!(box1.partial_nest_cmp(&Box_::new(3, vec![3, 1, 2])), Some(Ordering::Equal));
+    /// assert_eq!(box1.partial_nest_cmp(&Box_::new(4, vec![4, 3, 1])), None);
+    /// ```
+    fn partial_nest_cmp(&self, other: &Box_) -> Option<Ordering> {
+        if self.box_ == other.box_ {
+            Some(Ordering::Equal)
+        }
+        else if self.nests_inside(other) {
+            Some(Ordering::Less)
+        }
+        else if other.nests_inside(self) {
+            Some(Ordering::Greater)
+        }
+        else {
+            None
+        }
+    }
+}
+
+/// `Nesting` struct. The longest nesting string of a case, as
+/// returned by `Boxes::solve()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Nesting {
+    /// The number of boxes in the string.
+    pub length: usize,
+    
+    /// The 1-based input order indices of the boxes in the string,
+    /// innermost box first.
+    pub boxes: Vec<usize>,
+}
+
+impl fmt::Display for Nesting {
+    /// Write the nesting string as the judge expects it: its length
+    /// on one line, then its boxes' indices, separated by spaces, on
+    /// the next.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.length)?;
         
-        if self_box_len != other_box_len {
-            return self.box_.cmp(&other.box_);
+        for (i, index) in self.boxes.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", index)?;
         }
         
-        let mut is_less = true;
-        let mut is_greater = true;
+        Ok(())
+    }
+}
+
+/// `Solver` enum. Which algorithm `Boxes::solve_with()` finds the
+/// longest nesting string with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Solver {
+    /// Compare every pair of boxes, in O(n²) comparisons of n boxes.
+    /// Works for any number of dimensions.
+    #[default]
+    Dp,
+    
+    /// Find the longest increasing subsequence of the boxes' largest
+    /// dimensions, by patience sorting, in O(n log n) time of n
+    /// boxes. Only boxes of one or two dimensions are totally ordered
+    /// enough for this to be exact, so other boxes are solved as by
+    /// `Solver::Dp`.
+    Lis,
+    
+    /// Find the longest path through the graph of which box nests
+    /// inside which. See `Boxes::solve_graph()`.
+    Graph,
+}
+
+/// `DpTable` struct. The longest nesting string of a case, as
+/// returned by `Boxes::solve_dp()`, with the table it was found
+/// from. The table is in the order of the boxes in `Boxes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DpTable {
+    /// The longest nesting string.
+    pub nesting: Nesting,
+    
+    /// The length of the longest nesting string ending with each box.
+    pub lengths: Vec<usize>,
+    
+    /// The index, see `Box_::index()`, of the box before each box in
+    /// the longest nesting string ending with it, if any.
+    pub previous: Vec<Option<usize>>,
+}
+
+/// An error returned when boxes cannot make up a case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BoxesError {
+    /// A box has no dimensions. Holds the box's 1-based place.
+    NoDimensions { index: usize },
+    
+    /// A box has another number of dimensions than the first box.
+    /// Holds the box's 1-based place, the first box's number of
+    /// dimensions, and the box's.
+    DimensionMismatch { index: usize, expected: usize, got: usize },
+    
+    /// A box has more than `MAX_DIMENSIONS` dimensions. Holds the
+    /// box's 1-based place, and its number of dimensions.
+    TooManyDimensions { index: usize, got: usize },
+}
+
+impl fmt::Display for BoxesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoxesError::NoDimensions { index } => write!(f, "box {} has no dimensions", index),
+            BoxesError::DimensionMismatch { index, expected, got } => {
+                write!(f, "box {} has {} dimensions, but box 1 has {}", index, got, expected)
+            },
+            BoxesError::TooManyDimensions { index, got } => {
+                write!(f, "box {} has {} dimensions, more than the {} allowed", index, got, MAX_DIMENSIONS)
+            },
+        }
+    }
+}
+
+impl Error for BoxesError {}
+
+/// `BoxesBuilder` struct. Builds a `Boxes` instance a box at a time.
+/// See `Boxes::builder()`.
+#[derive(Debug, Clone, Default)]
+pub struct BoxesBuilder {
+    /// The dimensions of each box added so far.
+    boxes: Vec<Vec<usize>>,
+}
+
+impl BoxesBuilder {
+    /// Add a box of `dimensions`, after the boxes added so far.
+    pub fn push(mut self, dimensions: Vec<usize>) -> BoxesBuilder {
+        self.boxes.push(dimensions);
+        self
+    }
+    
+    /// Return the `Boxes` instance holding the boxes added, as
+    /// `Boxes::from_vecs()` does.
+    pub fn build(self) -> Result<Boxes, BoxesError> {
+        Boxes::from_vecs(self.boxes)
+    }
+}
+
+/// `Boxes` struct. The boxes of a case. With the `serde` feature,
+/// the boxes are serialized as the list of their dimensions as given,
+/// e.g., `[[3,7],[8,10]]`, and deserialized as by `Boxes::from_vecs()`,
+/// so their indices are their places in the list.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "Vec<Vec<usize>>", try_from = "Vec<Vec<usize>>")
+)]
+pub struct Boxes {
+    num: usize,
+    dimensions: usize,
+    boxes: Vec<Box_>,
+}
+
+impl Boxes {
+    /// Return a new `Boxes` instance holding `boxes`, each of which
+    /// has `dimensions` dimensions. Neither is checked, nor is
+    /// `MAX_DIMENSIONS`; see `Boxes::from_vecs()` for that.
+    pub fn new(dimensions: usize, boxes: Vec<Box_>) -> Boxes {
+        Boxes {
+            num: boxes.len(),
+            dimensions,
+            boxes,
+        }
+    }
+    
+    /// Return a new `Boxes` instance holding a box of each of `vecs`'
+    /// dimensions, numbered from 1 in that order. Returns an `Err()`
+    /// if a box has no dimensions, more than `MAX_DIMENSIONS`, or
+    /// another number of them than the first box.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::{Boxes, BoxesError};
+    ///
+    /// let boxes = Boxes::from_vecs(vec![vec![3, 7], vec![8, 10], vec![5, 2]]).unwrap();
+    /// assert_eq!(boxes.solve().boxes, vec![3, 1, 2]);
+    ///
+    /// assert_eq!(
+    ///     Boxes::from_vecs(vec![vec![3, 7], vec![8, 10, 1]]),
+    ///     Err(BoxesError::DimensionMismatch { index: 2, expected: 2, got: 3 })
+    /// );
+    /// ```
+    pub fn from_vecs(vecs: Vec<Vec<usize>>) -> Result<Boxes, BoxesError> {
+        let dimensions = vecs.first().map_or(0, Vec::len);
         
-        for i in 0..self_box_len {
-            if self.box_[i] >= other.box_[i] {
-                is_less = false;
-            }
-            
-            if self.box_[i] <= other.box_[i] {
-                is_greater = false;
+        let boxes = vecs
+            .into_iter()
+            .enumerate()
+            .map(|(i, box_)| match box_.len() {
+                0 => Err(BoxesError::NoDimensions { index: i + 1 }),
+                got if got > MAX_DIMENSIONS => Err(BoxesError::TooManyDimensions { index: i + 1, got }),
+                got if got != dimensions => {
+                    Err(BoxesError::DimensionMismatch { index: i + 1, expected: dimensions, got })
+                },
+                _ => Ok(Box_::new(i + 1, box_)),
+            })
+            .collect::<Result<Vec<Box_>, BoxesError>>()?;
+        
+        Ok(Boxes::new(dimensions, boxes))
+    }
+    
+    /// Return a `BoxesBuilder` with no boxes, to add boxes to one at a
+    /// time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::Boxes;
+    ///
+    /// let boxes = Boxes::builder().push(vec![3, 7]).push(vec![8, 10]).build().unwrap();
+    /// assert_eq!(boxes, Boxes::from_vecs(vec![vec![3, 7], vec![8, 10]]).unwrap());
+    /// ```
+    pub fn builder() -> BoxesBuilder {
+        BoxesBuilder::default()
+    }
+    
+    /// Return the longest nesting string of the boxes: its length,
+    /// and the indices of its boxes, see `Box_::index()`, innermost
+    /// box first. A box nests inside another if its dimensions can be
+    /// reordered so that each is strictly less than the other box's
+    /// dimension in the same position. If several strings are the
+    /// longest, any one of them is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::{Box_, Boxes, Nesting};
+    ///
+    /// let boxes = Boxes::new(2, vec![
+    ///     Box_::new(1, vec![3, 7]),
+    ///     Box_::new(2, vec![8, 10]),
+    ///     Box_::new(3, vec![5, 2]),
+    ///     Box_::new(4, vec![9, 11]),
+    ///     Box_::new(5, vec![21, 18]),
+    /// ]);
+    ///
+    /// let nesting = boxes.solve();
+    /// assert_eq!(nesting, Nesting { length: 5, boxes: vec![3, 1, 2, 4, 5] });
+    /// assert_eq!(nesting.to_string(), "5\n3 1 2 4 5");
+    /// ```
+    pub fn solve(&self) -> Nesting {
+        self.solve_with(Solver::default())
+    }
+    
+    /// Like `Boxes::solve()`, but with the algorithm of `solver`.
+    /// The string is as long whichever the algorithm, but may be
+    /// another one of the longest.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::{Box_, Boxes, Solver};
+    ///
+    /// let boxes = Boxes::new(2, (1..=1000).map(|i| Box_::new(i, vec![i % 100, i / 10])).collect());
+    ///
+    /// assert_eq!(boxes.solve_with(Solver::Lis).length, boxes.solve_with(Solver::Dp).length);
+    /// ```
+    pub fn solve_with(&self, solver: Solver) -> Nesting {
+        match solver {
+            Solver::Lis if (1..=2).contains(&self.dimensions) => self.lis(),
+            Solver::Graph => self.solve_graph(),
+            _ => self.solve_dp().nesting,
+        }
+    }
+    
+    /// Like `Boxes::solve()`, but by building the graph that has an
+    /// edge from each box to every box it nests inside, and finding
+    /// its longest path, visiting the boxes in topological order.
+    /// As nesting is strict, the graph has no cycles. This relies on
+    /// nothing but `Box_::nests_inside()`, not on the order of the
+    /// boxes' sorted dimensions, to check the other solvers against.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::{Box_, Boxes, Solver};
+    ///
+    /// let boxes = Boxes::new(3, (1..=100).map(|i| Box_::new(i, vec![i % 7, i % 11, i % 13])).collect());
+    ///
+    /// assert_eq!(boxes.solve_graph().length, boxes.solve_with(Solver::Dp).length);
+    /// ```
+    pub fn solve_graph(&self) -> Nesting {
+        let boxes: Vec<&Box_> = self.boxes.iter().collect();
+        
+        // The boxes each box nests inside, and how many boxes nest
+        // inside each box.
+        let mut edges = vec![Vec::new(); boxes.len()];
+        let mut in_degrees = vec![0; boxes.len()];
+        for (i, inner) in boxes.iter().enumerate() {
+            for (j, outer) in boxes.iter().enumerate() {
+                if inner.nests_inside(outer) {
+                    edges[i].push(j);
+                    in_degrees[j] += 1;
+                }
             }
         }
         
-        if is_less {
-            return Ordering::Less;
+        // Kahn's algorithm: a box comes after every box that nests
+        // inside it.
+        let mut order: Vec<usize> = (0..boxes.len()).filter(|&i| in_degrees[i] == 0).collect();
+        let mut next = 0;
+        while next < order.len() {
+            for &j in edges[order[next]].iter() {
+                in_degrees[j] -= 1;
+                if in_degrees[j] == 0 {
+                    order.push(j);
+                }
+            }
+            next += 1;
         }
         
-        if is_greater {
-            return Ordering::Greater;
+        // The length of the longest path ending with each box, and
+        // the box before it on that path.
+        let mut lengths = vec![1; boxes.len()];
+        let mut previous = vec![None; boxes.len()];
+        for &i in order.iter() {
+            for &j in edges[i].iter() {
+                if lengths[i] + 1 > lengths[j] {
+                    lengths[j] = lengths[i] + 1;
+                    previous[j] = Some(i);
+                }
+            }
         }
+        let last = (0..boxes.len()).max_by_key(|&i| lengths[i]);
         
-        Ordering::Equal
+        Boxes::nesting(&boxes, &previous, last)
     }
-}
-
-impl PartialEq for Box_ {
-    fn eq(&self, other: &Box_) -> bool {
-        let self_box_len = self.box_.len();
-        let other_box_len = other.box_.len();
+    
+    /// Solve as `Solver::Dp` says, and return the longest nesting
+    /// string along with the length of the longest string ending with
+    /// each box, and the box before each box in it, for debugging.
+    /// Following the boxes before each box back from the last box of
+    /// the string finds the string in O(n) of n boxes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::boxes::{Box_, Boxes, Nesting};
+    ///
+    /// let boxes = Boxes::new(2, vec![
+    ///     Box_::new(1, vec![3, 7]),
+    ///     Box_::new(2, vec![8, 10]),
+    ///     Box_::new(3, vec![5, 2]),
+    ///     Box_::new(4, vec![1, 1]),
+    /// ]);
+    /// let table = boxes.solve_dp();
+    ///
+    /// assert_eq!(table.nesting, Nesting { length: 4, boxes: vec![4, 3, 1, 2] });
+    /// assert_eq!(table.lengths, vec![3, 4, 2, 1]);
+    /// assert_eq!(table.previous, vec![Some(3), Some(1), Some(4), None]);
+    /// ```
+    pub fn solve_dp(&self) -> DpTable {
+        let boxes: Vec<&Box_> = self.boxes.iter().collect();
         
-        if self_box_len != other_box_len {
-            return false;
+        // With each box's dimensions sorted, a box nests inside
+        // another if each of its dimensions is strictly less than
+        // the other box's in the same position. Ordering the boxes
+        // by their sorted dimensions then puts every box after all
+        // of the boxes that nest inside it.
+        let mut order: Vec<usize> = (0..boxes.len()).collect();
+        order.sort_by(|&a, &b| boxes[a].box_.cmp(&boxes[b].box_));
+        
+        // The length of the longest nesting string ending with each
+        // box, and the box before it in that string.
+        let mut lengths = vec![1; boxes.len()];
+        let mut previous = vec![None; boxes.len()];
+        for (i, &outer) in order.iter().enumerate() {
+            for &inner in order[..i].iter() {
+                // Comparing the lengths first skips comparing the
+                // boxes when it couldn't make the string longer.
+                if lengths[inner] + 1 > lengths[outer] && boxes[inner].nests_inside(boxes[outer]) {
+                    lengths[outer] = lengths[inner] + 1;
+                    previous[outer] = Some(inner);
+                }
+            }
         }
+        let last = order.iter().copied().max_by_key(|&i| lengths[i]);
         
-        let mut is_less = true;
-        let mut is_greater = true;
+        DpTable {
+            nesting: Boxes::nesting(&boxes, &previous, last),
+            previous: previous.iter().map(|previous| previous.map(|i| boxes[i].index)).collect(),
+            lengths,
+        }
+    }
+    
+    //
+    // Private methods.
+    //
+    
+    /// Solve as `Solver::Lis` says, for boxes of one or two
+    /// dimensions.
+    fn lis(&self) -> Nesting {
+        // Ordering the boxes by their smallest dimension, and then by
+        // their largest one, descending, makes a box nest inside a
+        // later one exactly if its largest dimension is less, as
+        // boxes of the same smallest dimension are then in decreasing
+        // order of the largest.
+        let largest = |box_: &Box_| box_.box_[box_.box_.len() - 1];
+        let mut sorted: Vec<&Box_> = self.boxes.iter().collect();
+        sorted.sort_by(|a, b| a.box_[0].cmp(&b.box_[0]).then(largest(b).cmp(&largest(a))));
         
-        for i in 0..self_box_len {
-            if self.box_[i] >= other.box_[i] {
-                is_less = false;
-            }
+        // The last box of the nesting string of each length found so
+        // far that ends with the smallest largest dimension, and the
+        // box before each box in its string. These smallest largest
+        // dimensions increase with the length, so the string a box
+        // ends is found by a binary search.
+        let mut tails: Vec<usize> = Vec::new();
+        let mut previous = vec![None; sorted.len()];
+        for i in 0..sorted.len() {
+            let length = tails.partition_point(|&tail| largest(sorted[tail]) < largest(sorted[i]));
             
-            if self.box_[i] <= other.box_[i] {
-                is_greater = false;
+            if length > 0 {
+                previous[i] = Some(tails[length - 1]);
+            }
+            if length == tails.len() {
+                tails.push(i);
+            }
+            else {
+                tails[length] = i;
             }
         }
         
-        if is_less {
-            return false;
+        Boxes::nesting(&sorted, &previous, tails.last().copied())
+    }
+    
+    //
+    // Private functions.
+    //
+    
+    /// Return the nesting string of `boxes` ending with the box at
+    /// `last`, following the box before each box in `previous` back
+    /// from it.
+    fn nesting(boxes: &[&Box_], previous: &[Option<usize>], last: Option<usize>) -> Nesting {
+        let mut string = Vec::new();
+        let mut last = last;
+        while let Some(i) = last {
+            string.push(boxes[i].index);
+            last = previous[i];
         }
+        string.reverse();
         
-        if is_greater {
-            return false;
+        Nesting {
+            length: string.len(),
+            boxes: string,
+        }
+    }
+}
+
+impl TryFrom<Vec<Vec<usize>>> for Boxes {
+    type Error = BoxesError;
+    
+    fn try_from(vecs: Vec<Vec<usize>>) -> Result<Boxes, BoxesError> {
+        Boxes::from_vecs(vecs)
+    }
+}
+
+impl fmt::Display for Boxes {
+    /// Write the case as the judge's input holds it: the number of
+    /// boxes and their number of dimensions on one line, then each
+    /// box's dimensions, as given, on a line of its own.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", self.num, self.dimensions)?;
+        
+        for box_ in self.boxes.iter() {
+            for (i, dimension) in box_.raw.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}", dimension)?;
+            }
+            writeln!(f)?;
         }
         
-        true
+        Ok(())
     }
 }
 
-#[derive(Debug, PartialEq)]
-pub struct Boxes {
-    num: usize,
-    dimensions: usize,
-    boxes: Vec<Box_>,
+impl From<Boxes> for Vec<Vec<usize>> {
+    fn from(boxes: Boxes) -> Vec<Vec<usize>> {
+        boxes.boxes.into_iter().map(|box_| box_.raw).collect()
+    }
 }
 
+//
+// Tests.
+//
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::generator::Generator;
+    
+    /// Return a `Boxes` instance holding `boxes`.
+    fn boxes(boxes: &[&[usize]]) -> Boxes {
+        Boxes::from_vecs(boxes.iter().map(|box_| box_.to_vec()).collect()).unwrap()
+    }
     
     #[test]
-    fn box_is_less_than_box() {
-        let box1 = Box_ {
-            box_: vec![1, 2, 3, 4, 5],
-        };
+    fn boxes_from_vecs() {
+        assert_eq!(Boxes::from_vecs(vec![]), Ok(Boxes::new(0, vec![])));
+        assert_eq!(
+            Boxes::from_vecs(vec![vec![1, 2], vec![3, 4]]),
+            Ok(Boxes::new(2, vec![Box_::new(1, vec![1, 2]), Box_::new(2, vec![3, 4])]))
+        );
+        
+        let error = Boxes::from_vecs(vec![vec![1], vec![2], vec![3, 4]]).unwrap_err();
+        assert_eq!(error, BoxesError::DimensionMismatch { index: 3, expected: 1, got: 2 });
+        assert_eq!(error.to_string(), "box 3 has 2 dimensions, but box 1 has 1");
         
-        let box2 = Box_ {
-            box_: vec![2, 3, 4, 5, 6],
-        };
+        assert_eq!(Boxes::from_vecs(vec![vec![]]), Err(BoxesError::NoDimensions { index: 1 }));
+        assert_eq!(Boxes::builder().push(vec![1]).push(vec![]).build(), Err(BoxesError::NoDimensions { index: 2 }));
         
-        assert!(box1 < box2);
-        assert!(box2 > box1);
+        let error = Boxes::from_vecs(vec![vec![1; MAX_DIMENSIONS + 1]]).unwrap_err();
+        assert_eq!(error, BoxesError::TooManyDimensions { index: 1, got: MAX_DIMENSIONS + 1 });
+        assert_eq!(error.to_string(), "box 1 has 1001 dimensions, more than the 1000 allowed");
+        assert!(Boxes::from_vecs(vec![vec![1; MAX_DIMENSIONS]]).is_ok());
     }
     
     #[test]
-    fn box_is_greater_than_box() {
-        let box1 = Box_ {
-            box_: vec![16, 39, 10],
-        };
+    #[cfg(feature = "serde")]
+    fn boxes_serde_round_trip() {
+        let box_ = Box_::new(3, vec![5, 2, 20]);
+        let json = serde_json::to_string(&box_).unwrap();
+        assert_eq!(json, r#"{"index":3,"dimensions":[5,2,20]}"#);
+        assert_eq!(serde_json::from_str::<Box_>(&json).unwrap(), box_);
         
-        let box2 = Box_ {
-            box_: vec![1, 3, 9],
-        };
+        let boxes = boxes(&[&[3, 7], &[8, 10], &[5, 2]]);
+        let json = serde_json::to_string(&boxes).unwrap();
+        assert_eq!(json, "[[3,7],[8,10],[5,2]]");
+        assert_eq!(serde_json::from_str::<Boxes>(&json).unwrap(), boxes);
+        assert!(serde_json::from_str::<Boxes>("[[3,7],[8]]").is_err());
         
-        assert!(box1 > box2);
-        assert!(box2 < box1);
+        let nesting = boxes.solve();
+        let json = serde_json::to_string(&nesting).unwrap();
+        assert_eq!(json, r#"{"length":3,"boxes":[3,1,2]}"#);
+        assert_eq!(serde_json::from_str::<Nesting>(&json).unwrap(), nesting);
     }
     
     #[test]
-    fn box_is_equal_to_box() {
-        let box1 = Box_ {
-            box_: vec![1, 2, 3],
-        };
+    fn boxes_solve_sample() {
+        let sample = boxes(&[
+            &[5, 2, 20, 1, 30, 10],
+            &[23, 15, 7, 9, 11, 3],
+            &[40, 50, 34, 24, 14, 4],
+            &[9, 10, 11, 12, 13, 14],
+            &[31, 4, 18, 8, 27, 17],
+            &[44, 32, 13, 19, 41, 19],
+            &[1, 2, 3, 4, 5, 6],
+            &[80, 37, 47, 18, 21, 9],
+        ]);
         
-        let box2 = Box_ {
-            box_: vec![1, 2, 3],
-        };
+        let nesting = sample.solve();
+        assert_eq!(nesting, Nesting { length: 4, boxes: vec![7, 2, 5, 6] });
+        assert_eq!(nesting.to_string(), "4\n7 2 5 6");
+    }
+    
+    #[test]
+    fn boxes_solve_edge_cases() {
+        assert_eq!(boxes(&[]).solve(), Nesting { length: 0, boxes: vec![] });
+        assert_eq!(boxes(&[&[1, 2]]).solve().to_string(), "1\n1");
         
-        assert_eq!(box1, box2);
+        // Equal dimensions don't nest.
+        assert_eq!(boxes(&[&[2, 2], &[2, 2], &[2, 2]]).solve().length, 1);
+        assert_eq!(boxes(&[&[2, 3], &[1, 3], &[3, 4]]).solve().length, 2);
         
-        let box1 = Box_ {
-            box_: vec![1, 2, 3],
-        };
+        // Dimensions nest in any order, and boxes are named by their
+        // place in the input, not in the string.
+        assert_eq!(boxes(&[&[9, 1, 5], &[2, 10, 6], &[0, 4, 8]]).solve().boxes, vec![3, 1, 2]);
+    }
+    
+    #[test]
+    fn boxes_solvers_agree() {
+        for dimensions in 1..=4 {
+            // Small dimensions, so that ties are common.
+            let mut generator = Generator::new(0x9e37_79b9_7f4a_7c15)
+                .boxes(0..=59)
+                .dimensions(dimensions..=dimensions)
+                .values(0..=19);
+            
+            for _ in 0..60 {
+                let boxes = generator.case().boxes;
+                
+                let dp = boxes.solve_with(Solver::Dp);
+                let table = boxes.solve_dp();
+                assert_eq!(table.nesting, dp);
+                assert_eq!(table.lengths.iter().max().copied().unwrap_or(0), dp.length);
+                
+                for &solver in [Solver::Lis, Solver::Graph].iter() {
+                    let nesting = boxes.solve_with(solver);
+                    assert_eq!(nesting.length, dp.length, "{:?}: {:?}", solver, boxes);
+                    
+                    // Each box of the string nests inside the next.
+                    for pair in nesting.boxes.windows(2) {
+                        assert!(boxes.boxes[pair[0] - 1].nests_inside(&boxes.boxes[pair[1] - 1]),
+                                "{:?}: {:?}", solver, nesting);
+                    }
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn box_nests_inside_box_of_many_dimensions() {
+        let inner: Vec<usize> = (0..20).collect();
+        let outer: Vec<usize> = (1..=20).collect();
+        assert!(Box_::new(1, inner.clone()).nests_inside(&Box_::new(2, outer.clone())));
         
-        let box2 = Box_ {
-            box_: vec![1, 1, 1],
-        };
+        // The smallest and largest dimensions nest, but one in the
+        // middle of each chunk doesn't.
+        for i in 1..19 {
+            let mut outer = outer.clone();
+            outer[i] = i;
+            assert!(!Box_::new(1, inner.clone()).nests_inside(&Box_::new(2, outer)), "{}", i);
+        }
         
-        // If a box can't exactly fit inside another box
-        // (dimensions need to be strictly less) then it is
-        // considered equal in this context.
-        assert_eq!(box1, box2);
+        assert!(!Box_::new(1, inner.clone()).nests_inside(&Box_::new(2, outer[1..].to_vec())));
     }
     
     #[test]
-    fn box_diff_dimensions() {
-        let box1 = Box_ {
-            box_: vec![2, 3],
-        };
+    fn box_nests_inside_box() {
+        let box1 = Box_::new(1, vec![1, 2, 3, 4, 5]);
+        let box2 = Box_::new(2, vec![2, 3, 4, 5, 6]);
+        
+        assert_eq!(box1.partial_nest_cmp(&box2), Some(Ordering::Less));
+        assert_eq!(box2.partial_nest_cmp(&box1), Some(Ordering::Greater));
+        assert!(box1.nests_inside(&box2));
+        assert!(!box2.nests_inside(&box1));
         
-        let box2 = Box_ {
-            box_: vec![1, 2, 3],
-        };
+        // Dimensions are compared sorted.
+        let box1 = Box_::new(1, vec![16, 39, 10]);
+        let box2 = Box_::new(2, vec![1, 3, 9]);
         
-        assert!(box1 != box2);
-        assert!(box1 > box2); // Undefined behavior. Should it pass this test?
+        assert_eq!(box1.partial_nest_cmp(&box2), Some(Ordering::Greater));
+        assert_eq!(box2.partial_nest_cmp(&box1), Some(Ordering::Less));
+    }
+    
+    #[test]
+    fn box_is_equal_to_box() {
+        let box1 = Box_::new(1, vec![1, 2, 3]);
+        let box2 = Box_::new(1, vec![1, 2, 3]);
         
-        let box1 = Box_ {
-            box_: vec![2, 3],
-        };
+        assert_eq!(box1, box2);
+        assert_eq!(box1.partial_nest_cmp(&box2), Some(Ordering::Equal));
         
-        let box2 = Box_ {
-            box_: vec![2, 3, 1],
-        };
+        // Boxes are only equal if they are the same box, not if
+        // neither fits inside the other.
+        let box1 = Box_::new(1, vec![1, 2, 3]);
+        let box2 = Box_::new(2, vec![1, 1, 1]);
         
-        assert!(box1 != box2);
-        assert!(box1 < box2); // Undefined behavior. Should it pass this test?
+        assert_ne!(box1, box2);
+        assert_eq!(box1.partial_nest_cmp(&box2), None);
+        assert_eq!(box2.partial_nest_cmp(&box1), None);
+        assert!(!box1.nests_inside(&box2) && !box2.nests_inside(&box1));
         
-        let box1 = Box_ {
-            box_: vec![2, 3],
-        };
+        let box2 = Box_::new(1, vec![3, 2, 1]);
+        assert_ne!(box1, box2);
+        assert_eq!(box1.partial_nest_cmp(&box2), Some(Ordering::Equal));
+    }
+    
+    #[test]
+    fn box_diff_dimensions() {
+        // Boxes of different numbers of dimensions never nest.
+        let box1 = Box_::new(1, vec![2, 3]);
         
-        let box2 = Box_ {
-            box_: vec![2, 3, 3],
-        };
+        for dimensions in [vec![1, 2, 3], vec![2, 3, 1], vec![3, 4, 5], vec![1], vec![]].iter() {
+            let box2 = Box_::new(2, dimensions.clone());
+            
+            assert_ne!(box1, box2);
+            assert_eq!(box1.partial_nest_cmp(&box2), None, "{:?}", dimensions);
+            assert_eq!(box2.partial_nest_cmp(&box1), None, "{:?}", dimensions);
+        }
+    }
+    
+    #[test]
+    fn box_of_no_dimensions() {
+        let box_ = Box_::new(1, vec![]);
+        assert!(!box_.nests_inside(&box_));
+        assert!(!box_.nests_inside(&Box_::new(2, vec![])));
         
-        assert!(box1 != box2);
+        // So no two of them make a nesting string, whichever solver.
+        let boxes = Boxes::new(0, (1..=3).map(|i| Box_::new(i, vec![])).collect());
+        for &solver in [Solver::Dp, Solver::Lis, Solver::Graph].iter() {
+            assert_eq!(boxes.solve_with(solver).length, 1, "{:?}", solver);
+        }
     }
 }
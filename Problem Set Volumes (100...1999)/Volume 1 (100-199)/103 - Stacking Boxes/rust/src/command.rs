@@ -9,81 +9,115 @@
 
 use std::io;
 
+use crate::boxes::Nesting;
+use crate::parser::CaseParser;
+
+/// What `Command::run_with()` does after reporting a malformed case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    /// Skip the case and carry on with the next one.
+    #[default]
+    Continue,
+    
+    /// Stop reading, as if the input had ended.
+    Stop,
+}
+
 pub struct Command {
 }
 
 impl Command {
-    pub fn run(mut buf: &mut impl io::BufRead) -> Result<(), io::Error> {
-        // Read one line of setup input to determine the blocks size.
-        // Reading in a loop so we can re-prompt the user if they
-        // enter an invalid value.
-        let mut input = String::new();
+    /// Read cases from `buf` until the input ends, writing the longest
+    /// nesting string of each onto `out`, and the errors of malformed
+    /// cases onto `io::stderr`, skipping them. Returns the nesting
+    /// strings written.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::command::Command;
+    ///
+    /// let mut input = "5 2\n3 7\n8 10\n5 2\n9 11\n21 18\n".as_bytes();
+    /// let mut out = Vec::new();
+    ///
+    /// let results = Command::run(&mut input, &mut out).unwrap();
+    /// assert_eq!(out, b"5\n3 1 2 4 5\n");
+    /// assert_eq!(results[0].boxes, vec![3, 1, 2, 4, 5]);
+    /// ```
+    pub fn run(buf: &mut impl io::BufRead, out: &mut impl io::Write) -> Result<Vec<Nesting>, io::Error> {
+        Command::run_with(buf, out, &mut io::stderr(), ErrorPolicy::default())
+    }
+    
+    /// Like `Command::run()`, but the errors are written onto `err`,
+    /// and `policy` says whether to carry on after a malformed case.
+    /// Returns an `Err()` if reading or writing fails.
+    pub fn run_with(
+        buf: &mut impl io::BufRead,
+        out: &mut impl io::Write,
+        err: &mut impl io::Write,
+        policy: ErrorPolicy
+    ) -> Result<Vec<Nesting>, io::Error> {
+        let mut results = Vec::new();
         
-        loop {
-            input.clear();
-            buf.read_line(&mut input)?;
-            
-            let boxes_and_dimensions: Vec<usize> = 
-                input.split_whitespace()
-                     .map(|s| s.parse().unwrap())
-                     .collect();
-            
-            if boxes_and_dimensions.len() != 2 {
-                panic!("The first input line must be two unsigned integers.")
+        for case in CaseParser::new(buf) {
+            match case? {
+                Ok(boxes) => {
+                    // The length of the longest nesting string, then
+                    // its boxes, innermost first.
+                    let nesting = boxes.solve();
+                    writeln!(out, "{}", nesting)?;
+                    results.push(nesting);
+                },
+                Err(error) => {
+                    writeln!(err, "{}", error)?;
+                    
+                    if policy == ErrorPolicy::Stop {
+                        break;
+                    }
+                },
             }
-            
-            let num_boxes = boxes_and_dimensions[0];
-            let num_dimensions = boxes_and_dimensions[1];
-            
-            println!("num_boxes = {}, num_dimensions = {}", num_boxes, num_dimensions);
-            println!("{:?}", boxes_and_dimensions);
-            
-            for i in 0..num_boxes {
-                input.clear();
-                buf.read_line(&mut input)?;
-                
-                let box_vec: Vec<usize> = 
-                    input.split_whitespace()
-                         .map(|s| s.parse().unwrap())
-                         .collect();
-                
-                if box_vec.len() != num_dimensions {
-                    panic!(
-                        "Box {} input line must be {} unsigned integers.",
-                        i + 1,
-                        num_dimensions
-                    );
-                }
-                
-                println!("Box {} = {:?}", i + 1, box_vec);
-            }
-            
-            println!("The longest nested string is ...");
         }
-        //loop {
-        //        for boxes_input in input.trim()
-        //                                .split_whitespace()
-        //                                .map(|a| a.parse::<usize>())
-        //                                .collect() {
-        //            println!("num_boxes = {}, num_dimensions = {}", boxes_input[1], boxes_input[2]);
-        //        }
-        //    }
-        //    if let Ok(num_blocks) = setup.trim().parse::<usize>() {
-        //        if num_blocks == 0 {
-        //            eprintln!("Error! Blocks size must be greater than 0.");
-        //            continue;
-        //        }
-        //        
-        //        // Create a Robot instance containing Blocks of the
-        //        // specified size, and run it.
-        //        return Robot::new(num_blocks).main_loop(&mut buf);
-        //    }
-        //    else if setup.trim() == "q" || setup.trim() == "quit" {
-        //        return Ok(());
-        //    }
-        //    else {
-        //        eprintln!("Error! Please enter the desired blocks size as a positive integer.");
-        //    }
-        //}
+        
+        out.flush()?;
+        
+        Ok(results)
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn command_run_sample() {
+        let input = "5 2\n3 7\n8 10\n5 2\n9 11\n21 18\n\
+                     8 6\n5 2 20 1 30 10\n23 15 7 9 11 3\n40 50 34 24 14 4\n9 10 11 12 13 14\n\
+                     31 4 18 8 27 17\n44 32 13 19 41 19\n1 2 3 4 5 6\n80 37 47 18 21 9\n";
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        
+        let results = Command::run_with(&mut input.as_bytes(), &mut out, &mut err, ErrorPolicy::Continue).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "5\n3 1 2 4 5\n4\n7 2 5 6\n");
+        assert!(err.is_empty());
+        assert_eq!(results.iter().map(|nesting| nesting.length).collect::<Vec<_>>(), vec![5, 4]);
+    }
+    
+    #[test]
+    fn command_run_with_policy() {
+        let input = "1 2\n3 4\n1 x\n1 2\n5 6\n";
+        let expected_err = "Error! Line 3: `x` is not an unsigned integer.\n";
+        
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        Command::run_with(&mut input.as_bytes(), &mut out, &mut err, ErrorPolicy::Continue).unwrap();
+        assert_eq!(out, b"1\n1\n1\n1\n");
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err);
+        
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        Command::run_with(&mut input.as_bytes(), &mut out, &mut err, ErrorPolicy::Stop).unwrap();
+        assert_eq!(out, b"1\n1\n");
+        assert_eq!(String::from_utf8(err).unwrap(), expected_err);
     }
 }
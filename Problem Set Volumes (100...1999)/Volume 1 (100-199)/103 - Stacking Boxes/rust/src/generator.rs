@@ -0,0 +1,277 @@
+//! `generator` module.
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-19
+//!
+//! This module generates random cases, to stress test the solvers
+//! with. A case can have a nesting string of a known length planted
+//! in it, so that the solvers' answers can be sanity checked.
+
+use std::ops::RangeInclusive;
+
+use crate::boxes::{Boxes, MAX_DIMENSIONS};
+
+/// `Case` struct. A case made by `Generator::case()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// The case's boxes, numbered from 1.
+    pub boxes: Boxes,
+    
+    /// The indices of the boxes of the planted nesting string,
+    /// innermost box first, or nothing if none was planted. The
+    /// longest nesting string is at least as long.
+    pub planted: Vec<usize>,
+}
+
+/// `Generator` struct. Generates random cases, the same ones for the
+/// same seed and settings. The defaults are the problem's limits: up
+/// to 30 boxes of up to 10 dimensions.
+///
+/// # Example
+///
+/// ```
+/// use rust::generator::Generator;
+///
+/// let mut generator = Generator::new(42).boxes(10..=20).dimensions(3..=3).planted_chain(5);
+/// let case = generator.case();
+///
+/// assert_eq!(case.planted.len(), 5);
+/// assert!(case.boxes.solve().length >= 5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Generator {
+    /// The state of the xorshift64 generator. Never 0.
+    state: u64,
+    
+    /// How many boxes a case has.
+    boxes: RangeInclusive<usize>,
+    
+    /// How many dimensions a case's boxes have.
+    dimensions: RangeInclusive<usize>,
+    
+    /// How long each dimension is.
+    values: RangeInclusive<usize>,
+    
+    /// How long a nesting string to plant in each case.
+    chain: usize,
+}
+
+impl Generator {
+    /// Return a new `Generator` instance seeded with `seed`.
+    pub fn new(seed: u64) -> Generator {
+        Generator {
+            // xorshift64 is stuck at 0.
+            state: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed },
+            boxes: 1..=30,
+            dimensions: 1..=10,
+            values: 1..=100,
+            chain: 0,
+        }
+    }
+    
+    /// Make cases of a number of boxes in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty.
+    pub fn boxes(mut self, range: RangeInclusive<usize>) -> Generator {
+        assert!(!range.is_empty(), "the range of the number of boxes, {:?}, is empty", range);
+        
+        self.boxes = range;
+        self
+    }
+    
+    /// Make cases of boxes of a number of dimensions in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty, or holds 0, or more than
+    /// `MAX_DIMENSIONS`, as such boxes can't make up a case.
+    pub fn dimensions(mut self, range: RangeInclusive<usize>) -> Generator {
+        assert!(
+            !range.is_empty() && *range.start() > 0 && *range.end() <= MAX_DIMENSIONS,
+            "the range of the number of dimensions, {:?}, is not within 1..={}",
+            range, MAX_DIMENSIONS
+        );
+        
+        self.dimensions = range;
+        self
+    }
+    
+    /// Make boxes of dimensions in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is empty.
+    pub fn values(mut self, range: RangeInclusive<usize>) -> Generator {
+        assert!(!range.is_empty(), "the range of the dimensions, {:?}, is empty", range);
+        
+        self.values = range;
+        self
+    }
+    
+    /// Plant a nesting string of `length` boxes in each case, or of
+    /// as many boxes as the case has, or as there are values, if
+    /// fewer.
+    pub fn planted_chain(mut self, length: usize) -> Generator {
+        self.chain = length;
+        self
+    }
+    
+    /// Return a new random case.
+    pub fn case(&mut self) -> Case {
+        let num_boxes = self.next_in(&self.boxes.clone());
+        let num_dimensions = self.next_in(&self.dimensions.clone());
+        let values = self.values.clone();
+        
+        let mut boxes: Vec<Vec<usize>> = (0..num_boxes)
+            .map(|_| (0..num_dimensions).map(|_| self.next_in(&values)).collect())
+            .collect();
+        
+        // Replace a random choice of the boxes by a nesting string.
+        // Each of its dimensions is a strictly increasing choice of
+        // values, and each of its boxes has its dimensions in a
+        // random order.
+        let length = self.chain.min(num_boxes).min(values.end().saturating_sub(*values.start()).saturating_add(1));
+        let mut places: Vec<usize> = (0..num_boxes).collect();
+        self.shuffle(&mut places);
+        places.truncate(length);
+        
+        let mut chain = vec![Vec::with_capacity(num_dimensions); length];
+        for _ in 0..num_dimensions {
+            // How many values the string's boxes leave unused, which
+            // can't overflow, even if `values` holds every `usize`.
+            let slack = (values.end() - values.start()) - length.saturating_sub(1);
+            let mut offsets: Vec<usize> = (0..length).map(|_| self.next_in(&(0..=slack))).collect();
+            offsets.sort_unstable();
+            
+            for (i, offset) in offsets.into_iter().enumerate() {
+                chain[i].push(*values.start() + offset + i);
+            }
+        }
+        for (&place, mut box_) in places.iter().zip(chain) {
+            self.shuffle(&mut box_);
+            boxes[place] = box_;
+        }
+        
+        Case {
+            boxes: Boxes::from_vecs(boxes).expect("every box has the same dimensions"),
+            planted: places.iter().map(|place| place + 1).collect(),
+        }
+    }
+    
+    //
+    // Private methods.
+    //
+    
+    /// Return the next pseudo-random number.
+    fn next(&mut self) -> u64 {
+        // xorshift64.
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        
+        self.state
+    }
+    
+    /// Return a pseudo-random number in `range`.
+    fn next_in(&mut self, range: &RangeInclusive<usize>) -> usize {
+        let next = self.next() as usize;
+        let width = range.end().checked_sub(*range.start()).expect("the range isn't empty");
+        
+        // Only a range of every `usize` is too wide to count.
+        match width.checked_add(1) {
+            Some(width) => range.start() + next % width,
+            None => next,
+        }
+    }
+    
+    /// Put `items` in a random order.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        // Fisher-Yates.
+        for i in (1..items.len()).rev() {
+            let j = self.next_in(&(0..=i));
+            items.swap(i, j);
+        }
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    use crate::boxes::Solver;
+    use crate::parser::CaseParser;
+    
+    #[test]
+    fn generator_plants_chains() {
+        let mut generator = Generator::new(7).boxes(1..=40).dimensions(1..=6).values(1..=30).planted_chain(8);
+        
+        for _ in 0..200 {
+            let case = generator.case();
+            let boxes: Vec<Vec<usize>> = case.boxes.clone().into();
+            
+            assert!((1..=40).contains(&boxes.len()));
+            assert!(boxes.iter().flatten().all(|value| (1..=30).contains(value)));
+            assert_eq!(case.planted.len(), boxes.len().min(8));
+            
+            // The planted string nests, and the solvers find one at
+            // least as long.
+            for pair in case.planted.windows(2) {
+                let inner = Boxes::from_vecs(vec![boxes[pair[0] - 1].clone(), boxes[pair[1] - 1].clone()]).unwrap();
+                assert_eq!(inner.solve().length, 2, "{:?}", case);
+            }
+            for &solver in [Solver::Dp, Solver::Lis, Solver::Graph].iter() {
+                assert!(case.boxes.solve_with(solver).length >= case.planted.len(), "{:?}", case);
+            }
+        }
+    }
+    
+    #[test]
+    fn generator_cases_round_trip_through_the_input() {
+        let mut generator = Generator::new(0).planted_chain(100).values(5..=7);
+        
+        for _ in 0..50 {
+            let case = generator.case();
+            assert!(case.planted.len() <= 3);
+            
+            let input = case.boxes.to_string();
+            let parsed = CaseParser::new(input.as_bytes()).next_case().unwrap().unwrap().unwrap();
+            assert_eq!(parsed, case.boxes);
+        }
+        
+        // The same seed makes the same cases.
+        assert_eq!(Generator::new(3).case(), Generator::new(3).case());
+    }
+    
+    #[test]
+    fn generator_extreme_ranges() {
+        let mut generator = Generator::new(5).boxes(0..=3).values(0..=usize::MAX).planted_chain(3);
+        for _ in 0..50 {
+            let case = generator.case();
+            assert!(case.boxes.solve().length >= case.planted.len(), "{:?}", case);
+        }
+        
+        // A single value makes a string of a single box.
+        let case = Generator::new(5).boxes(3..=3).values(7..=7).planted_chain(3).case();
+        assert_eq!(case.planted.len(), 1);
+    }
+    
+    #[test]
+    #[should_panic(expected = "the range of the number of boxes, 5..=3, is empty")]
+    fn generator_empty_range() {
+        let (start, end) = (5, 3);
+        Generator::new(1).boxes(start..=end);
+    }
+    
+    #[test]
+    #[should_panic(expected = "the range of the number of dimensions, 0..=3, is not within 1..=1000")]
+    fn generator_no_dimensions() {
+        Generator::new(1).dimensions(0..=3);
+    }
+}
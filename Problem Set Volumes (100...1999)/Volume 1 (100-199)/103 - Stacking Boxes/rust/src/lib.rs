@@ -11,3 +11,5 @@
 
 pub mod boxes;
 pub mod command;
+pub mod generator;
+pub mod parser;
@@ -1,48 +1,48 @@
-#![allow(unused_imports)]
-#![allow(unused_must_use)]
-
+use std::env;
 use std::io;
 use std::fs::File;
+use std::path::PathBuf;
+use std::process;
 
-use rust::boxes::{Box_, Boxes};
-use rust::command::Command;
+use rust::command::{Command, ErrorPolicy};
 
 fn main() -> std::io::Result<()> {
-    let stdin = io::stdin();
-    let mut _reader = stdin.lock();
+    let mut policy = ErrorPolicy::Continue;
+    let mut input = None;
     
-    // Uncomment these two lines to read the commands
-    // from a file called "input.txt". TODO: pass in file
-    // as parameters to the execution of this program.
-    //let fh = File::open("input.txt")?;
-    //let mut _reader = io::BufReader::new(fh);
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            // Stop at the first malformed case, instead of skipping
+            // it.
+            "--stop-on-error" => policy = ErrorPolicy::Stop,
+            
+            // Anything else that isn't a flag is the input file.
+            _ if !arg.starts_with("--") && input.is_none() => {
+                input = Some(PathBuf::from(arg));
+            },
+            
+            _ => {
+                eprintln!("Error! Unknown argument `{}`, expected `--stop-on-error`, or an input file.", arg);
+                process::exit(2);
+            },
+        }
+    }
     
-    Command::run(&mut _reader);
+    // Read the cases from the input file if one was given, else from
+    // `io::stdin`.
+    let stdin = io::stdin();
+    let mut reader: Box<dyn io::BufRead> = match input {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(io::BufReader::new(file)),
+            Err(error) => {
+                eprintln!("Error! Cannot open `{}`: {}", path.display(), error);
+                process::exit(1);
+            },
+        },
+        None => Box::new(stdin.lock()),
+    };
+    
+    Command::run_with(&mut reader, &mut io::stdout(), &mut io::stderr(), policy)?;
     
     Ok(())
 }
-
-//    let b1 = Box_ {
-//        box_: vec![3, 10, 2, 4],
-//    };
-//    
-//    let b2 = Box_ {
-//        box_: vec![2, 9, 1, 3],
-//    };
-//    
-//    let b3 = Box_ {
-//        box_: vec![1, 10, 1, 3],
-//    };
-//    
-//    let b4 = Box_ {
-//        box_: vec![1, 10, 1, 3],
-//    };
-//    
-//    println!("b1 < b2 = {}", b1 < b2);
-//    println!("b1 > b2 = {}", b1 > b2);
-//    println!("b2 > b3 = {}", b2 > b3);
-//    println!("b2 == b3 = {}", b2 == b3);
-//    println!("b3 == b4 = {}", b3 == b4);
-//    println!("b3 <= b4 = {}", b3 <= b4);
-//    println!("b3 >= b4 = {}", b3 >= b4);
-//}
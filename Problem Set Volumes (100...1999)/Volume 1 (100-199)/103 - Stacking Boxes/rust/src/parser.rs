@@ -0,0 +1,317 @@
+//! `parser` module.
+//!
+//! Author: Jonathan Sawyer <jonmsawyer[at]gmail.com>
+//!
+//! Date: 2020-06-19
+//!
+//! This module parses the cases of the input: a header line holding
+//! the number of boxes `k` and their number of dimensions `d`, then
+//! `k` lines of `d` integers each.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::boxes::{Box_, Boxes, MAX_DIMENSIONS};
+
+/// An error returned when a case's input lines are malformed. Each
+/// variant holds the 1-based number of the offending input line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// A token is not an unsigned integer. Holds the line number and
+    /// the token.
+    InvalidInteger { line: usize, token: String },
+    
+    /// The header line does not hold exactly two integers, the number
+    /// of boxes and their number of dimensions. Holds the line number
+    /// and how many fields it holds.
+    WrongFieldCount { line: usize, got: usize },
+    
+    /// A box's line does not hold one integer per dimension. Holds
+    /// the line number, the number of dimensions, and how many fields
+    /// it holds.
+    WrongDimensionCount { line: usize, expected: usize, got: usize },
+    
+    /// The header line asks for boxes of no dimensions. Holds the
+    /// line number.
+    NoDimensions { line: usize },
+    
+    /// The header line asks for more than `MAX_DIMENSIONS`
+    /// dimensions. Holds the line number and the number of
+    /// dimensions.
+    TooManyDimensions { line: usize, got: usize },
+    
+    /// The input ended before all of a case's boxes. Holds the line
+    /// number of the last line, the number of boxes, and how many of
+    /// them were read.
+    MissingBoxes { line: usize, expected: usize, got: usize },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::InvalidInteger { line, token } => {
+                write!(f, "Error! Line {}: `{}` is not an unsigned integer.", line, token)
+            },
+            ParseError::WrongFieldCount { line, got } => write!(
+                f,
+                "Error! Line {}: expected the number of boxes and dimensions, got {} fields.",
+                line, got
+            ),
+            ParseError::WrongDimensionCount { line, expected, got } => {
+                write!(f, "Error! Line {}: expected {} dimensions, got {}.", line, expected, got)
+            },
+            ParseError::NoDimensions { line } => {
+                write!(f, "Error! Line {}: a box must have at least one dimension.", line)
+            },
+            ParseError::TooManyDimensions { line, got } => write!(
+                f,
+                "Error! Line {}: {} dimensions is more than the {} allowed.",
+                line, got, MAX_DIMENSIONS
+            ),
+            ParseError::MissingBoxes { line, expected, got } => {
+                write!(f, "Error! Line {}: the input ended after {} of {} boxes.", line, got, expected)
+            },
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// `CaseParser` struct. Reads the cases of the input from a reader,
+/// one at a time, keeping count of the lines read so that errors can
+/// name their line. Iterating over it yields each case, as a `Boxes`
+/// instance or a `ParseError`, until the input ends, or reading
+/// fails.
+pub struct CaseParser<R> {
+    /// The reader the cases are read from.
+    buf: R,
+    
+    /// The number of lines read so far.
+    line: usize,
+    
+    /// The last line read, reused for every line.
+    input: String,
+}
+
+impl<R: io::BufRead> CaseParser<R> {
+    /// Return a new `CaseParser` instance reading cases from `buf`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rust::parser::{CaseParser, ParseError};
+    ///
+    /// let mut parser = CaseParser::new("2 1\n1\n2\n1 2\n3\n".as_bytes());
+    ///
+    /// assert_eq!(parser.next_case().unwrap().unwrap().unwrap().solve().boxes, vec![1, 2]);
+    /// assert_eq!(
+    ///     parser.next_case().unwrap(),
+    ///     Some(Err(ParseError::WrongDimensionCount { line: 5, expected: 2, got: 1 }))
+    /// );
+    /// assert_eq!(parser.next_case().unwrap(), None);
+    /// ```
+    pub fn new(buf: R) -> CaseParser<R> {
+        CaseParser {
+            buf,
+            line: 0,
+            input: String::new(),
+        }
+    }
+    
+    /// Return the number of lines read so far.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+    
+    /// Read the next case. All of the case's lines are read even if
+    /// one of them is malformed, so that the next case starts where
+    /// it should, and the first error is returned. Blank lines before
+    /// the header are skipped. Returns `None` if the input ends
+    /// before the header, and an `Err()` if reading fails.
+    pub fn next_case(&mut self) -> io::Result<Option<Result<Boxes, ParseError>>> {
+        loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            if !self.input.trim().is_empty() {
+                break;
+            }
+        }
+        
+        let header = match CaseParser::<R>::parse_integers(&self.input, self.line) {
+            Ok(header) => header,
+            Err(error) => return Ok(Some(Err(error))),
+        };
+        if header.len() != 2 {
+            return Ok(Some(Err(ParseError::WrongFieldCount { line: self.line, got: header.len() })));
+        }
+        
+        let (num_boxes, num_dimensions) = (header[0], header[1]);
+        // The number of boxes comes from the input, so it may be too
+        // big to allocate up front.
+        let mut boxes = Vec::new();
+        let mut first_error = None;
+        if num_dimensions == 0 {
+            first_error = Some(ParseError::NoDimensions { line: self.line });
+        }
+        else if num_dimensions > MAX_DIMENSIONS {
+            first_error = Some(ParseError::TooManyDimensions { line: self.line, got: num_dimensions });
+        }
+        for index in 1..=num_boxes {
+            if !self.read_line()? {
+                return Ok(Some(Err(ParseError::MissingBoxes { line: self.line, expected: num_boxes, got: index - 1 })));
+            }
+            
+            match self.parse_box(index, num_dimensions) {
+                Ok(box_) => boxes.push(box_),
+                Err(error) => {
+                    first_error.get_or_insert(error);
+                },
+            }
+        }
+        
+        match first_error {
+            Some(error) => Ok(Some(Err(error))),
+            None => Ok(Some(Ok(Boxes::new(num_dimensions, boxes)))),
+        }
+    }
+    
+    //
+    // Private methods.
+    //
+    
+    /// Read the next line of the input into `input`, counting it.
+    /// Returns `false` if the input has ended, and an `Err()` if
+    /// reading fails.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.input.clear();
+        
+        if self.buf.read_line(&mut self.input)? == 0 {
+            return Ok(false);
+        }
+        self.line += 1;
+        
+        Ok(true)
+    }
+    
+    /// Parse the last line read as the box at 1-based place `index`
+    /// of `dimensions` dimensions.
+    fn parse_box(&self, index: usize, dimensions: usize) -> Result<Box_, ParseError> {
+        let box_ = CaseParser::<R>::parse_integers(&self.input, self.line)?;
+        
+        if box_.len() == dimensions {
+            Ok(Box_::new(index, box_))
+        }
+        else {
+            Err(ParseError::WrongDimensionCount { line: self.line, expected: dimensions, got: box_.len() })
+        }
+    }
+    
+    //
+    // Private functions.
+    //
+    
+    /// Parse every whitespace separated token of `input`, line `line`
+    /// of the input, as an unsigned integer. Returns an `Err()` naming
+    /// the first token that isn't one.
+    fn parse_integers(input: &str, line: usize) -> Result<Vec<usize>, ParseError> {
+        input
+            .split_whitespace()
+            .map(|token| {
+                token.parse().map_err(|_| ParseError::InvalidInteger { line, token: token.to_string() })
+            })
+            .collect()
+    }
+}
+
+impl<R: io::BufRead> Iterator for CaseParser<R> {
+    type Item = io::Result<Result<Boxes, ParseError>>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_case().transpose()
+    }
+}
+
+//
+// Tests.
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    
+    #[test]
+    fn parser_next_case() {
+        let mut parser = CaseParser::new("2 3\n1 2 3\n4 5 6\n1 x\n2 2\n1 2\n1 2 3\n1 2 3\n".as_bytes());
+        
+        let boxes = parser.next_case().unwrap().unwrap().unwrap();
+        assert_eq!(boxes.solve().boxes, vec![1, 2]);
+        assert_eq!(parser.line(), 3);
+        
+        assert_eq!(
+            parser.next_case().unwrap(),
+            Some(Err(ParseError::InvalidInteger { line: 4, token: "x".to_string() }))
+        );
+        
+        // The rest of a malformed case is still read.
+        assert_eq!(
+            parser.next_case().unwrap(),
+            Some(Err(ParseError::WrongDimensionCount { line: 7, expected: 2, got: 3 }))
+        );
+        assert_eq!(parser.next_case().unwrap(), Some(Err(ParseError::WrongFieldCount { line: 8, got: 3 })));
+        assert_eq!(parser.next_case().unwrap(), None);
+    }
+    
+    #[test]
+    fn parser_at_the_end_of_the_input() {
+        // Trailing blank lines end the input as well.
+        let mut parser = CaseParser::new("1 2\n3 4\n\n  \n".as_bytes());
+        assert!(parser.next_case().unwrap().unwrap().is_ok());
+        assert_eq!(parser.next_case().unwrap(), None);
+        assert_eq!(parser.line(), 4);
+        
+        // Ending in the middle of a case is an error, and then the
+        // end of the input.
+        let mut parser = CaseParser::new("1 1\n1\n3 2\n3 4\n".as_bytes());
+        assert_eq!(parser.by_ref().count(), 2);
+        assert_eq!(parser.line(), 4);
+        
+        let mut parser = CaseParser::new("3 2\n3 4\n".as_bytes());
+        let error = parser.next().unwrap().unwrap().unwrap_err();
+        assert_eq!(error, ParseError::MissingBoxes { line: 2, expected: 3, got: 1 });
+        assert_eq!(error.to_string(), "Error! Line 2: the input ended after 1 of 3 boxes.");
+        assert!(parser.next().is_none());
+        
+        // A huge number of boxes is read as any other.
+        let mut parser = CaseParser::new("99999999999999999 2\n1 2\n".as_bytes());
+        assert_eq!(
+            parser.next_case().unwrap(),
+            Some(Err(ParseError::MissingBoxes { line: 2, expected: 99999999999999999, got: 1 }))
+        );
+    }
+    
+    #[test]
+    fn parser_no_dimensions() {
+        let mut parser = CaseParser::new("3 0\n\n\n\n1 1\n1\n".as_bytes());
+        
+        // The case's lines are still read, so the next case is fine.
+        let error = parser.next_case().unwrap().unwrap().unwrap_err();
+        assert_eq!(error, ParseError::NoDimensions { line: 1 });
+        assert_eq!(error.to_string(), "Error! Line 1: a box must have at least one dimension.");
+        assert!(parser.next_case().unwrap().unwrap().is_ok());
+        assert_eq!(parser.line(), 6);
+    }
+    
+    #[test]
+    fn parser_too_many_dimensions() {
+        let input = format!("1 {}\n{}\n1 1\n1\n", MAX_DIMENSIONS + 1, vec!["1"; MAX_DIMENSIONS + 1].join(" "));
+        let mut parser = CaseParser::new(input.as_bytes());
+        
+        // The case's boxes are still read, so the next case is fine.
+        let error = parser.next_case().unwrap().unwrap().unwrap_err();
+        assert_eq!(error, ParseError::TooManyDimensions { line: 1, got: MAX_DIMENSIONS + 1 });
+        assert_eq!(error.to_string(), "Error! Line 1: 1001 dimensions is more than the 1000 allowed.");
+        assert!(parser.next_case().unwrap().unwrap().is_ok());
+    }
+}